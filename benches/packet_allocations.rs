@@ -0,0 +1,142 @@
+//! Counts heap allocations and allocated bytes per video frame on the way
+//! from the encoder to the decoder, without the browser parts on either end.
+//!
+//! Run with `cargo bench --bench packet_allocations`.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use live_lesson::{
+    constants::{DATA_CHANNEL_MAX_MESSAGE, VIDEO_FRAGMENT_SIZE, VIDEO_FRAGMENT_TIMEOUT_MS, VIDEO_JITTER_BUFFER_SIZE, VIDEO_JITTER_WAIT_MS, VIDEO_MAX_FRAME_BYTES},
+    models::{
+        fragment::Reassembler,
+        jitter_buffer::{JitterBuffer, Release},
+        packet::{PacketData, VideoPacket},
+    },
+};
+
+const FRAMES: u64 = 1000;
+// a camera delta frame, a camera key frame, a screen key frame
+const FRAME_SIZES: [usize; 3] = [4_000, 40_000, 400_000];
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+// SAFETY: only counts, the allocating is left to the system allocator.
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+#[derive(Default)]
+struct Counts {
+    allocations: usize,
+    bytes: usize,
+}
+
+impl Counts {
+    fn measure<T>(&mut self, f: impl FnOnce() -> T) -> T {
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+        let bytes = BYTES.load(Ordering::Relaxed);
+        let result = f();
+        self.allocations += ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+        self.bytes += BYTES.load(Ordering::Relaxed) - bytes;
+        result
+    }
+
+    fn print(&self, stage: &str, frame_size: usize) {
+        println!(
+            "{:>8} B  {:<8} {:>6.1} allocations {:>10.0} B per frame",
+            frame_size,
+            stage,
+            self.allocations as f64 / FRAMES as f64,
+            self.bytes as f64 / FRAMES as f64,
+        );
+    }
+}
+
+/// The packet `VideoPacket::new` makes of an encoded chunk, the copy out of
+/// the chunk is a fill.
+fn encoded(frame_size: usize, sequence_number: u64) -> VideoPacket {
+    let chunk_type = if sequence_number == 0 { "key" } else { "delta" }.to_owned();
+    let timestamp = sequence_number as f64 * 33_333.0;
+    VideoPacket::from_parts(frame_size, chunk_type, timestamp, 33_333.0, sequence_number, |buffer| buffer.fill(7))
+}
+
+fn run(frame_size: usize) {
+    let mut encode = Counts::default();
+    let mut send = Counts::default();
+    let mut receive = Counts::default();
+    let mut fragments = Reassembler::new(VIDEO_FRAGMENT_SIZE, VIDEO_MAX_FRAME_BYTES, VIDEO_FRAGMENT_TIMEOUT_MS);
+    let mut cache = JitterBuffer::new(VIDEO_JITTER_BUFFER_SIZE, VIDEO_JITTER_WAIT_MS);
+    let mut decoded = 0;
+
+    for sequence_number in 0..FRAMES {
+        let now = sequence_number as f64 * 33.0;
+        let packets = encode.measure(|| encoded(frame_size, sequence_number).into_fragments(VIDEO_FRAGMENT_SIZE));
+        for packet in packets {
+            let message = send.measure(|| serde_json::to_vec(&packet).unwrap());
+            receive.measure(|| {
+                // the same steps as `Video::decode_break` up to the decoder
+                let packet: VideoPacket = serde_json::from_slice(&message).unwrap();
+                let packet = match packet.fragment {
+                    Some(fragment) => {
+                        let Some(data) = fragments.push(packet.sequence_number, fragment, &packet.data, now) else {
+                            return;
+                        };
+                        VideoPacket { data: PacketData::Owned(data), fragment: None, ..packet }
+                    },
+                    None => packet,
+                };
+                let is_key = packet.chunk_type == "key";
+                cache.push(packet.sequence_number, is_key, packet, now);
+                while let Some(release) = cache.pop(now) {
+                    if let Release::Frame(packet) = release {
+                        decoded += packet.data.len();
+                    }
+                }
+            });
+        }
+    }
+    assert_eq!(decoded, frame_size * FRAMES as usize);
+
+    encode.print("encode", frame_size);
+    send.print("send", frame_size);
+    receive.print("receive", frame_size);
+}
+
+/// A fragment of bytes that serialize the longest still fits in one message.
+fn check_message_size() {
+    let packet = VideoPacket::from_parts(VIDEO_FRAGMENT_SIZE * 2, "key".to_owned(), 0.0, 0.0, u64::MAX, |buffer| buffer.fill(255));
+    for fragment in packet.into_fragments(VIDEO_FRAGMENT_SIZE) {
+        let message = serde_json::to_vec(&fragment).unwrap();
+        assert!(message.len() <= DATA_CHANNEL_MAX_MESSAGE, "{} B message", message.len());
+    }
+}
+
+fn main() {
+    check_message_size();
+    for frame_size in FRAME_SIZES {
+        run(frame_size);
+    }
+}
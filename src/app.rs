@@ -3,6 +3,7 @@ use yew_router::prelude::*;
 use crate::components::document::document::Document;
 use crate::components::home::Home;
 use crate::components::multi::multi::Multi;
+use crate::components::replay::replay::Replay;
 use crate::components::table::table::Table;
 
 #[derive(Clone, Routable, PartialEq, Eq)]
@@ -15,6 +16,8 @@ pub enum Route {
     Table,
     #[at("/multi")]
     Multi,
+    #[at("/replay")]
+    Replay,
 }
 
 #[function_component(App)]
@@ -44,5 +47,8 @@ fn switch(routes: Route) -> Html {
         Route::Multi => {
             html! { <Multi /> }
         }
+        Route::Replay => {
+            html! { <Replay /> }
+        }
     }
 }
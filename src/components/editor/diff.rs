@@ -0,0 +1,64 @@
+use yew::prelude::*;
+
+use crate::models::line_diff::{diff_lines, side_by_side, ChangeKind, LineChange};
+
+#[derive(PartialEq, Properties)]
+pub struct DiffViewProps {
+    pub original: String,
+    pub modified: String,
+    #[prop_or_default]
+    pub original_title: String,
+    #[prop_or_default]
+    pub modified_title: String,
+}
+
+fn render_cell(line: Option<&LineChange>, line_number: Option<usize>) -> Html {
+    match line {
+        Some(line) => {
+            let class = match line.kind {
+                ChangeKind::Same => "diff-same",
+                ChangeKind::Removed => "diff-removed",
+                ChangeKind::Added => "diff-added",
+            };
+            let number = line_number.map(|number| number.to_string()).unwrap_or_default();
+            html! {
+                <>
+                    <td class="diff-line-number">{ number }</td>
+                    <td class={ class }><pre>{ line.text.clone() }</pre></td>
+                </>
+            }
+        },
+        None => html! {
+            <>
+                <td class="diff-line-number"></td>
+                <td class="diff-empty"></td>
+            </>
+        },
+    }
+}
+
+/// Side-by-side line diff, recomputed whenever one of the texts changes.
+#[function_component(DiffView)]
+pub fn diff_view(props: &DiffViewProps) -> Html {
+    let rows = use_memo(
+        (props.original.clone(), props.modified.clone()),
+        |(original, modified)| side_by_side(diff_lines(original, modified)),
+    );
+
+    html! {
+        <div class="diff-view document">
+            <table>
+                <tr>
+                    <th colspan="2">{ props.original_title.clone() }</th>
+                    <th colspan="2">{ props.modified_title.clone() }</th>
+                </tr>
+                { for rows.iter().map(|row| html! {
+                    <tr>
+                        { render_cell(row.old.as_ref(), row.old.as_ref().and_then(|line| line.old_line)) }
+                        { render_cell(row.new.as_ref(), row.new.as_ref().and_then(|line| line.new_line)) }
+                    </tr>
+                }) }
+            </table>
+        </div>
+    }
+}
@@ -1,20 +1,38 @@
+use js_sys::{Array, Function, Object, Reflect};
 use monaco::{
     api::{CodeEditorOptions, TextModel},
-    sys::editor::{BuiltinTheme, IStandaloneCodeEditor},
+    sys::editor::{BuiltinTheme, IStandaloneCodeEditor, IStandaloneEditorConstructionOptions, ITextModel},
     yew::{CodeEditor, CodeEditorLink},
 };
-use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{closure::Closure, JsValue};
 use yew::prelude::*;
 
 use wasm_bindgen::JsCast;
 
+use crate::models::{editor_settings::EditorSettings, history::HistoryAction, presence::{EditorCursor, RemoteCursor, SelectionRange}, review::ReviewThread};
 
-fn get_options() -> CodeEditorOptions {
-    CodeEditorOptions::default()
-        .with_language("rust".to_owned())
+
+// Options that follow the host's settings, also used to update a running editor.
+fn settings_options(settings: &EditorSettings, read_only: bool) -> Object {
+    let minimap = js_object(&[("enabled", settings.minimap.into())]);
+    js_object(&[
+        ("tabSize", settings.tab_size.into()),
+        ("detectIndentation", false.into()),
+        ("fontSize", settings.font_size.into()),
+        ("minimap", minimap.into()),
+        ("readOnly", read_only.into()),
+    ])
+}
+
+fn get_options(settings: &EditorSettings, read_only: bool) -> IStandaloneEditorConstructionOptions {
+    let options = CodeEditorOptions::default()
+        .with_language(settings.language.clone())
         // .with_value(content)
         .with_builtin_theme(BuiltinTheme::VsDark)
         .with_automatic_layout(true)
+        .to_sys_options();
+    Object::assign(options.unchecked_ref(), &settings_options(settings, read_only));
+    options
 }
 
 #[derive(PartialEq, Properties)]
@@ -22,6 +40,9 @@ pub struct CustomEditorProps {
     #[prop_or_default]
     pub on_editor_created: Callback<CodeEditorLink>,
     pub text_model: TextModel,
+    pub settings: EditorSettings,
+    #[prop_or_default]
+    pub read_only: bool,
 }
 
 #[derive(PartialEq, Properties)]
@@ -30,8 +51,33 @@ pub struct EditorWrapperProps {
     pub on_cb: Callback<String>,
     pub text_model: TextModel,
     pub is_write: bool,
+    pub settings: EditorSettings,
+    #[prop_or_default]
+    pub on_cursor: Callback<EditorCursor>,
+    #[prop_or_default]
+    pub remote_cursor: Option<RemoteCursor>,
+    #[prop_or_default]
+    pub read_only: bool,
+    /// First visible line, reported while scrolling.
+    #[prop_or_default]
+    pub on_scroll: Callback<u32>,
+    /// Line to keep at the top of the viewport.
+    #[prop_or_default]
+    pub scroll_line: Option<u32>,
+    /// Review threads of the shown file.
+    #[prop_or_default]
+    pub review: Vec<ReviewThread>,
+    /// Takes over undo and redo, so that they only revert the local user's edits.
+    #[prop_or_default]
+    pub on_history: Option<Callback<HistoryAction>>,
 }
 
+// monaco KeyMod and KeyCode values
+const KEY_MOD_CTRL_CMD: u32 = 2048;
+const KEY_MOD_SHIFT: u32 = 1024;
+const KEY_CODE_Y: u32 = 55;
+const KEY_CODE_Z: u32 = 56;
+
 ///
 /// This is really just a helper component, so we can pass in props easier.
 /// It makes it much easier to use, as we can pass in what we need, and it
@@ -42,10 +88,12 @@ pub fn custom_editor(props: &CustomEditorProps) -> Html {
     let CustomEditorProps {
         on_editor_created,
         text_model,
+        settings,
+        read_only,
     } = props;
 
     html! {
-        <CodeEditor classes={"full-height"} options={ get_options().to_sys_options() } {on_editor_created} model={text_model.clone()} />
+        <CodeEditor classes={"full-height"} options={ get_options(settings, *read_only) } {on_editor_created} model={text_model.clone()} />
     }
 }
 
@@ -54,10 +102,15 @@ pub fn editor_wrapper(props: &EditorWrapperProps) -> Html {
     let text_model = use_state_eq(|| props.text_model.clone());
     let is_write = props.is_write;
     if is_write {
-        text_model.set_value(&props.text_model.get_value());
+        // an edit instead of set_value keeps the cursor where it was
+        let model: &ITextModel = (*text_model).as_ref();
+        replace_content(model.as_ref(), &text_model.get_value(), &props.text_model.get_value());
     }
 
     let on_cb = &props.on_cb;
+    let editor_link = use_mut_ref(|| Option::<CodeEditorLink>::None);
+    let decorations = use_mut_ref(Array::new);
+    let review_decorations = use_mut_ref(Array::new);
 
     // Here we setup the Callback for when the editor is created.
     let on_editor_created = {
@@ -75,23 +128,293 @@ pub fn editor_wrapper(props: &EditorWrapperProps) -> Html {
             })
         };
 
+        let cursor_closure = {
+            let on_cursor = props.on_cursor.clone();
+            Closure::<dyn Fn(JsValue)>::new(move |event: JsValue| {
+                if let Some(cursor) = read_cursor(&event) {
+                    on_cursor.emit(cursor);
+                }
+            })
+        };
+
+        let scroll_closure = {
+            let on_scroll = props.on_scroll.clone();
+            let editor_link = editor_link.clone();
+            Closure::<dyn Fn(JsValue)>::new(move |_event: JsValue| {
+                if let Some(link) = editor_link.borrow().as_ref() {
+                    let line = link.with_editor(|editor| {
+                        let raw_editor: &IStandaloneCodeEditor = editor.as_ref();
+                        read_top_line(raw_editor.as_ref())
+                    });
+                    if let Some(line) = line.flatten() {
+                        on_scroll.emit(line);
+                    }
+                }
+            })
+        };
+
+        let history_closures = props.on_history.clone().map(|on_history| {
+            let history_closure = |action: HistoryAction| {
+                let on_history = on_history.clone();
+                Closure::<dyn Fn()>::new(move || on_history.emit(action))
+            };
+            [
+                (KEY_MOD_CTRL_CMD | KEY_CODE_Z, history_closure(HistoryAction::Undo)),
+                (KEY_MOD_CTRL_CMD | KEY_MOD_SHIFT | KEY_CODE_Z, history_closure(HistoryAction::Redo)),
+                (KEY_MOD_CTRL_CMD | KEY_CODE_Y, history_closure(HistoryAction::Redo)),
+            ]
+        });
+
+        let editor_link = editor_link.clone();
         // Here we define our callback, we use use_callback as we want to re-render when dependencies change.
         // See https://yew.rs/docs/concepts/function-components/state#general-view-of-how-to-store-state
         use_callback(
             text_model,
-            move |editor_link: CodeEditorLink, _text_model| {
-                editor_link.with_editor(|editor| {
+            move |link: CodeEditorLink, _text_model| {
+                link.with_editor(|editor| {
                     let raw_editor: &IStandaloneCodeEditor = editor.as_ref();
-                    raw_editor.on_key_up(js_closure.as_ref().unchecked_ref());          
+                    raw_editor.on_key_up(js_closure.as_ref().unchecked_ref());
+                    raw_editor.on_did_change_cursor_selection(cursor_closure.as_ref().unchecked_ref());
+                    call_method(raw_editor.as_ref(), "onDidScrollChange", &Array::of1(scroll_closure.as_ref()));
+                    history_closures.iter().flatten().for_each(|(keybinding, closure)| {
+                        call_method(raw_editor.as_ref(), "addCommand", &Array::of2(&(*keybinding).into(), closure.as_ref()));
+                    });
                 });
+                *editor_link.borrow_mut() = Some(link);
             },
-            
+
         )
     };
+
+    use_effect_with((props.settings.clone(), props.read_only), {
+        let editor_link = editor_link.clone();
+        let text_model = text_model.clone();
+        move |(settings, read_only): &(EditorSettings, bool)| {
+            monaco::sys::editor::set_model_language((*text_model).as_ref(), &settings.language);
+            if let Some(link) = editor_link.borrow().as_ref() {
+                link.with_editor(|editor| {
+                    let raw_editor: &IStandaloneCodeEditor = editor.as_ref();
+                    raw_editor.update_options(settings_options(settings, *read_only).unchecked_ref());
+                });
+            }
+        }
+    });
+
+    use_effect_with(props.scroll_line, {
+        let editor_link = editor_link.clone();
+        move |scroll_line: &Option<u32>| {
+            if let (Some(line), Some(link)) = (scroll_line, editor_link.borrow().as_ref()) {
+                link.with_editor(|editor| {
+                    let raw_editor: &IStandaloneCodeEditor = editor.as_ref();
+                    scroll_to_line(raw_editor.as_ref(), *line);
+                });
+            }
+        }
+    });
+
+    use_effect_with(props.remote_cursor.clone(), {
+        let editor_link = editor_link.clone();
+        move |remote_cursor: &Option<RemoteCursor>| {
+            if let Some(link) = editor_link.borrow().as_ref() {
+                link.with_editor(|editor| {
+                    let raw_editor: &IStandaloneCodeEditor = editor.as_ref();
+                    let new_decorations = remote_cursor
+                        .as_ref()
+                        .map_or_else(Array::new, create_cursor_decorations);
+                    let ids = raw_editor.delta_decorations(&decorations.borrow(), &new_decorations);
+                    *decorations.borrow_mut() = ids;
+                });
+            }
+        }
+    });
+
+    use_effect_with(props.review.clone(), {
+        let editor_link = editor_link.clone();
+        move |review: &Vec<ReviewThread>| {
+            if let Some(link) = editor_link.borrow().as_ref() {
+                link.with_editor(|editor| {
+                    let raw_editor: &IStandaloneCodeEditor = editor.as_ref();
+                    let new_decorations = create_review_decorations(review);
+                    let ids = raw_editor.delta_decorations(&review_decorations.borrow(), &new_decorations);
+                    *review_decorations.borrow_mut() = ids;
+                });
+            }
+        }
+    });
+
     html! {
         <div class="code-wrapper document">
-            <CustomEditor {on_editor_created} text_model={(*text_model).clone()} />
+            <CustomEditor {on_editor_created} text_model={(*text_model).clone()} settings={props.settings.clone()} read_only={props.read_only} />
         </div>
     }
 }
 
+// Calls an editor method that has no typed binding.
+fn call_method(target: &JsValue, name: &str, args: &Array) -> Option<JsValue> {
+    Reflect::get(target, &JsValue::from_str(name))
+        .ok()?
+        .dyn_into::<Function>()
+        .ok()?
+        .apply(target, args)
+        .ok()
+}
+
+// Replaces the changed middle part of the model, found by trimming the common prefix and suffix.
+fn replace_content(model: &JsValue, old: &str, new: &str) {
+    if old == new {
+        return;
+    }
+    // monaco offsets count UTF-16 code units
+    let old_units: Vec<u16> = old.encode_utf16().collect();
+    let new_units: Vec<u16> = new.encode_utf16().collect();
+    let is_high_surrogate = |unit: u16| (0xD800..0xDC00).contains(&unit);
+    let is_low_surrogate = |unit: u16| (0xDC00..0xE000).contains(&unit);
+    let mut prefix = old_units
+        .iter()
+        .zip(new_units.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    // don't split a surrogate pair
+    if prefix > 0 && is_high_surrogate(new_units[prefix - 1]) {
+        prefix -= 1;
+    }
+    let mut suffix = old_units[prefix..]
+        .iter()
+        .rev()
+        .zip(new_units[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    if suffix > 0 && is_low_surrogate(new_units[new_units.len() - suffix]) {
+        suffix -= 1;
+    }
+    let position_at = |offset: usize| call_method(model, "getPositionAt", &Array::of1(&(offset as u32).into()));
+    let (Some(start), Some(end)) = (position_at(prefix), position_at(old_units.len() - suffix)) else {
+        return;
+    };
+    let range = SelectionRange {
+        start_line: read_number(&start, "lineNumber").unwrap_or(1),
+        start_column: read_number(&start, "column").unwrap_or(1),
+        end_line: read_number(&end, "lineNumber").unwrap_or(1),
+        end_column: read_number(&end, "column").unwrap_or(1),
+    };
+    let text = String::from_utf16_lossy(&new_units[prefix..new_units.len() - suffix]);
+    let edit = js_object(&[("range", js_range(&range).into()), ("text", JsValue::from_str(&text))]);
+    call_method(model, "applyEdits", &Array::of1(&edit));
+}
+
+fn read_top_line(editor: &JsValue) -> Option<u32> {
+    let ranges = call_method(editor, "getVisibleRanges", &Array::new())?;
+    read_number(&Array::from(&ranges).get(0), "startLineNumber")
+}
+
+fn scroll_to_line(editor: &JsValue, line: u32) {
+    if let Some(top) = call_method(editor, "getTopForLineNumber", &Array::of1(&line.into())) {
+        call_method(editor, "setScrollTop", &Array::of1(&top));
+    }
+}
+
+fn read_number(value: &JsValue, key: &str) -> Option<u32> {
+    Reflect::get(value, &JsValue::from_str(key))
+        .ok()?
+        .as_f64()
+        .map(|number| number as u32)
+}
+
+fn read_selection(value: &JsValue) -> Option<SelectionRange> {
+    Some(SelectionRange {
+        start_line: read_number(value, "startLineNumber")?,
+        start_column: read_number(value, "startColumn")?,
+        end_line: read_number(value, "endLineNumber")?,
+        end_column: read_number(value, "endColumn")?,
+    })
+}
+
+// Reads monaco's ICursorSelectionChangedEvent.
+fn read_cursor(event: &JsValue) -> Option<EditorCursor> {
+    let selection = Reflect::get(event, &JsValue::from_str("selection")).ok()?;
+    let mut selections = vec![read_selection(&selection)?];
+    if let Ok(secondary) = Reflect::get(event, &JsValue::from_str("secondarySelections")) {
+        if Array::is_array(&secondary) {
+            selections.extend(Array::from(&secondary).iter().filter_map(|value| read_selection(&value)));
+        }
+    }
+    selections.retain(|selection| !selection.is_empty());
+    Some(EditorCursor {
+        line: read_number(&selection, "positionLineNumber")?,
+        column: read_number(&selection, "positionColumn")?,
+        selections,
+    })
+}
+
+fn js_object(entries: &[(&str, JsValue)]) -> Object {
+    let object = Object::new();
+    entries.iter().for_each(|(key, value)| {
+        let _ = Reflect::set(&object, &JsValue::from_str(key), value);
+    });
+    object
+}
+
+fn js_range(range: &SelectionRange) -> Object {
+    js_object(&[
+        ("startLineNumber", range.start_line.into()),
+        ("startColumn", range.start_column.into()),
+        ("endLineNumber", range.end_line.into()),
+        ("endColumn", range.end_column.into()),
+    ])
+}
+
+fn create_decoration(range: &SelectionRange, options: Object) -> Object {
+    js_object(&[("range", js_range(range).into()), ("options", options.into())])
+}
+
+fn create_cursor_decorations(remote: &RemoteCursor) -> Array {
+    let hover_message = || js_object(&[("value", JsValue::from_str(&remote.name))]);
+    let decorations = Array::new();
+    remote.cursor.selections.iter().for_each(|selection| {
+        let options = js_object(&[
+            ("className", JsValue::from_str("remote-selection")),
+            ("hoverMessage", hover_message().into()),
+        ]);
+        decorations.push(&create_decoration(selection, options));
+    });
+    let label = js_object(&[
+        ("content", JsValue::from_str(&remote.name)),
+        ("inlineClassName", JsValue::from_str("remote-cursor-label")),
+    ]);
+    let options = js_object(&[
+        ("beforeContentClassName", JsValue::from_str("remote-cursor")),
+        ("hoverMessage", hover_message().into()),
+        ("after", label.into()),
+    ]);
+    let caret = SelectionRange::caret(remote.cursor.line, remote.cursor.column);
+    decorations.push(&create_decoration(&caret, options));
+    decorations
+}
+
+fn create_review_decorations(review: &[ReviewThread]) -> Array {
+    review
+        .iter()
+        .map(|thread| {
+            let hover_message = thread.messages
+                .iter()
+                .map(|message| {
+                    let value = format!("**{}**: {}", message.author.label(), message.text);
+                    JsValue::from(js_object(&[("value", JsValue::from_str(&value))]))
+                })
+                .collect::<Array>();
+            let options = js_object(&[
+                ("isWholeLine", true.into()),
+                ("className", JsValue::from_str("review-line")),
+                ("linesDecorationsClassName", JsValue::from_str("review-marker")),
+                ("hoverMessage", hover_message.into()),
+            ]);
+            let range = SelectionRange {
+                start_line: thread.start_line,
+                start_column: 1,
+                end_line: thread.end_line,
+                end_column: 1,
+            };
+            JsValue::from(create_decoration(&range, options))
+        })
+        .collect()
+}
@@ -1 +1,6 @@
-pub mod editor;
\ No newline at end of file
+pub mod editor;
+pub mod workspace;
+pub mod settings;
+pub mod diff;
+pub mod review;
+pub mod replay;
\ No newline at end of file
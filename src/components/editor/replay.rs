@@ -0,0 +1,182 @@
+use std::{cell::RefCell, rc::Rc};
+
+use gloo_timers::callback::Interval;
+use monaco::api::TextModel;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+use crate::{components::editor::{editor::EditorWrapper, workspace::WorkspaceBar}, models::{editor_settings::EditorSettings, replay::{LessonRecording, ReplayState}}, utils::dom::format_duration};
+
+const TICK_MS: u32 = 100;
+const SPEEDS: [f64; 4] = [1.0, 2.0, 4.0, 8.0];
+
+#[derive(PartialEq, Properties)]
+pub struct ReplayPlayerProps {
+    pub recording: Rc<RefCell<LessonRecording>>,
+    pub settings: EditorSettings,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+struct Playback {
+    position: f64,
+    is_playing: bool,
+    speed: f64,
+}
+
+enum PlaybackAction {
+    Tick(f64),
+    Seek(f64),
+    Toggle(f64),
+    SetSpeed(f64),
+}
+
+// Every action gets the current duration, the recording keeps growing during the lesson.
+impl Reducible for Playback {
+    type Action = PlaybackAction;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        let playback = match action {
+            PlaybackAction::Tick(duration) => {
+                let position = (self.position + f64::from(TICK_MS) * self.speed).min(duration);
+                Playback {
+                    position,
+                    is_playing: position < duration,
+                    ..*self
+                }
+            }
+            PlaybackAction::Seek(position) => Playback { position, ..*self },
+            PlaybackAction::Toggle(duration) => {
+                let is_at_end = self.position >= duration;
+                Playback {
+                    position: if !self.is_playing && is_at_end { 0.0 } else { self.position },
+                    is_playing: !self.is_playing,
+                    ..*self
+                }
+            }
+            PlaybackAction::SetSpeed(speed) => Playback { speed, ..*self },
+        };
+        playback.into()
+    }
+}
+
+/// Read-only playback of a lesson recording with a timeline and bookmarks.
+#[function_component(ReplayPlayer)]
+pub fn replay_player(props: &ReplayPlayerProps) -> Html {
+    let ReplayPlayerProps {
+        recording,
+        settings,
+    } = props;
+
+    let playback = use_reducer(|| Playback {
+        position: 0.0,
+        is_playing: false,
+        speed: SPEEDS[0],
+    });
+    let replay = use_mut_ref(|| ReplayState::new(&recording.borrow()));
+
+    use_effect_with(playback.is_playing, {
+        let dispatcher = playback.dispatcher();
+        let recording = recording.clone();
+        move |is_playing: &bool| {
+            let interval = is_playing.then(|| {
+                Interval::new(TICK_MS, move || {
+                    dispatcher.dispatch(PlaybackAction::Tick(recording.borrow().duration()));
+                })
+            });
+            move || drop(interval)
+        }
+    });
+
+    let duration = recording.borrow().duration();
+    replay.borrow_mut().seek(&recording.borrow(), playback.position);
+    let workspace = replay.borrow().workspace.clone();
+    let text_model = TextModel::create(workspace.active_content(), Some(&settings.language), None).unwrap();
+
+    let toggle_click = {
+        let dispatcher = playback.dispatcher();
+        Callback::from(move |_| dispatcher.dispatch(PlaybackAction::Toggle(duration)))
+    };
+    let on_seek = {
+        let dispatcher = playback.dispatcher();
+        Callback::from(move |e: InputEvent| {
+            let value = e
+                .target()
+                .expect("Event should have a target when dispatched")
+                .unchecked_into::<HtmlInputElement>()
+                .value_as_number();
+            if value.is_finite() {
+                dispatcher.dispatch(PlaybackAction::Seek(value));
+            }
+        })
+    };
+    let on_speed = {
+        let dispatcher = playback.dispatcher();
+        Callback::from(move |e: Event| {
+            let speed = e
+                .target()
+                .expect("Event should have a target when dispatched")
+                .unchecked_into::<HtmlSelectElement>()
+                .value()
+                .parse::<f64>();
+            if let Ok(speed) = speed {
+                dispatcher.dispatch(PlaybackAction::SetSpeed(speed));
+            }
+        })
+    };
+    let speed_options = SPEEDS
+        .iter()
+        .map(|speed| {
+            html! {
+                <option value={ speed.to_string() } selected={ *speed == playback.speed }>{ format!("{}×", speed) }</option>
+            }
+        })
+        .collect::<Html>();
+    let bookmarks = recording
+        .borrow()
+        .bookmarks
+        .iter()
+        .map(|bookmark| {
+            let onclick = {
+                let dispatcher = playback.dispatcher();
+                let at = bookmark.at;
+                Callback::from(move |_| dispatcher.dispatch(PlaybackAction::Seek(at)))
+            };
+            html! {
+                <li>
+                    <button { onclick }>{ format!("{} {}", format_duration(bookmark.at), bookmark.title) }</button>
+                </li>
+            }
+        })
+        .collect::<Html>();
+
+    html! {
+        <div class="replay document">
+            <div class="replay-controls">
+                <button onclick={ toggle_click }>{ if playback.is_playing { "⏸" } else { "▶" } }</button>
+                <input
+                    type="range"
+                    min="0"
+                    max={ duration.to_string() }
+                    step={ TICK_MS.to_string() }
+                    value={ playback.position.to_string() }
+                    oninput={ on_seek }
+                />
+                <span class="replay-time">{ format!("{} / {}", format_duration(playback.position), format_duration(duration)) }</span>
+                <select onchange={ on_speed }>{ speed_options }</select>
+            </div>
+            if !recording.borrow().bookmarks.is_empty() {
+                <ul class="replay-bookmarks">{ bookmarks }</ul>
+            }
+            <WorkspaceBar workspace={ workspace.clone() } />
+            <EditorWrapper
+                key={ workspace.active.clone() }
+                on_cb={ Callback::default() }
+                { text_model }
+                is_write={ true }
+                read_only={ true }
+                settings={ settings.clone() }
+            />
+        </div>
+    }
+}
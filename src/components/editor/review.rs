@@ -0,0 +1,65 @@
+use yew::prelude::*;
+
+use crate::{models::review::ReviewThread, utils::dom::format_time};
+
+#[derive(PartialEq, Properties)]
+pub struct ReviewPanelProps {
+    pub threads: Vec<ReviewThread>,
+    /// Thread id and the reply text.
+    #[prop_or_default]
+    pub on_reply: Callback<(String, String)>,
+}
+
+/// Review threads of the open file with their replies.
+#[function_component(ReviewPanel)]
+pub fn review_panel(props: &ReviewPanelProps) -> Html {
+    let ReviewPanelProps {
+        threads,
+        on_reply,
+    } = props;
+
+    if threads.is_empty() {
+        return html! {};
+    }
+
+    let render_thread = |thread: &ReviewThread| {
+        let reply_click = {
+            let on_reply = on_reply.clone();
+            let thread_id = thread.id.clone();
+            Callback::from(move |_| {
+                let text = web_sys::window()
+                    .and_then(|window| window.prompt_with_message("Ответ").ok().flatten())
+                    .map(|text| text.trim().to_owned())
+                    .filter(|text| !text.is_empty());
+                if let Some(text) = text {
+                    on_reply.emit((thread_id.clone(), text));
+                }
+            })
+        };
+        let messages = thread.messages
+            .iter()
+            .map(|message| {
+                html! {
+                    <li>
+                        <span class="review-author">{ message.author.label() }</span>
+                        <span class="review-time">{ format_time(message.created_at) }</span>
+                        <div class="review-text">{ message.text.clone() }</div>
+                    </li>
+                }
+            })
+            .collect::<Html>();
+        html! {
+            <div key={ thread.id.clone() } class="review-thread">
+                <div class="review-lines">{ thread.lines_label() }</div>
+                <ul>{ messages }</ul>
+                <button onclick={ reply_click }>{ "Ответить" }</button>
+            </div>
+        }
+    };
+
+    html! {
+        <div class="review-panel">
+            { threads.iter().map(render_thread).collect::<Html>() }
+        </div>
+    }
+}
@@ -0,0 +1,103 @@
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+use crate::{constants::EDITOR_LANGUAGES, models::editor_settings::EditorSettings};
+
+#[derive(PartialEq, Properties)]
+pub struct EditorSettingsBarProps {
+    pub settings: EditorSettings,
+    pub on_change: Callback<EditorSettings>,
+}
+
+fn input(event: &Event) -> HtmlInputElement {
+    event
+        .target()
+        .expect("Event should have a target when dispatched")
+        .unchecked_into::<HtmlInputElement>()
+}
+
+fn number(event: &Event) -> Option<u32> {
+    input(event)
+        .value()
+        .parse::<u32>()
+        .ok()
+        .filter(|value| *value > 0)
+}
+
+/// Controls for the host to pick the lesson language and editor look.
+#[function_component(EditorSettingsBar)]
+pub fn editor_settings_bar(props: &EditorSettingsBarProps) -> Html {
+    let EditorSettingsBarProps {
+        settings,
+        on_change,
+    } = props;
+
+    let on_language = {
+        let settings = settings.clone();
+        let on_change = on_change.clone();
+        Callback::from(move |e: Event| {
+            let language = e
+                .target()
+                .expect("Event should have a target when dispatched")
+                .unchecked_into::<HtmlSelectElement>()
+                .value();
+            let mut settings = settings.clone();
+            settings.set_language(language);
+            on_change.emit(settings);
+        })
+    };
+    let on_tab_size = {
+        let settings = settings.clone();
+        let on_change = on_change.clone();
+        Callback::from(move |e: Event| {
+            if let Some(tab_size) = number(&e) {
+                let mut settings = settings.clone();
+                settings.set_tab_size(tab_size);
+                on_change.emit(settings);
+            }
+        })
+    };
+    let on_font_size = {
+        let settings = settings.clone();
+        let on_change = on_change.clone();
+        Callback::from(move |e: Event| {
+            if let Some(font_size) = number(&e) {
+                let mut settings = settings.clone();
+                settings.set_font_size(font_size);
+                on_change.emit(settings);
+            }
+        })
+    };
+    let on_minimap = {
+        let settings = settings.clone();
+        let on_change = on_change.clone();
+        Callback::from(move |e: Event| {
+            let mut settings = settings.clone();
+            settings.set_minimap(input(&e).checked());
+            on_change.emit(settings);
+        })
+    };
+
+    html! {
+        <div class="editor-settings">
+            <select onchange={ on_language }>
+                { for EDITOR_LANGUAGES.iter().map(|(id, name)| html! {
+                    <option value={ *id } selected={ settings.language == *id }>{ *name }</option>
+                }) }
+            </select>
+            <label>
+                { "Отступ" }
+                <input type="number" min="1" max="8" value={ settings.tab_size.to_string() } onchange={ on_tab_size } />
+            </label>
+            <label>
+                { "Шрифт" }
+                <input type="number" min="8" max="32" value={ settings.font_size.to_string() } onchange={ on_font_size } />
+            </label>
+            <label>
+                <input type="checkbox" checked={ settings.minimap } onchange={ on_minimap } />
+                { "Миникарта" }
+            </label>
+        </div>
+    }
+}
@@ -0,0 +1,160 @@
+use yew::prelude::*;
+
+use crate::models::workspace::{Workspace, WorkspaceAction, WorkspaceFile};
+
+#[derive(PartialEq, Properties)]
+pub struct WorkspaceBarProps {
+    pub workspace: Workspace,
+    #[prop_or_default]
+    pub on_action: Callback<WorkspaceAction>,
+    #[prop_or_default]
+    pub editable: bool,
+}
+
+fn ask_path(message: &str, default: &str) -> Option<String> {
+    web_sys::window()?
+        .prompt_with_message_and_default(message, default)
+        .ok()
+        .flatten()
+        .map(|path| path.trim().to_owned())
+        .filter(|path| !path.is_empty())
+}
+
+fn indent(depth: usize) -> String {
+    format!("padding-left: {}em", depth)
+}
+
+fn render_tree(workspace: &Workspace, on_action: &Callback<WorkspaceAction>) -> Html {
+    let mut shown_folders: Vec<&str> = Vec::new();
+    workspace
+        .sorted_files()
+        .into_iter()
+        .map(|file: &WorkspaceFile| {
+            let mut folders: Vec<&str> = file.path.split('/').collect();
+            folders.pop();
+            let common = shown_folders
+                .iter()
+                .zip(folders.iter())
+                .take_while(|(shown, folder)| shown == folder)
+                .count();
+            let folder_rows = folders[common..]
+                .iter()
+                .enumerate()
+                .map(|(index, folder)| {
+                    html! {
+                        <li class="workspace-folder" style={ indent(common + index) }>{ folder }</li>
+                    }
+                })
+                .collect::<Html>();
+            shown_folders = folders;
+
+            let onclick = {
+                let on_action = on_action.clone();
+                let id = file.id.clone();
+                Callback::from(move |_| on_action.emit(WorkspaceAction::Open { id: id.clone() }))
+            };
+            let class = if file.id == workspace.active { "workspace-file active" } else { "workspace-file" };
+            html! {
+                <>
+                    { folder_rows }
+                    <li { class } style={ indent(file.depth()) } { onclick }>{ file.name() }</li>
+                </>
+            }
+        })
+        .collect::<Html>()
+}
+
+/// Tabs and file tree of a workspace. Without `editable` only switching files is allowed.
+#[function_component(WorkspaceBar)]
+pub fn workspace_bar(props: &WorkspaceBarProps) -> Html {
+    let WorkspaceBarProps {
+        workspace,
+        on_action,
+        editable,
+    } = props;
+
+    let tabs = workspace
+        .tabs
+        .iter()
+        .filter_map(|id| workspace.file(id))
+        .map(|file| {
+            let onclick = {
+                let on_action = on_action.clone();
+                let id = file.id.clone();
+                Callback::from(move |_| on_action.emit(WorkspaceAction::Open { id: id.clone() }))
+            };
+            let on_close = {
+                let on_action = on_action.clone();
+                let id = file.id.clone();
+                Callback::from(move |e: MouseEvent| {
+                    e.stop_propagation();
+                    on_action.emit(WorkspaceAction::Close { id: id.clone() });
+                })
+            };
+            let class = if file.id == workspace.active { "workspace-tab active" } else { "workspace-tab" };
+            html! {
+                <span key={ file.id.clone() } { class } title={ file.path.clone() } { onclick }>
+                    { file.name() }
+                    if *editable && workspace.tabs.len() > 1 {
+                        <span class="workspace-tab-close" onclick={ on_close }>{ "×" }</span>
+                    }
+                </span>
+            }
+        })
+        .collect::<Html>();
+
+    let create_click = {
+        let on_action = on_action.clone();
+        Callback::from(move |_| {
+            if let Some(path) = ask_path("Имя нового файла", "") {
+                on_action.emit(WorkspaceAction::create(path));
+            }
+        })
+    };
+    let rename_click = {
+        let on_action = on_action.clone();
+        let active = workspace.active_file().cloned();
+        Callback::from(move |_| {
+            if let Some(file) = &active {
+                if let Some(path) = ask_path("Новое имя файла", &file.path) {
+                    on_action.emit(WorkspaceAction::Rename { id: file.id.clone(), path });
+                }
+            }
+        })
+    };
+    let delete_click = {
+        let on_action = on_action.clone();
+        let active = workspace.active_file().cloned();
+        Callback::from(move |_| {
+            if let Some(file) = &active {
+                let confirmed = web_sys::window()
+                    .and_then(|window| window.confirm_with_message(&format!("Удалить {}?", file.path)).ok())
+                    .unwrap_or(false);
+                if confirmed {
+                    on_action.emit(WorkspaceAction::Delete { id: file.id.clone() });
+                }
+            }
+        })
+    };
+
+    html! {
+        <div class="workspace">
+            <div class="workspace-tabs">
+                { tabs }
+                if *editable {
+                    <button title="Новый файл" onclick={ create_click }>{ "+" }</button>
+                    <button title="Переименовать" onclick={ rename_click }>{ "✎" }</button>
+                    if workspace.files.len() > 1 {
+                        <button title="Удалить" onclick={ delete_click }>{ "🗑" }</button>
+                    }
+                }
+            </div>
+            <details class="workspace-tree">
+                <summary>{ "Файлы" }</summary>
+                <ul>
+                    { render_tree(workspace, on_action) }
+                </ul>
+            </details>
+        </div>
+    }
+}
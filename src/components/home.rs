@@ -63,6 +63,9 @@ pub fn home() -> Html {
                         { "Создать встречу" }
                     </button>
                 </p>
+                <p>
+                    <Link<Route> to={ Route::Replay }>{ "Посмотреть запись урока" }</Link<Route>>
+                </p>
                
                 
             </main>
@@ -3,4 +3,5 @@ pub mod home;
 pub mod document;
 pub mod table;
 pub mod editor;
-pub mod common;
\ No newline at end of file
+pub mod common;
+pub mod replay;
\ No newline at end of file
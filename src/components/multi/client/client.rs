@@ -6,8 +6,11 @@ use crate::components::common::battons::{VideoButton, AudioButton};
 use crate::components::common::video::VideoBox;
 use crate::components::multi::client::client_area::ClientArea;
 use crate::components::multi::client::host_area::HostArea;
+use crate::components::multi::draw::{annotation::Annotation, laser::LaserDot};
 use crate::constants::VIDEO_ELEMENT_ID;
+use crate::models::laser::LaserSurface;
 use crate::stores::client_store::{ClientMsg, ClientStore};
+use crate::stores::host_props_store::{ClientHostPropsMsg, HostPropsStore};
 use crate::stores::media_store::{ClientMediaMsg, MediaStore};
 use crate::utils::dom::get_vis_class;
 use crate::media_devices::device_selector::DeviceSelector;
@@ -100,6 +103,19 @@ pub fn item_content() -> Html {
     }
 }
 
+/// Host's marks over the shared screen, the student adds their own when allowed to draw.
+#[function_component(ScreenAnnotation)]
+pub fn screen_annotation() -> Html {
+    let (state, dispatch) = use_store::<HostPropsStore>();
+    let on_operation = Callback::from(move |operation| dispatch.apply(ClientHostPropsMsg::Annotation(operation)));
+    match state.get_annotation() {
+        Some(board) => html! {
+            <Annotation board={ board.clone() } { on_operation } is_host={ false } author={ state.get_board_author().cloned() } />
+        },
+        None => html! {},
+    }
+}
+
 #[function_component(Client)]
 pub fn client() -> Html {
     let (state, dispatch) = use_store::<ClientStore>();
@@ -133,7 +149,9 @@ pub fn client() -> Html {
                 </div>
             </div>
             <Devices />
-            <div id="shcreen_container" class="consumer unvis">
+            <div id="shcreen_container" class="consumer screen-share unvis">
+                <ScreenAnnotation />
+                <LaserDot surface={ LaserSurface::Screen } />
             </div>
         </div>
     }
@@ -1,9 +1,9 @@
 use monaco::api::TextModel;
-use yew::{Callback, html, Html, function_component};
+use yew::{Callback, html, Html, function_component, KeyboardEvent};
 use yew_icons::{Icon, IconId};
 use yewdux::prelude::use_store;
 
-use crate::{models::commons::AreaKind, components::editor::editor::EditorWrapper, stores::client_props_store::{ClientPropsStore, ClientPropsMsg}};
+use crate::{utils::dom::{format_time, history_shortcut}, models::{commons::{AreaKind, LockMode}, workspace::DocumentKey}, components::editor::{editor::EditorWrapper, review::ReviewPanel, workspace::WorkspaceBar}, stores::{client_props_store::{ClientPropsStore, ClientPropsMsg}, host_props_store::HostPropsStore}};
 
 const TEXTAREA_ID_CLIENT: &str = "client-textarea";
 
@@ -12,21 +12,69 @@ const TEXTAREA_ID_CLIENT: &str = "client-textarea";
 pub fn client_area() -> Html {
 
     let (state, dispatch) = use_store::<ClientPropsStore>();
+    let (host_state, _host_dispatch) = use_store::<HostPropsStore>();
+
+    let lock_mode = host_state.get_host_props().lock_mode;
+    let read_only = lock_mode.is_locked();
 
     let render = || {
         match state.get_client_props().client_area_kind {
             AreaKind::Editor => {
-                let text_model = TextModel::create(&state.get_client_props().client_editor_content, Some("java"), None).unwrap();
+                let workspace = state.get_client_props().workspace.clone();
+                let settings = host_state.get_host_props().editor_settings.clone();
+                let text_model = TextModel::create(workspace.active_content(), Some(&settings.language), None).unwrap();
+                let on_workspace_action = {
+                    let dispatch = dispatch.clone();
+                    Callback::from(move |action| {
+                        dispatch.apply(ClientPropsMsg::Workspace(action));
+                    })
+                };
                 let on_host_editor_cb = {
                     let dispatch = dispatch.clone();
                     Callback::from(move |content| {
                         dispatch.apply(ClientPropsMsg::UpdateClientValue(content));
                     })
                 };
+                let on_cursor = {
+                    let dispatch = dispatch.clone();
+                    Callback::from(move |cursor| {
+                        dispatch.apply(ClientPropsMsg::UpdateCursor(cursor));
+                    })
+                };
+                let on_reply = {
+                    let dispatch = dispatch.clone();
+                    Callback::from(move |(thread_id, text): (String, String)| {
+                        dispatch.apply(ClientPropsMsg::ReviewReply(thread_id, text));
+                    })
+                };
+                let review = state.get_client_props().active_review();
+                let on_history = {
+                    let dispatch = dispatch.clone();
+                    let document = workspace.active_key();
+                    Callback::from(move |action| {
+                        dispatch.apply(ClientPropsMsg::History(document.clone(), action));
+                    })
+                };
                 let is_write = &state.get_client_props().is_write;
+                let remote_cursor = state.get_client_props().remote_cursor
+                    .clone()
+                    .filter(|remote_cursor| remote_cursor.file_id == workspace.active);
                 html! {
                     <div class="col document">
-                        <EditorWrapper on_cb={ on_host_editor_cb.clone() } text_model={ text_model.clone() } is_write={ is_write }/>
+                        <WorkspaceBar workspace={ workspace.clone() } on_action={ on_workspace_action } editable={ !read_only } />
+                        <EditorWrapper
+                            key={ workspace.active.clone() }
+                            on_cb={ on_host_editor_cb.clone() }
+                            text_model={ text_model.clone() }
+                            is_write={ is_write }
+                            { on_cursor }
+                            { remote_cursor }
+                            { settings }
+                            { read_only }
+                            review={ review.clone() }
+                            on_history={ Some(on_history) }
+                        />
+                        <ReviewPanel threads={ review } { on_reply } />
                     </div>
                 }
             },
@@ -35,10 +83,20 @@ pub fn client_area() -> Html {
                     let dispatch = dispatch.clone();
                     Callback::from(move |e| dispatch.apply(ClientPropsMsg::UpdateClientTextArea(e)))
                 };
+                let onkeydown = {
+                    let dispatch = dispatch.clone();
+                    Callback::from(move |e: KeyboardEvent| {
+                        if let Some(action) = history_shortcut(&e) {
+                            e.prevent_default();
+                            dispatch.apply(ClientPropsMsg::History(DocumentKey::TextArea, action));
+                        }
+                    })
+                };
                 let value = state.get_client_props().client_text_area.content.clone();
+                let disabled = state.get_client_props().client_text_area.is_disabled;
                 html! {
                     <div class="col document">
-                        <textarea id={ TEXTAREA_ID_CLIENT } value={ value } { oninput } class="document" cols="100" rows="30" />
+                        <textarea id={ TEXTAREA_ID_CLIENT } value={ value } { oninput } { onkeydown } { disabled } class="document" cols="100" rows="30" />
                     </div>
                 }
             },
@@ -48,6 +106,13 @@ pub fn client_area() -> Html {
     html! {
        <div class="col-3">
             <ClientButtonBar />
+            {
+                match lock_mode {
+                    LockMode::Unlocked => html! {},
+                    LockMode::Locked => html! { <div class="lock-notice">{ "Учитель запретил редактирование" }</div> },
+                    LockMode::FollowHost => html! { <div class="lock-notice">{ "Следите за кодом учителя" }</div> },
+                }
+            }
             { render() }
         </div>
     }
@@ -56,7 +121,7 @@ pub fn client_area() -> Html {
 #[function_component(ClientButtonBar)]
 pub fn client_button_bar() -> Html {
 
-    let (_state, dispatch) = use_store::<ClientPropsStore>();
+    let (state, dispatch) = use_store::<ClientPropsStore>();
 
     let editor_click = {
         let dispatch = dispatch.clone();
@@ -71,6 +136,29 @@ pub fn client_button_bar() -> Html {
         })
     };
 
+    let submit_click = {
+        let dispatch = dispatch.clone();
+        Callback::from(move |_| {
+            dispatch.apply(ClientPropsMsg::Submit);
+        })
+    };
+    let render_assignment = || {
+        match &state.get_client_props().assignment {
+            Some(assignment) => {
+                let status = state.get_client_props().submitted_at
+                    .map_or_else(|| "Не сдано".to_owned(), |submitted_at| format!("Сдано в {}", format_time(submitted_at)));
+                html! {
+                    <span class="assignment">
+                        { assignment.title.clone() }
+                        <button onclick={ submit_click }>{ "Сдать" }</button>
+                        <span class="submission-status">{ status }</span>
+                    </span>
+                }
+            },
+            None => html! {},
+        }
+    };
+
     html! {
         <>
             <button>
@@ -79,6 +167,7 @@ pub fn client_button_bar() -> Html {
             <button>
                 <Icon icon_id={IconId::BootstrapFileEarmarkText} onclick={ text_area_click }/>
             </button>
+            { render_assignment() }
         </>
     }
 }
\ No newline at end of file
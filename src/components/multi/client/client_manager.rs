@@ -1,7 +1,7 @@
 use std::{rc::Rc, cell::RefCell, sync::Arc, collections::HashMap};
 
 use wasm_peers::{one_to_many::MiniClient, ConnectionType, SessionId, many_to_many::NetworkManager, UserId};
-use crate::{models::{audio::Audio, video::Video}, utils::{ inputs::{Message, ManyMassage}, device::{create_audio_decoder, create_video_decoder_video, VideoElementKind, create_video_decoder_video_screen}, dom::{create_video_id, remove_element, switch_visible_el}}, crypto::aes::Aes128State, stores::client_store::ClientMsg};
+use crate::{models::{audio::Audio, laser::{LaserSignal, LaserUpdate}, video::Video}, utils::{ inputs::{Message, ClientMessage, ManyMassage}, laser_channel::LaserChannel, device::{create_audio_decoder, create_video_decoder_video, VideoElementKind, create_video_decoder_video_screen}, dom::{create_video_id, remove_element, switch_visible_el}}, crypto::aes::Aes128State, stores::client_store::ClientMsg, constants::SCREEN_SHARE_ELEMENT_ID};
 
 #[derive(Clone, PartialEq)]
     pub struct ClientManager {
@@ -10,6 +10,7 @@ use crate::{models::{audio::Audio, video::Video}, utils::{ inputs::{Message, Man
     pub network_manager: NetworkManager,
     pub audio_decoders: Rc<RefCell<HashMap<UserId, Rc<RefCell<Audio>>>>>,
     pub video_decoders: Rc<RefCell<HashMap<UserId, Rc<RefCell<Video>>>>>,
+    pub laser_channel: Rc<RefCell<Option<LaserChannel>>>,
 }
 
 impl ClientManager {
@@ -39,6 +40,7 @@ impl ClientManager {
             network_manager,
             audio_decoders,
             video_decoders,
+            laser_channel: Rc::new(RefCell::new(None)),
         }
     }
 
@@ -56,10 +58,11 @@ impl ClientManager {
         };
 
         let video = Rc::new(RefCell::new(create_video_decoder_video("render".to_owned(), VideoElementKind::ReadyId)));
-        let screen_share_decoder = create_video_decoder_video_screen("screen_share".to_owned(), VideoElementKind::ScreenBox);
+        let screen_share_decoder = create_video_decoder_video_screen(SCREEN_SHARE_ELEMENT_ID.to_owned(), VideoElementKind::ScreenBox);
         
         let on_action = on_action.clone();
         let audio = self.audio.clone();
+        let laser_channel = self.laser_channel.clone();
                   
         let on_message_callback = {
             let _aes = Arc::new(Aes128State::new(true));
@@ -69,27 +72,98 @@ impl ClientManager {
             move |message: Message| {
                 match message {
                     Message::HostToHost { 
-                        message,
-                        area_kind, 
+                        operation,
+                        document, 
                     } => {
-                        on_action.borrow()(ClientMsg::HostToHost { message, area_kind })
+                        on_action.borrow()(ClientMsg::HostToHost { operation, document })
                     },                    
                     Message::HostToClient {
-                        message,
-                        area_kind
+                        operation,
+                        document
+                    } => {
+                        on_action.borrow()(ClientMsg::HostToClient { operation, document })
+                    },
+                    Message::HostAckClient { 
+                        document
+                    } => {
+                        on_action.borrow()(ClientMsg::HostAckClient { document })
+                    },
+                    Message::HostResync { 
+                        message
+                    } => {
+                        on_action.borrow()(ClientMsg::HostResync(message))
+                    },
+                    Message::HostWorkspace { 
+                        action
+                    } => {
+                        on_action.borrow()(ClientMsg::HostWorkspace { action })
+                    },
+                    Message::HostLock { 
+                        mode
+                    } => {
+                        on_action.borrow()(ClientMsg::HostLock(mode))
+                    },
+                    Message::HostScroll { 
+                        line
+                    } => {
+                        on_action.borrow()(ClientMsg::HostScroll(line))
+                    },
+                    Message::HostAssignment { 
+                        assignment
                     } => {
-                        on_action.borrow()(ClientMsg::HostToClient { message, area_kind })
+                        on_action.borrow()(ClientMsg::HostAssignment(assignment))
+                    },
+                    Message::HostSubmissionAccepted { 
+                        assignment_id,
+                        submitted_at
+                    } => {
+                        on_action.borrow()(ClientMsg::HostSubmissionAccepted { assignment_id, submitted_at })
+                    },
+                    Message::HostCursor { 
+                        message
+                    } => {
+                        on_action.borrow()(ClientMsg::HostCursor { message })
+                    },
+                    Message::HostBookmark { 
+                        title
+                    } => {
+                        on_action.borrow()(ClientMsg::HostBookmark(title))
+                    },
+                    Message::HostReviewThread { 
+                        thread
+                    } => {
+                        on_action.borrow()(ClientMsg::HostReviewThread(thread))
+                    },
+                    Message::HostReviewReply { 
+                        thread_id,
+                        message
+                    } => {
+                        on_action.borrow()(ClientMsg::HostReviewReply(thread_id, message))
                     },
                     Message::InitHostArea { 
                         message,
+                        settings,
                     } => {
-                       on_action.borrow()(ClientMsg::InitHostAra(message));
+                       on_action.borrow()(ClientMsg::InitHostAra(message, settings));
+                    }
+                    Message::HostEditorSettings { 
+                        settings
+                    } => {
+                        on_action.borrow()(ClientMsg::HostEditorSettings(settings));
                     }
                     Message::HostVideo { 
                         message,
                     } => {
                         if video.borrow().on_video {
-                            let _ = video.borrow_mut().decode_break(Arc::new(message));
+                            let _ = video.borrow_mut().decode_break(message);
+                            let report = video.borrow_mut().take_report();
+                            if let Some(report) = report {
+                                on_action.borrow()(ClientMsg::SendMessage(ClientMessage::ClientVideoReport { report }));
+                            }
+                            let stream = video.borrow_mut().take_key_request();
+                            if let Some(stream) = stream {
+                                on_action.borrow()(ClientMsg::SendMessage(ClientMessage::ClientKeyframeRequest { stream }));
+                            }
                         }
                     },
                     Message::HostIsScreenShare { 
@@ -101,7 +175,13 @@ impl ClientManager {
                     Message::HostScreenShare { 
                         message
                     } => {
-                        let _ = screen_share_decoder.decode_break(Arc::new(message));
+                        let _ = screen_share_decoder.decode_break(message);
+                        if let Some(report) = screen_share_decoder.take_report() {
+                            on_action.borrow()(ClientMsg::SendMessage(ClientMessage::ClientVideoReport { report }));
+                        }
+                        if let Some(stream) = screen_share_decoder.take_key_request() {
+                            on_action.borrow()(ClientMsg::SendMessage(ClientMessage::ClientKeyframeRequest { stream }));
+                        }
                     },
                     Message::HostAudio { 
                         packet
@@ -132,18 +212,79 @@ impl ClientManager {
                     } => {
                         on_action.borrow()(ClientMsg::HostSwitchArea(message));
                     },
-                    Message::OpenPaint => {
-                        on_action.borrow()(ClientMsg::OpenPaint);
+                    Message::OpenPaint { 
+                        board
+                    } => {
+                        on_action.borrow()(ClientMsg::OpenPaint(board));
                     },
                     Message::ClosePaint => {
-                        remove_element("draw-canvas".to_string());
+                        on_action.borrow()(ClientMsg::ClosePaint);
                     }
                     Message::HostPaint { 
-                        offset_x,
-                        offset_y,
-                        action,
+                        page_id,
+                        operation
+                    } => {
+                        on_action.borrow()(ClientMsg::HostPaint(page_id, operation));
+                    },
+                    Message::HostBoardPage { 
+                        action
+                    } => {
+                        on_action.borrow()(ClientMsg::HostBoardPage(action));
+                    },
+                    Message::HostBoardAccess { 
+                        author
+                    } => {
+                        on_action.borrow()(ClientMsg::HostBoardAccess(author));
+                    },
+                    Message::HostAnnotations { 
+                        board
+                    } => {
+                        on_action.borrow()(ClientMsg::HostAnnotations(board));
+                    },
+                    Message::HostAnnotation { 
+                        operation
+                    } => {
+                        on_action.borrow()(ClientMsg::HostAnnotation(operation));
+                    },
+                    Message::HostLaserSignal { 
+                        signal: LaserSignal::Offer { sdp }
+                    } => {
+                        // a host that reconnected offers a new channel
+                        if let Some(previous) = laser_channel.borrow_mut().take() {
+                            previous.close();
+                        }
+                        let on_signal = {
+                            let on_action = on_action.clone();
+                            move |signal: LaserSignal| {
+                                on_action.borrow()(ClientMsg::SendMessage(ClientMessage::ClientLaserSignal { signal }));
+                            }
+                        };
+                        let on_update = {
+                            let on_action = on_action.clone();
+                            move |update: LaserUpdate| on_action.borrow()(ClientMsg::HostLaser(update))
+                        };
+                        match LaserChannel::answer(sdp, on_signal, on_update) {
+                            Ok(channel) => *laser_channel.borrow_mut() = Some(channel),
+                            Err(err) => log::error!("cannot answer laser channel: {:?}", err),
+                        }
+                    },
+                    Message::HostLaserSignal { 
+                        signal
                     } => {
-                        on_action.borrow()(ClientMsg::HostPaint { offset_x, offset_y, action });
+                        match laser_channel.borrow().as_ref() {
+                            Some(channel) => channel.signal(signal),
+                            None => log::error!("laser signal before the offer"),
+                        }
+                    },
+                    Message::HostVideoReport { 
+                        report
+                    } => {
+                        on_action.borrow()(ClientMsg::HostVideoReport(report));
+                    },
+                    Message::HostKeyframeRequest { 
+                        stream
+                    } => {
+                        on_action.borrow()(ClientMsg::KeyframeRequest(stream));
                     },
                     Message::OnCummunication { 
                         message
@@ -163,7 +304,11 @@ impl ClientManager {
         self.mini_client.start(on_open_callback, on_message_callback, on_disconnect_callback);
     }
 
-    pub fn many_init(&mut self) {
+    pub fn many_init(
+        &mut self,
+        on_action: impl Fn(ClientMsg) + 'static,
+    ) {
+        let on_action  = Rc::new(RefCell::new(on_action));
         let audio_decoders = self.audio_decoders.clone();
         let video_decoders = self.video_decoders.clone();
         
@@ -195,7 +340,23 @@ impl ClientManager {
                     } => {
                         let video = video_decoders.as_ref().borrow().get(&user_id).unwrap().clone();
                         let mut video = video.as_ref().borrow_mut();
-                        let _ = video.decode_break(Arc::new(packet));
+                        let _ = video.decode_break(packet);
+                        if let Some(report) = video.take_report() {
+                            on_action.borrow()(ClientMsg::SendManyMessageToUser(user_id, ManyMassage::VideoReport { report }));
+                        }
+                        if let Some(stream) = video.take_key_request() {
+                            on_action.borrow()(ClientMsg::SendManyMessageToUser(user_id, ManyMassage::KeyframeRequest { stream }));
+                        }
+                    },
+                    ManyMassage::VideoReport { 
+                        report
+                    } => {
+                        on_action.borrow()(ClientMsg::PeerVideoReport(user_id, report));
+                    },
+                    ManyMassage::KeyframeRequest { 
+                        stream
+                    } => {
+                        on_action.borrow()(ClientMsg::KeyframeRequest(stream));
                     }
                 }
             } 
@@ -1,23 +1,49 @@
 use monaco::api::TextModel;
-use yew::{Callback, html, Html, function_component};
+use yew::{Callback, html, Html, function_component, use_state};
 use yewdux::prelude::use_store;
 
-use crate::{models::commons::AreaKind, components::editor::editor::EditorWrapper, stores::host_props_store::HostPropsStore};
+use crate::{models::{commons::{AreaKind, LockMode}, laser::LaserSurface, workspace::WorkspaceAction}, components::{editor::{editor::EditorWrapper, replay::ReplayPlayer, workspace::WorkspaceBar}, multi::draw::{laser::LaserDot, pages::PageBar, paint::PaintF}}, stores::host_props_store::{HostPropsStore, ClientHostPropsMsg}};
 
 const TEXTAREA_ID: &str = "document-textarea";
 
 #[function_component(HostArea)]
 pub fn host_area() -> Html {
-    let (state, _dispatch) = use_store::<HostPropsStore>();
+    let (state, dispatch) = use_store::<HostPropsStore>();
+    let is_replay = use_state(|| false);
+    // a page the student went back to, `None` follows the host
+    let viewed_page = use_state(|| None::<String>);
 
     let render = || {
+        if *is_replay {
+            return html! {
+                <ReplayPlayer
+                    recording={ state.get_host_props().recording.clone() }
+                    settings={ state.get_host_props().editor_settings.clone() }
+                />
+            };
+        }
         match state.get_host_props().host_area_kind {
             AreaKind::Editor => {
-                let text_model = TextModel::create(&state.get_host_props().host_editor_content, Some("java"), None).unwrap();
+                // students may look through the host's files, but only the host changes them,
+                // and while following the host they stay on the host's file
+                let is_following = state.get_host_props().lock_mode == LockMode::FollowHost;
+                let on_workspace_action = {
+                    let dispatch = dispatch.clone();
+                    Callback::from(move |action| {
+                        if let (WorkspaceAction::Open { .. }, false) = (&action, is_following) {
+                            dispatch.apply(ClientHostPropsMsg::HostWorkspace(action));
+                        }
+                    })
+                };
+                let workspace = state.get_host_props().workspace.clone();
+                let settings = state.get_host_props().editor_settings.clone();
+                let text_model = TextModel::create(state.get_host_props().editor_content(), Some(&settings.language), None).unwrap();
                 let on_host_editor_cb = Callback::default();
+                let scroll_line = state.get_host_props().scroll_line.filter(|_| is_following);
                 html! {
                     <div class="col document">
-                        <EditorWrapper on_cb={ on_host_editor_cb.clone() } text_model={ text_model.clone() } is_write={ true }/>
+                        <WorkspaceBar workspace={ workspace.clone() } on_action={ on_workspace_action } />
+                        <EditorWrapper key={ workspace.active.clone() } on_cb={ on_host_editor_cb.clone() } text_model={ text_model.clone() } is_write={ true } { settings } { scroll_line } read_only={ true }/>
                     </div>
                 }
             },
@@ -33,11 +59,55 @@ pub fn host_area() -> Html {
         }
     };
    
+    let render_paint = || {
+        let on_operation = {
+            let dispatch = dispatch.clone();
+            Callback::from(move |operation| dispatch.apply(ClientHostPropsMsg::Board(operation)))
+        };
+        let Some(board) = state.get_board() else {
+            return html! {};
+        };
+        let shown = (*viewed_page)
+            .clone()
+            .filter(|id| board.page(id).is_some())
+            .unwrap_or_else(|| board.current.clone());
+        let on_show = {
+            let viewed_page = viewed_page.clone();
+            let current = board.current.clone();
+            Callback::from(move |id: String| viewed_page.set(Some(id).filter(|id| id != &current)))
+        };
+        // earlier pages are only for looking back
+        let author = state.get_board_author()
+            .filter(|_| shown == board.current)
+            .cloned();
+        match board.page(&shown) {
+            Some(page) => html! {
+                <>
+                    <PageBar board={ board.clone() } shown={ shown.clone() } { on_show } />
+                    <PaintF board={ page.clone() } { on_operation } is_host={ false } { author } />
+                </>
+            },
+            None => html! {},
+        }
+    };
+
+    let replay_click = {
+        let is_replay = is_replay.clone();
+        Callback::from(move |_| is_replay.set(!*is_replay))
+    };
+
     html! {
         <>
             <div class="host-content-box">
-                { render() }
+                <button class={ if *is_replay { "active" } else { "" } } onclick={ replay_click }>
+                    { if *is_replay { "К уроку" } else { "Повтор урока" } }
+                </button>
+                <div class="laser-area">
+                    { render() }
+                    <LaserDot surface={ LaserSurface::Document } />
+                </div>
                 <div id="host-paint" class="host-paint">
+                    { render_paint() }
                 </div>
             </div>
             
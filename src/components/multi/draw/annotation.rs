@@ -0,0 +1,126 @@
+use wasm_bindgen::prelude::*;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+use yew::prelude::*;
+use yewdux::prelude::use_store;
+
+use crate::constants::{SCREEN_VIDEO_HEIGHT, SCREEN_VIDEO_WIDTH};
+use crate::models::board::{Board, BoardOperation, Brush, Viewport};
+use crate::stores::host_props_store::{HostHostMsg, HostPropsStore};
+use crate::stores::laser_store::LaserStore;
+
+use super::paint::{draw_action, draw_stroke, fit_canvas, Pencil};
+use super::toolbar::BoardToolbar;
+
+#[derive(PartialEq, Properties)]
+pub struct AnnotationProps {
+    pub board: Board,
+    #[prop_or_default]
+    pub on_operation: Callback<BoardOperation>,
+    pub is_host: bool,
+    /// A student's id to draw under, `None` lets pointer events through to the video.
+    #[prop_or_default]
+    pub author: Option<String>,
+}
+
+/// Transparent layer over the shared screen. Strokes are kept in pixels of the
+/// shared frame, so they stay on the same spot of the screen for every viewer.
+#[function_component(Annotation)]
+pub fn annotation(props: &AnnotationProps) -> Html {
+    let (_state, dispatch) = use_store::<HostPropsStore>();
+    let (laser_state, _laser_dispatch) = use_store::<LaserStore>();
+
+    let canvas = use_node_ref();
+    let brush = use_state(Brush::default);
+    let pencil = use_mut_ref(Pencil::default);
+    let viewport = use_mut_ref(|| Viewport::with_area(SCREEN_VIDEO_WIDTH as f64, SCREEN_VIDEO_HEIGHT as f64));
+    // while the host points with the laser, the layer lets the pointer through
+    let can_draw = (props.is_host && !laser_state.is_enabled()) || props.author.is_some();
+    *pencil.borrow_mut() = Pencil {
+        brush: (*brush).clone(),
+        board: props.board.clone(),
+        can_draw,
+        author: props.author.clone(),
+        zoomable: false,
+    };
+
+    use_effect_with((), {
+        let canvas = canvas.clone();
+        let pencil = pencil.clone();
+        let viewport = viewport.clone();
+        let on_operation = props.on_operation.clone();
+        move |_| {
+            let on_resize = canvas.cast::<HtmlCanvasElement>().map(|canvas| {
+                draw_action(&canvas, pencil.clone(), viewport.clone(), on_operation);
+                let on_resize = Closure::<dyn FnMut()>::new(move || {
+                    render(&canvas, &pencil.borrow().board, &mut viewport.borrow_mut());
+                });
+                if let Some(window) = web_sys::window() {
+                    let _ = window.add_event_listener_with_callback("resize", on_resize.as_ref().unchecked_ref());
+                }
+                on_resize
+            });
+            move || {
+                if let (Some(window), Some(on_resize)) = (web_sys::window(), on_resize) {
+                    let _ = window.remove_event_listener_with_callback("resize", on_resize.as_ref().unchecked_ref());
+                }
+            }
+        }
+    });
+
+    use_effect_with(props.board.clone(), {
+        let canvas = canvas.clone();
+        let viewport = viewport.clone();
+        move |board| {
+            if let Some(canvas) = canvas.cast::<HtmlCanvasElement>() {
+                render(&canvas, board, &mut viewport.borrow_mut());
+            }
+        }
+    });
+
+    let on_brush = {
+        let brush = brush.clone();
+        Callback::from(move |value: Brush| brush.set(value))
+    };
+    let clear_click = {
+        let dispatch = dispatch.clone();
+        Callback::from(move |_| dispatch.apply(HostHostMsg::ClearAnnotation))
+    };
+    let class = if can_draw { "annotation drawing" } else { "annotation" };
+
+    html! {
+        <>
+            if can_draw {
+                <div class="annotation-toolbar">
+                    <BoardToolbar brush={ (*brush).clone() } on_change={ on_brush } />
+                    if props.is_host {
+                        <button onclick={ clear_click }>{ "Стереть всё" }</button>
+                    }
+                </div>
+            }
+            <canvas ref={ canvas } { class }></canvas>
+        </>
+    }
+}
+
+fn render(canvas: &HtmlCanvasElement, board: &Board, viewport: &mut Viewport) {
+    fit_canvas(canvas, viewport);
+    if let Err(err) = redraw(canvas, board, viewport) {
+        log::error!("cannot draw annotations: {:?}", err);
+    }
+}
+
+/// Unlike the board there is no sheet under the strokes, the video shows through.
+fn redraw(canvas: &HtmlCanvasElement, board: &Board, viewport: &Viewport) -> Result<(), JsValue> {
+    let context = canvas
+        .get_context("2d")?
+        .ok_or_else(|| JsValue::from_str("canvas has no 2d context"))?
+        .dyn_into::<CanvasRenderingContext2d>()?;
+    context.set_transform(1., 0., 0., 1., 0., 0.)?;
+    context.clear_rect(0., 0., canvas.width() as f64, canvas.height() as f64);
+    let (scale, x, y) = viewport.transform();
+    context.set_transform(scale, 0., 0., scale, x, y)?;
+    board.strokes
+        .iter()
+        .for_each(|stroke| draw_stroke(&context, stroke));
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use std::{cell::RefCell, rc::Rc};
+
+use yew::prelude::*;
+use yewdux::prelude::use_store;
+
+use crate::{models::{board::{Point, Viewport}, laser::LaserSurface}, stores::laser_store::LaserStore};
+
+#[derive(PartialEq, Properties)]
+pub struct LaserDotProps {
+    pub surface: LaserSurface,
+    /// Places board units on the canvas, other surfaces get fractions of their size.
+    #[prop_or_default]
+    pub viewport: Option<Rc<RefCell<Viewport>>>,
+}
+
+/// The host's laser over a surface. The parent has to be positioned.
+#[function_component(LaserDot)]
+pub fn laser_dot(props: &LaserDotProps) -> Html {
+    let (state, _dispatch) = use_store::<LaserStore>();
+    let Some(update) = state.get_latest() else {
+        return html! {};
+    };
+    let Some(point) = update.point.filter(|point| point.surface == props.surface) else {
+        return html! {};
+    };
+    let style = match &props.viewport {
+        Some(viewport) => {
+            let at = viewport.borrow().to_css(&Point::new(point.x, point.y));
+            format!("left: {}px; top: {}px", at.x, at.y)
+        },
+        None => format!("left: {}%; top: {}%", point.x * 100.0, point.y * 100.0),
+    };
+    // a new element for every update restarts the fade
+    html! {
+        <div key={ update.sequence.to_string() } class="laser" { style }></div>
+    }
+}
@@ -1 +1,5 @@
-pub mod paint;
\ No newline at end of file
+pub mod paint;
+pub mod toolbar;
+pub mod pages;
+pub mod annotation;
+pub mod laser;
\ No newline at end of file
@@ -0,0 +1,156 @@
+use wasm_bindgen::JsCast;
+use web_sys::HtmlSelectElement;
+use yew::prelude::*;
+
+use crate::{components::multi::draw::paint::export_png, models::{board::{Background, PageAction, Whiteboard}, board_svg::{board_svg, whiteboard_html}}, utils::dom::download_file};
+
+#[derive(PartialEq, Properties)]
+pub struct PageBarProps {
+    pub board: Whiteboard,
+    /// Page on screen, for a student it may differ from the host's current one.
+    pub shown: String,
+    pub on_show: Callback<String>,
+    #[prop_or_default]
+    pub on_action: Callback<PageAction>,
+    #[prop_or_default]
+    pub on_add: Callback<()>,
+    #[prop_or_default]
+    pub editable: bool,
+}
+
+/// How the shown page or the whole board is saved.
+#[derive(Clone, Copy)]
+enum Export {
+    Svg,
+    Png,
+    AllPages,
+}
+
+fn export(board: &Whiteboard, shown: &str, format: Export) -> crate::Result<()> {
+    let index = board.page_index(shown).unwrap_or_default();
+    let page = board
+        .page(shown)
+        .ok_or_else(|| crate::Error::InvalidBoard(format!("page with id '{}' not found", shown)))?;
+    match format {
+        Export::Svg => download_file(&format!("board-{}.svg", index + 1), &board_svg(page), "image/svg+xml"),
+        Export::Png => export_png(page, &format!("board-{}.png", index + 1)),
+        Export::AllPages => download_file("board.html", &whiteboard_html(board), "text/html"),
+    }
+}
+
+/// Page switcher of the whiteboard. Without `editable` pages can only be browsed.
+#[function_component(PageBar)]
+pub fn page_bar(props: &PageBarProps) -> Html {
+    let PageBarProps {
+        board,
+        shown,
+        on_show,
+        on_action,
+        on_add,
+        editable,
+    } = props;
+
+    let pages = board
+        .pages
+        .iter()
+        .enumerate()
+        .map(|(index, page)| {
+            let onclick = {
+                let on_show = on_show.clone();
+                let id = page.id.clone();
+                Callback::from(move |_| on_show.emit(id.clone()))
+            };
+            let mut class = classes!("board-page");
+            if &page.id == shown {
+                class.push("active");
+            }
+            if page.id == board.current {
+                class.push("current");
+            }
+            html! {
+                <span key={ page.id.clone() } { class } { onclick }>{ index + 1 }</span>
+            }
+        })
+        .collect::<Html>();
+
+    let index = board.page_index(shown).unwrap_or_default();
+    let move_click = |to: usize| {
+        let on_action = on_action.clone();
+        let id = shown.clone();
+        Callback::from(move |_| on_action.emit(PageAction::Move { id: id.clone(), index: to }))
+    };
+    let add_click = {
+        let on_add = on_add.clone();
+        Callback::from(move |_| on_add.emit(()))
+    };
+    let delete_click = {
+        let on_action = on_action.clone();
+        let id = shown.clone();
+        Callback::from(move |_| {
+            let confirmed = web_sys::window()
+                .and_then(|window| window.confirm_with_message("Удалить страницу?").ok())
+                .unwrap_or(false);
+            if confirmed {
+                on_action.emit(PageAction::Delete { id: id.clone() });
+            }
+        })
+    };
+    let follow_click = {
+        let on_show = on_show.clone();
+        let current = board.current.clone();
+        Callback::from(move |_| on_show.emit(current.clone()))
+    };
+
+    let background_change = {
+        let on_action = on_action.clone();
+        let id = shown.clone();
+        Callback::from(move |e: Event| {
+            let index = e
+                .target()
+                .expect("Event should have a target when dispatched")
+                .unchecked_into::<HtmlSelectElement>()
+                .value()
+                .parse::<usize>();
+            if let Some(background) = index.ok().and_then(|index| Background::ALL.get(index)) {
+                on_action.emit(PageAction::SetBackground { id: id.clone(), background: *background });
+            }
+        })
+    };
+    let background = board.page(shown).map(|page| page.background).unwrap_or_default();
+
+    let export_click = |format: Export| {
+        let board = board.clone();
+        let shown = shown.clone();
+        Callback::from(move |_| {
+            if let Err(err) = export(&board, &shown, format) {
+                log::error!("cannot export board: {}", err);
+            }
+        })
+    };
+
+    html! {
+        <div class="board-pages">
+            { pages }
+            if *editable {
+                <button title="Новая страница" onclick={ add_click }>{ "+" }</button>
+                <button title="Сдвинуть влево" disabled={ index == 0 } onclick={ move_click(index.saturating_sub(1)) }>{ "←" }</button>
+                <button title="Сдвинуть вправо" disabled={ index + 1 >= board.pages.len() } onclick={ move_click(index + 1) }>{ "→" }</button>
+                if board.pages.len() > 1 {
+                    <button title="Удалить страницу" onclick={ delete_click }>{ "🗑" }</button>
+                }
+                <select title="Фон страницы" onchange={ background_change }>
+                    { for Background::ALL.iter().enumerate().map(|(index, option)| html! {
+                        <option value={ index.to_string() } selected={ *option == background }>{ option.title() }</option>
+                    }) }
+                </select>
+            } else if shown != &board.current {
+                <button onclick={ follow_click }>{ "К странице учителя" }</button>
+            }
+            <span class="board-export">
+                <button title="Сохранить страницу в SVG" onclick={ export_click(Export::Svg) }>{ "SVG" }</button>
+                <button title="Сохранить страницу в PNG" onclick={ export_click(Export::Png) }>{ "PNG" }</button>
+                <button title="Сохранить все страницы" onclick={ export_click(Export::AllPages) }>{ "Все страницы" }</button>
+            </span>
+        </div>
+    }
+}
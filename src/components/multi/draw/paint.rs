@@ -1,60 +1,102 @@
-use std::cell::Cell;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::f64::consts::{PI, TAU};
 use std::rc::Rc;
 use wasm_bindgen::prelude::*;
-use web_sys::{HtmlCanvasElement, MouseEvent};
-use yew::{Callback, Properties, html, use_node_ref, Html, function_component, use_effect, use_state_eq};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{CanvasRenderingContext2d, DragEvent, Event, File, HtmlCanvasElement, HtmlImageElement, HtmlInputElement, MouseEvent, PointerEvent, Url, WheelEvent};
+use yew::{Callback, Properties, html, use_node_ref, Html, function_component, use_effect_with, use_mut_ref, use_state};
 use yew_icons::{IconId, Icon};
 use yewdux::prelude::use_store;
 
+use crate::constants::{BOARD_DEFAULT_COLOR, BOARD_GRID_COLOR, BOARD_HANDLE_SIZE, BOARD_HEIGHT, BOARD_IMAGE_MAX_BYTES, BOARD_IMAGE_MAX_SIZE, BOARD_IMAGE_MIN_QUALITY, BOARD_IMAGE_QUALITY, BOARD_PAGE_COLOR, BOARD_PALM_MIN_SIZE, BOARD_PALM_REJECTION_MS, BOARD_WIDTH, BOARD_ZOOM_STEP};
+use crate::models::board::{Background, Board, BoardHistory, BoardOperation, Brush, Point, Stroke, Tool, Viewport};
+use crate::models::laser::{LaserPoint, LaserSurface};
 use crate::stores::host_props_store::{HostPropsStore, HostHostMsg};
-use crate::utils;
-use crate::utils::inputs::{Message, PaintAction};
+use crate::stores::laser_store::{LaserMsg, LaserStore};
+use crate::utils::dom::{download_url, get_document};
 
-pub enum Msg {
-
-}
+use super::laser::LaserDot;
+use super::toolbar::BoardToolbar;
 
 #[derive(PartialEq, Properties)]
 pub struct CurrentProps {
+    pub board: Board,
     #[prop_or_default]
-    pub content: String,
+    pub on_operation: Callback<BoardOperation>,
+    pub is_host: bool,
+    /// A student's id to draw under, `None` keeps the board read-only for them.
     #[prop_or_default]
-    pub send_message_all_cb: Callback<Message>,
-    pub is_host: bool
+    pub author: Option<String>,
+}
+
+/// Everything the pointer listeners read. They are only installed once, so
+/// this is refreshed on every render instead.
+#[derive(Default)]
+pub(super) struct Pencil {
+    pub(super) brush: Brush,
+    pub(super) board: Board,
+    pub(super) can_draw: bool,
+    pub(super) author: Option<String>,
+    /// Whether the wheel and dragging may zoom and scroll the view.
+    pub(super) zoomable: bool,
 }
 
 #[function_component(PaintF)]
 pub fn paint(props: &CurrentProps) -> Html {
 
-    let (_state, dispatch) = use_store::<HostPropsStore>();
-
-    let content = use_state_eq(|| props.content.clone());
+    let (state, dispatch) = use_store::<HostPropsStore>();
+    let (laser_state, laser_dispatch) = use_store::<LaserStore>();
 
     let canvas = use_node_ref();
+    let brush = use_state(Brush::default);
+    let pencil = use_mut_ref(Pencil::default);
+    let viewport = use_mut_ref(Viewport::default);
+    let can_draw = (props.is_host && !laser_state.is_enabled()) || props.author.is_some();
+    *pencil.borrow_mut() = Pencil {
+        brush: (*brush).clone(),
+        board: props.board.clone(),
+        can_draw,
+        author: props.author.clone(),
+        zoomable: true,
+    };
 
-    use_effect({
+    use_effect_with((), {
         let canvas = canvas.clone();
-        let content = content.clone();
-        let send_message_all_cb = props.send_message_all_cb.clone();
-        move || {
-            let canvas = canvas.cast::<HtmlCanvasElement>();
-            match canvas {
-                Some(canvas) => {
-                    let context = canvas
-                        .get_context("2d")
-                        .unwrap()
-                        .unwrap()
-                        .dyn_into::<web_sys::CanvasRenderingContext2d>()
-                        .unwrap();
-                    canvas.set_width(600);
-                    canvas.set_height(500);
-                    context.set_font("20px Arial");
-                    draw_content(&content, &context);
-                    host_action(&canvas, context, send_message_all_cb);
-                },
-                None => {
-                    log::error!("none canvas element");
-                },
+        let pencil = pencil.clone();
+        let viewport = viewport.clone();
+        let on_operation = props.on_operation.clone();
+        move |_| {
+            let on_resize = canvas.cast::<HtmlCanvasElement>().map(|canvas| {
+                draw_action(&canvas, pencil.clone(), viewport.clone(), on_operation);
+                let on_resize = Closure::<dyn FnMut()>::new(move || {
+                    render(&canvas, &pencil.borrow().board, &mut viewport.borrow_mut());
+                });
+                if let Some(window) = web_sys::window() {
+                    let _ = window.add_event_listener_with_callback("resize", on_resize.as_ref().unchecked_ref());
+                }
+                on_resize
+            });
+            if on_resize.is_none() {
+                log::error!("none canvas element");
+            }
+            move || {
+                if let (Some(window), Some(on_resize)) = (web_sys::window(), on_resize) {
+                    let _ = window.remove_event_listener_with_callback("resize", on_resize.as_ref().unchecked_ref());
+                }
+            }
+        }
+    });
+
+    use_effect_with(props.board.clone(), {
+        let canvas = canvas.clone();
+        let pencil = pencil.clone();
+        let viewport = viewport.clone();
+        move |board| {
+            if let Some(canvas) = canvas.cast::<HtmlCanvasElement>() {
+                render(&canvas, board, &mut viewport.borrow_mut());
+                // images that were not loaded yet show up once they are
+                load_images(board, move || render(&canvas, &pencil.borrow().board, &mut viewport.borrow_mut()));
             }
         }
     });
@@ -65,51 +107,312 @@ pub fn paint(props: &CurrentProps) -> Html {
             dispatch.apply(HostHostMsg::ClosePaint);
         }
     };
+    let on_brush = {
+        let brush = brush.clone();
+        Callback::from(move |value: Brush| brush.set(value))
+    };
+    let on_history = props.is_host.then(|| {
+        let dispatch = dispatch.clone();
+        Callback::from(move |action| dispatch.apply(HostHostMsg::BoardHistory(action)))
+    });
+    let on_access = {
+        let dispatch = dispatch.clone();
+        move |e: Event| {
+            let everyone = e
+                .target()
+                .expect("Event should have a target when dispatched")
+                .unchecked_into::<HtmlInputElement>()
+                .checked();
+            dispatch.apply(HostHostMsg::SetBoardAccess(everyone));
+        }
+    };
+    // `None` resets the view, otherwise zooms around the middle of the canvas
+    let zoom_click = |factor: Option<f64>| {
+        let canvas = canvas.clone();
+        let pencil = pencil.clone();
+        let viewport = viewport.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let Some(canvas) = canvas.cast::<HtmlCanvasElement>() {
+                let mut viewport = viewport.borrow_mut();
+                match factor {
+                    Some(factor) => viewport.zoom_at(factor, canvas.client_width() as f64 / 2.0, canvas.client_height() as f64 / 2.0),
+                    None => viewport.reset(),
+                }
+                render(&canvas, &pencil.borrow().board, &mut viewport);
+            }
+        })
+    };
+    let on_laser_move = laser_state.is_enabled().then(|| {
+        let laser_dispatch = laser_dispatch.clone();
+        let viewport = viewport.clone();
+        Callback::from(move |e: PointerEvent| {
+            let point = viewport.borrow().to_board(e.offset_x() as f64, e.offset_y() as f64);
+            laser_dispatch.apply(LaserMsg::Move(Some(LaserPoint { surface: LaserSurface::Board, x: point.x, y: point.y })));
+        })
+    });
+    let on_laser_leave = laser_state.is_enabled().then(|| {
+        let laser_dispatch = laser_dispatch.clone();
+        Callback::from(move |_: PointerEvent| laser_dispatch.apply(LaserMsg::Move(None)))
+    });
+    let on_image = can_draw.then(|| {
+        let author = props.author.clone();
+        let on_operation = props.on_operation.clone();
+        let center = Point::new(BOARD_WIDTH as f64 / 2.0, BOARD_HEIGHT as f64 / 2.0);
+        Callback::from(move |file: File| add_image(file, center, author.clone(), on_operation.clone()))
+    });
+    let on_drag_over = can_draw.then(|| Callback::from(|e: DragEvent| e.prevent_default()));
+    let on_drop = can_draw.then(|| {
+        let author = props.author.clone();
+        let on_operation = props.on_operation.clone();
+        let viewport = viewport.clone();
+        Callback::from(move |e: DragEvent| {
+            e.prevent_default();
+            let file = e
+                .data_transfer()
+                .and_then(|data| data.files())
+                .and_then(|files| files.get(0))
+                .filter(|file| file.type_().starts_with("image/"));
+            if let Some(file) = file {
+                let center = viewport.borrow().to_board(e.offset_x() as f64, e.offset_y() as f64);
+                add_image(file, center, author.clone(), on_operation.clone());
+            }
+        })
+    });
+    let history = state.get_board_history();
 
     html! {
-        <div>
-            <button>
-                <Icon icon_id={IconId::BootstrapXSquare} onclick={ editor_click }/>
-            </button>
-            <canvas id="draw-canvas" ref={ canvas } class="paint"></canvas>
+        <div class="board">
+            if props.is_host {
+                <button>
+                    <Icon icon_id={IconId::BootstrapXSquare} onclick={ editor_click }/>
+                </button>
+                <label class="board-access">
+                    <input type="checkbox" checked={ state.get_board_permission().everyone } onchange={ on_access } />
+                    { "Все ученики рисуют" }
+                </label>
+            }
+            <span class="board-zoom">
+                <button title="Уменьшить" onclick={ zoom_click(Some(1.0 / BOARD_ZOOM_STEP)) }>{ "−" }</button>
+                <button title="Весь лист" onclick={ zoom_click(None) }>{ "⤢" }</button>
+                <button title="Увеличить" onclick={ zoom_click(Some(BOARD_ZOOM_STEP)) }>{ "+" }</button>
+            </span>
+            if can_draw {
+                <BoardToolbar
+                    brush={ (*brush).clone() }
+                    on_change={ on_brush }
+                    can_undo={ history.is_some_and(BoardHistory::can_undo) }
+                    can_redo={ history.is_some_and(BoardHistory::can_redo) }
+                    { on_history }
+                    { on_image }
+                />
+            }
+            <div class="board-canvas" ondragover={ on_drag_over } ondrop={ on_drop }>
+                <canvas id="draw-canvas" ref={ canvas } class="paint" onpointermove={ on_laser_move } onpointerleave={ on_laser_leave }></canvas>
+                <LaserDot surface={ LaserSurface::Board } viewport={ viewport.clone() } />
+            </div>
         </div>
     }
 }
 
+// Matches the canvas buffer to its css size times the pixel ratio, so lines
+// stay sharp on high-DPI screens, and fits the board into it.
+pub(super) fn fit_canvas(canvas: &HtmlCanvasElement, viewport: &mut Viewport) {
+    let (width, height) = (canvas.client_width() as f64, canvas.client_height() as f64);
+    if width <= 0.0 || height <= 0.0 {
+        return;
+    }
+    let pixel_ratio = web_sys::window().map_or(1.0, |window| window.device_pixel_ratio());
+    viewport.fit_to(width, height, pixel_ratio);
+    let (pixel_width, pixel_height) = ((width * pixel_ratio).round() as u32, (height * pixel_ratio).round() as u32);
+    if canvas.width() != pixel_width || canvas.height() != pixel_height {
+        canvas.set_width(pixel_width);
+        canvas.set_height(pixel_height);
+    }
+}
+
+fn render(canvas: &HtmlCanvasElement, board: &Board, viewport: &mut Viewport) {
+    fit_canvas(canvas, viewport);
+    if let Err(err) = redraw(canvas, board, viewport) {
+        log::error!("cannot draw board: {:?}", err);
+    }
+}
 
-pub fn start(
-    content: &str,
-    send_message_all_cb: Callback<Message>,
-    is_host: bool,
-) -> Result<Rc<HtmlCanvasElement>, JsValue> {
-    let document = web_sys::window().unwrap().document().unwrap();
-    let canvas = document
-        .create_element("canvas")?
-        .dyn_into::<web_sys::HtmlCanvasElement>()?;
-    let div = utils::dom::get_element("host-paint").unwrap();
-    let _ = div.append_child(&canvas);
-    canvas.set_id("draw-canvas");
-    canvas.set_width(600);
-    canvas.set_height(500);
-    canvas.set_class_name("paint");
+/// Clears the canvas and draws the whole board again.
+pub fn redraw(canvas: &HtmlCanvasElement, board: &Board, viewport: &Viewport) -> Result<(), JsValue> {
     let context = canvas
         .get_context("2d")?
-        .unwrap()
-        .dyn_into::<web_sys::CanvasRenderingContext2d>()?;
-    
+        .ok_or_else(|| JsValue::from_str("canvas has no 2d context"))?
+        .dyn_into::<CanvasRenderingContext2d>()?;
+    context.set_transform(1., 0., 0., 1., 0., 0.)?;
+    context.clear_rect(0., 0., canvas.width() as f64, canvas.height() as f64);
+    let (scale, x, y) = viewport.transform();
+    context.set_transform(scale, 0., 0., scale, x, y)?;
+    // edge of the page, visible once zoomed out
+    context.set_fill_style(&JsValue::from_str(BOARD_PAGE_COLOR));
+    context.fill_rect(0., 0., BOARD_WIDTH as f64, BOARD_HEIGHT as f64);
+    draw_board(&context, board);
+    Ok(())
+}
+
+/// Renders a page at board size on a white sheet, off screen, and saves it as PNG.
+pub fn export_png(board: &Board, file_name: &str) -> crate::Result<()> {
+    let canvas = get_document()
+        .create_element("canvas")
+        .map_err(|err| crate::Error::FailedToExport(format!("{:?}", err)))?
+        .dyn_into::<HtmlCanvasElement>()
+        .map_err(|err| crate::Error::UnexpectedElement(format!("element is not a canvas: {:?}", err)))?;
+    canvas.set_width(BOARD_WIDTH);
+    canvas.set_height(BOARD_HEIGHT);
+    let context = canvas
+        .get_context("2d")
+        .ok()
+        .flatten()
+        .and_then(|context| context.dyn_into::<CanvasRenderingContext2d>().ok())
+        .ok_or_else(|| crate::Error::FailedToExport("canvas has no 2d context".to_owned()))?;
+    context.set_fill_style(&JsValue::from_str("white"));
+    context.fill_rect(0., 0., BOARD_WIDTH as f64, BOARD_HEIGHT as f64);
+    draw_board(&context, board);
+    let url = canvas
+        .to_data_url_with_type("image/png")
+        .map_err(|err| crate::Error::FailedToExport(format!("{:?}", err)))?;
+    download_url(file_name, &url)
+}
+
+fn draw_board(context: &CanvasRenderingContext2d, board: &Board) {
+    draw_background(context, board.background);
+    context.set_fill_style(&JsValue::from_str(BOARD_DEFAULT_COLOR));
     context.set_font("20px Arial");
-    draw_content(content, &context);
+    draw_content(&board.text, context);
+    board.strokes
+        .iter()
+        .for_each(|stroke| draw_stroke(context, stroke));
+}
 
-    if is_host {
-        host_action(&canvas, context, send_message_all_cb);
-    } else {
-        // client_action(&canvas, context, send_message_all_cb);
+fn draw_background(context: &CanvasRenderingContext2d, background: Background) {
+    let color = JsValue::from_str(BOARD_GRID_COLOR);
+    context.set_stroke_style(&color);
+    context.set_fill_style(&color);
+    context.set_line_cap("butt");
+    for line in background.lines() {
+        context.begin_path();
+        context.set_line_width(line.width);
+        context.move_to(line.start.x, line.start.y);
+        context.line_to(line.end.x, line.end.y);
+        context.stroke();
     }
-    
-    Ok(Rc::new(canvas))
+    for dot in background.dots() {
+        context.fill_rect(dot.x - 1.0, dot.y - 1.0, 2.0, 2.0);
+    }
+}
+
+thread_local! {
+    // decoded images of the image strokes, by stroke id
+    static IMAGES: RefCell<HashMap<String, HtmlImageElement>> = RefCell::new(HashMap::new());
 }
 
-fn draw_content(content: &str, context: &web_sys::CanvasRenderingContext2d) {
+/// Starts decoding the images of a page that are not cached yet, `on_load`
+/// is called for each of them once it can be drawn.
+fn load_images(board: &Board, on_load: impl Fn() + Clone + 'static) {
+    IMAGES.with(|images| {
+        let mut images = images.borrow_mut();
+        for stroke in &board.strokes {
+            let Some(src) = stroke.image_src() else {
+                continue;
+            };
+            if images.get(&stroke.id).is_some_and(|image| image.src() == src) {
+                continue;
+            }
+            let Ok(image) = HtmlImageElement::new() else {
+                continue;
+            };
+            let on_load = on_load.clone();
+            let on_load = Closure::once_into_js(move || on_load());
+            image.set_onload(Some(on_load.unchecked_ref()));
+            image.set_src(src);
+            images.insert(stroke.id.clone(), image);
+        }
+    });
+}
+
+fn draw_image(context: &CanvasRenderingContext2d, stroke: &Stroke, start: &Point, end: &Point) {
+    IMAGES.with(|images| {
+        let images = images.borrow();
+        let Some(image) = images.get(&stroke.id).filter(|image| image.complete()) else {
+            return;
+        };
+        let _ = context.draw_image_with_html_image_element_and_dw_and_dh(
+            image,
+            start.x.min(end.x),
+            start.y.min(end.y),
+            (end.x - start.x).abs(),
+            (end.y - start.y).abs(),
+        );
+    });
+}
+
+/// Shrinks an uploaded picture, encodes it as jpeg and adds it to the page at `center`.
+fn add_image(file: File, center: Point, author: Option<String>, on_operation: Callback<BoardOperation>) {
+    spawn_local(async move {
+        match image_stroke(&file, center).await {
+            Ok(mut stroke) => {
+                stroke.set_author(author);
+                on_operation.emit(BoardOperation::Add { stroke });
+            },
+            Err(err) => {
+                log::error!("cannot add image: {:?}", err);
+            },
+        }
+    });
+}
+
+async fn image_stroke(file: &File, center: Point) -> Result<Stroke, JsValue> {
+    let url = Url::create_object_url_with_blob(file)?;
+    let image = HtmlImageElement::new()?;
+    let loaded = js_sys::Promise::new(&mut |resolve, reject| {
+        image.set_onload(Some(&resolve));
+        image.set_onerror(Some(&reject));
+    });
+    image.set_src(&url);
+    let loaded = JsFuture::from(loaded).await;
+    Url::revoke_object_url(&url)?;
+    loaded?;
+    let (width, height) = (image.natural_width() as f64, image.natural_height() as f64);
+    if width == 0.0 || height == 0.0 {
+        return Err(JsValue::from_str("image is empty"));
+    }
+    // the picture travels with the page, so it is kept small
+    let scale = (BOARD_IMAGE_MAX_SIZE / width.max(height)).min(1.0);
+    let (width, height) = ((width * scale).round().max(1.0), (height * scale).round().max(1.0));
+    let canvas = get_document()
+        .create_element("canvas")?
+        .dyn_into::<HtmlCanvasElement>()?;
+    canvas.set_width(width as u32);
+    canvas.set_height(height as u32);
+    let context = canvas
+        .get_context("2d")?
+        .ok_or_else(|| JsValue::from_str("canvas has no 2d context"))?
+        .dyn_into::<CanvasRenderingContext2d>()?;
+    // jpeg has no transparency
+    context.set_fill_style(&JsValue::from_str("white"));
+    context.fill_rect(0., 0., width, height);
+    context.draw_image_with_html_image_element_and_dw_and_dh(&image, 0., 0., width, height)?;
+    let mut quality = BOARD_IMAGE_QUALITY;
+    let src = loop {
+        let src = canvas.to_data_url_with_type_and_encoder_options("image/jpeg", &JsValue::from_f64(quality))?;
+        if src.len() <= BOARD_IMAGE_MAX_BYTES {
+            break src;
+        }
+        if quality <= BOARD_IMAGE_MIN_QUALITY {
+            return Err(JsValue::from_str("image is too large"));
+        }
+        quality = (quality - 0.1).max(BOARD_IMAGE_MIN_QUALITY);
+    };
+    // at first it takes at most half of the page
+    let fit = (BOARD_WIDTH as f64 / 2.0 / width).min(BOARD_HEIGHT as f64 / 2.0 / height).min(1.0);
+    Ok(Stroke::image(src, center, width * fit, height * fit))
+}
+
+fn draw_content(content: &str, context: &CanvasRenderingContext2d) {
     let arr = content.lines();
     let mut step = 20.0;
     arr.into_iter().for_each(|line| {
@@ -118,117 +421,318 @@ fn draw_content(content: &str, context: &web_sys::CanvasRenderingContext2d) {
     });
 }
 
-fn host_action(canvas: &web_sys::HtmlCanvasElement, context: web_sys::CanvasRenderingContext2d, send_message_all_cb: Callback<Message>) {
-    let context = Rc::new(context);
-    let pressed = Rc::new(Cell::new(false));
-       
-    {
-        let context = context.clone();
-        let pressed = pressed.clone();
-        let send_message_all_cb = send_message_all_cb.clone();
-        let closure = Closure::<dyn FnMut(_)>::new(move |event: web_sys::MouseEvent| {
-            context.begin_path();
-            context.move_to(event.offset_x() as f64, event.offset_y() as f64);
-            pressed.set(true);
-            send_message_all_cb.emit(
-                Message::HostPaint { 
-                    offset_x: event.offset_x() as f64,
-                    offset_y: event.offset_y() as f64,
-                    action: PaintAction::Down
-                 }
-            )
-        });
-        canvas.add_event_listener_with_callback("mousedown", closure.as_ref().unchecked_ref()).expect("error add event listener paint mousedown");
-        closure.forget();
-    }
-    {
-        let context = context.clone();
-        let pressed = pressed.clone();
-        let send_message_all_cb = send_message_all_cb.clone();
-        let closure = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
-            if pressed.get() {
-                context.line_to(event.offset_x() as f64, event.offset_y() as f64);
-                context.stroke();
+pub(super) fn draw_stroke(context: &CanvasRenderingContext2d, stroke: &Stroke) {
+    let (Some(start), Some(end)) = (stroke.points.first(), stroke.points.last()) else {
+        return;
+    };
+    let color = JsValue::from_str(&stroke.color);
+    context.set_stroke_style(&color);
+    context.set_fill_style(&color);
+    context.set_line_width(stroke.width);
+    context.set_line_cap("round");
+    context.set_line_join("round");
+    context.begin_path();
+    match stroke.tool {
+        Tool::Pen if stroke.has_pressure() => {
+            // each segment gets its own width, so it is stroked on its own
+            let mut previous = start;
+            for point in &stroke.points {
                 context.begin_path();
-                context.move_to(event.offset_x() as f64, event.offset_y() as f64);
-                send_message_all_cb.emit(
-                    Message::HostPaint { 
-                        offset_x: event.offset_x() as f64,
-                        offset_y: event.offset_y() as f64,
-                        action: PaintAction::Move
-                     }
-                )
-            }
-        }) as Box<dyn FnMut(_)>);
-        canvas.add_event_listener_with_callback("mousemove", closure.as_ref().unchecked_ref()).expect("error add event listener pain mousemove");
-        closure.forget();
+                context.set_line_width((stroke.width_at(previous) + stroke.width_at(point)) / 2.0);
+                context.move_to(previous.x, previous.y);
+                context.line_to(point.x, point.y);
+                context.stroke();
+                previous = point;
+            }
+            return;
+        },
+        Tool::Pen => {
+            context.move_to(start.x, start.y);
+            // a single click still leaves a dot
+            context.line_to(start.x, start.y);
+            stroke.points
+                .iter()
+                .skip(1)
+                .for_each(|point| context.line_to(point.x, point.y));
+        },
+        Tool::Line => {
+            context.move_to(start.x, start.y);
+            context.line_to(end.x, end.y);
+        },
+        Tool::Arrow => {
+            context.move_to(start.x, start.y);
+            context.line_to(end.x, end.y);
+            let angle = (end.y - start.y).atan2(end.x - start.x);
+            let head = (stroke.width * 4.0).max(10.0);
+            for side in [PI / 6.0, -PI / 6.0] {
+                context.move_to(end.x, end.y);
+                context.line_to(end.x - head * (angle + side).cos(), end.y - head * (angle + side).sin());
+            }
+        },
+        Tool::Rect => {
+            context.rect(start.x.min(end.x), start.y.min(end.y), (end.x - start.x).abs(), (end.y - start.y).abs());
+        },
+        Tool::Ellipse => {
+            let (rx, ry) = ((end.x - start.x).abs() / 2.0, (end.y - start.y).abs() / 2.0);
+            let _ = context.ellipse((start.x + end.x) / 2.0, (start.y + end.y) / 2.0, rx, ry, 0.0, 0.0, TAU);
+        },
+        Tool::Text => {
+            context.set_font(&format!("{}px Arial", stroke.font_size()));
+            let _ = context.fill_text(&stroke.text, start.x, start.y);
+            return;
+        },
+        Tool::Image => {
+            draw_image(context, stroke, start, end);
+            return;
+        },
+        Tool::Eraser | Tool::Move => {
+            return;
+        },
     }
-    {
-        let context = context.clone();
-        let send_message_all_cb = send_message_all_cb.clone();
-        let closure = Closure::<dyn FnMut(_)>::new(move |event: web_sys::MouseEvent| {
-            context.line_to(event.offset_x() as f64, event.offset_y() as f64);
-            context.stroke();
-            pressed.set(false);
-            send_message_all_cb.emit(
-                Message::HostPaint { 
-                    offset_x: event.offset_x() as f64,
-                    offset_y: event.offset_y() as f64,
-                    action: PaintAction::Up
-                 }
-            )
-        });
-        canvas.add_event_listener_with_callback("mouseup", closure.as_ref().unchecked_ref()).expect("error add event listener paint mouseup");
-        closure.forget();
+    context.stroke();
+}
+
+fn event_point(event: &PointerEvent, viewport: &RefCell<Viewport>) -> Point {
+    let point = viewport.borrow().to_board(event.offset_x() as f64, event.offset_y() as f64);
+    // mice and fingers report a fixed pressure, it only means something for a pen
+    if event.pointer_type() == "pen" {
+        point.with_pressure(event.pressure() as f64)
+    } else {
+        point
     }
 }
 
+fn ask_text() -> Option<String> {
+    web_sys::window()?
+        .prompt_with_message("Текст")
+        .ok()
+        .flatten()
+        .filter(|text| !text.trim().is_empty())
+}
+
+/// What the pressed pointer is doing on the canvas.
+#[derive(Default)]
+enum Gesture {
+    #[default]
+    Idle,
+    Drawing(Stroke),
+    Erasing,
+    /// Dragging a stroke as it was picked up at `from`, or its end corner with `resize`.
+    Moving {
+        stroke: Stroke,
+        from: Point,
+        resize: bool,
+    },
+    /// Scrolling the view, with the last pointer position in css pixels.
+    Panning(f64, f64),
+}
+
+/// The one pointer that drives the current gesture, and when a pen last
+/// touched the canvas.
+#[derive(Default)]
+struct Pointers {
+    gesture: Gesture,
+    active: Option<i32>,
+    pen_at: Option<f64>,
+}
+
+impl Pointers {
+    /// A palm resting on the screen shows up as a large touch, and while the
+    /// teacher writes with a pen no touch is meant to draw.
+    fn is_palm(&self, event: &PointerEvent, now: f64) -> bool {
+        event.pointer_type() == "touch"
+            && (self.pen_at.is_some_and(|pen_at| now - pen_at < BOARD_PALM_REJECTION_MS)
+                || event.width().max(event.height()) as f64 > BOARD_PALM_MIN_SIZE)
+    }
+
+    fn is_active(&self, event: &PointerEvent) -> bool {
+        self.active == Some(event.pointer_id())
+    }
+}
+
+// Deletes the strokes under the eraser. They are removed from the pencil's
+// board right away so that the next move does not delete them again before
+// the redraw. A student only erases their own strokes.
+fn erase(pencil: &RefCell<Pencil>, point: &Point, on_operation: &Callback<BoardOperation>) {
+    let ids = {
+        let pencil = pencil.borrow();
+        let board = &pencil.board;
+        board
+            .hits(point, pencil.brush.eraser_radius())
+            .into_iter()
+            .filter(|id| pencil.author.is_none() || board.stroke(id).is_some_and(|stroke| stroke.author == pencil.author))
+            .collect::<Vec<String>>()
+    };
+    for id in ids {
+        let operation = BoardOperation::Delete { id };
+        let _ = pencil.borrow_mut().board.apply(&operation);
+        on_operation.emit(operation);
+    }
+}
+
+// Picks up the topmost stroke under the pointer, grabbing a shape or an image
+// close to its end corner resizes it. A student only moves their own strokes.
+fn grab(pencil: &Pencil, point: &Point) -> Option<Gesture> {
+    let board = &pencil.board;
+    let stroke = board
+        .hits(point, pencil.brush.eraser_radius())
+        .iter()
+        .filter_map(|id| board.stroke(id))
+        .find(|stroke| pencil.author.is_none() || stroke.author == pencil.author)?;
+    let resize = stroke.is_resizable() && stroke.points.last().is_some_and(|end| end.distance_to(point) <= BOARD_HANDLE_SIZE);
+    Some(Gesture::Moving {
+        stroke: stroke.clone(),
+        from: *point,
+        resize,
+    })
+}
+
+/// Turns pointer input from a mouse, a finger or a pen into board operations.
+/// Drawing itself happens when the operation comes back through the store.
+/// The wheel zooms, and dragging with the middle button, with Shift or on a
+/// read-only board scrolls; this only changes the local view.
+pub(super) fn draw_action(
+    canvas: &HtmlCanvasElement,
+    pencil: Rc<RefCell<Pencil>>,
+    viewport: Rc<RefCell<Viewport>>,
+    on_operation: Callback<BoardOperation>,
+) {
+    let pointers = Rc::new(RefCell::new(Pointers::default()));
 
-fn _client_action(canvas: &web_sys::HtmlCanvasElement, context: web_sys::CanvasRenderingContext2d, send_message_all_cb: Callback<Message>) {
-    let context = Rc::new(context);
-    let pressed = Rc::new(Cell::new(false));
-       
     {
-        let context = context.clone();
-        let pressed = pressed.clone();
-        let closure = Closure::<dyn FnMut(_)>::new(move |event: web_sys::MouseEvent| {
-            context.begin_path();
-            context.move_to(event.offset_x() as f64, event.offset_y() as f64);
-            pressed.set(true);
+        let pointers = pointers.clone();
+        let pencil = pencil.clone();
+        let viewport = viewport.clone();
+        let canvas_ref = canvas.clone();
+        let on_operation = on_operation.clone();
+        let closure = Closure::<dyn FnMut(_)>::new(move |event: PointerEvent| {
+            let now = js_sys::Date::now();
+            let mut pointers = pointers.borrow_mut();
+            if pointers.is_palm(&event, now) || pointers.active.is_some() {
+                return;
+            }
+            if event.pointer_type() == "pen" {
+                pointers.pen_at = Some(now);
+            }
+            event.prevent_default();
+            pointers.active = Some(event.pointer_id());
+            let _ = canvas_ref.set_pointer_capture(event.pointer_id());
+            let (brush, author, can_draw, zoomable) = {
+                let pencil = pencil.borrow();
+                (pencil.brush.clone(), pencil.author.clone(), pencil.can_draw, pencil.zoomable)
+            };
+            if zoomable && (event.button() == 1 || event.shift_key() || !can_draw) {
+                pointers.gesture = Gesture::Panning(event.offset_x() as f64, event.offset_y() as f64);
+                return;
+            }
+            if !can_draw {
+                pointers.active = None;
+                return;
+            }
+            let point = event_point(&event, &viewport);
+            match brush.tool {
+                Tool::Eraser => {
+                    pointers.gesture = Gesture::Erasing;
+                    erase(&pencil, &point, &on_operation);
+                },
+                Tool::Move => {
+                    match grab(&pencil.borrow(), &point) {
+                        Some(gesture) => pointers.gesture = gesture,
+                        None => pointers.active = None,
+                    }
+                },
+                Tool::Text => {
+                    pointers.active = None;
+                    if let Some(text) = ask_text() {
+                        let mut stroke = Stroke::text_box(brush.color.clone(), brush.width, point, text);
+                        stroke.set_author(author);
+                        on_operation.emit(BoardOperation::Add { stroke });
+                    }
+                },
+                _ => {
+                    let mut stroke = brush.stroke(point);
+                    stroke.set_author(author);
+                    pointers.gesture = Gesture::Drawing(stroke.clone());
+                    on_operation.emit(BoardOperation::Add { stroke });
+                },
+            }
         });
-        canvas.add_event_listener_with_callback("mousedown", closure.as_ref().unchecked_ref()).expect("error add event listener paint mousedown");
+        canvas.add_event_listener_with_callback("pointerdown", closure.as_ref().unchecked_ref()).expect("error add event listener paint pointerdown");
         closure.forget();
     }
     {
-        let context = context.clone();
-        let pressed = pressed.clone();
-        let closure = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
-            if pressed.get() {
-                context.line_to(event.offset_x() as f64, event.offset_y() as f64);
-                context.stroke();
-                context.begin_path();
-                context.move_to(event.offset_x() as f64, event.offset_y() as f64);
-                send_message_all_cb.emit(
-                    Message::HostPaint { 
-                        offset_x: event.offset_x() as f64,
-                        offset_y: event.offset_y() as f64,
-                        action: PaintAction::Move
-                    }
-                )
+        let pointers = pointers.clone();
+        let pencil = pencil.clone();
+        let viewport = viewport.clone();
+        let canvas_ref = canvas.clone();
+        let on_operation = on_operation.clone();
+        let closure = Closure::<dyn FnMut(_)>::new(move |event: PointerEvent| {
+            let mut pointers = pointers.borrow_mut();
+            if event.pointer_type() == "pen" {
+                pointers.pen_at = Some(js_sys::Date::now());
+            }
+            if !pointers.is_active(&event) {
+                return;
+            }
+            let point = event_point(&event, &viewport);
+            match &mut pointers.gesture {
+                Gesture::Drawing(stroke) if stroke.tool.is_shape() => {
+                    stroke.set_end(point);
+                    on_operation.emit(BoardOperation::Update { stroke: stroke.clone() });
+                },
+                Gesture::Drawing(stroke) => {
+                    on_operation.emit(BoardOperation::Extend { id: stroke.id.clone(), points: vec![point] });
+                },
+                Gesture::Erasing => {
+                    erase(&pencil, &point, &on_operation);
+                },
+                Gesture::Moving { stroke, from, resize } => {
+                    let points = if *resize {
+                        let mut resized = stroke.clone();
+                        resized.set_end(Point::new(point.x, point.y));
+                        resized.points
+                    } else {
+                        stroke.moved_by(point.x - from.x, point.y - from.y)
+                    };
+                    on_operation.emit(BoardOperation::Move { id: stroke.id.clone(), points });
+                },
+                Gesture::Panning(x, y) => {
+                    let (to_x, to_y) = (event.offset_x() as f64, event.offset_y() as f64);
+                    viewport.borrow_mut().pan_by(to_x - *x, to_y - *y);
+                    (*x, *y) = (to_x, to_y);
+                    render(&canvas_ref, &pencil.borrow().board, &mut viewport.borrow_mut());
+                },
+                Gesture::Idle => {},
+            }
+        });
+        canvas.add_event_listener_with_callback("pointermove", closure.as_ref().unchecked_ref()).expect("error add event listener paint pointermove");
+        closure.forget();
+    }
+    for event_type in ["pointerup", "pointercancel"] {
+        let pointers = pointers.clone();
+        let closure = Closure::<dyn FnMut(_)>::new(move |event: PointerEvent| {
+            let mut pointers = pointers.borrow_mut();
+            if pointers.is_active(&event) {
+                pointers.gesture = Gesture::Idle;
+                pointers.active = None;
             }
-        }) as Box<dyn FnMut(_)>);
-        canvas.add_event_listener_with_callback("mousemove", closure.as_ref().unchecked_ref()).expect("error add event listener pain mousemove");
+        });
+        canvas.add_event_listener_with_callback(event_type, closure.as_ref().unchecked_ref()).expect("error add event listener paint pointerup");
         closure.forget();
     }
     {
-        let context = context.clone();
-        let closure = Closure::<dyn FnMut(_)>::new(move |event: web_sys::MouseEvent| {
-            context.line_to(event.offset_x() as f64, event.offset_y() as f64);
-            context.stroke();
-            pressed.set(false);
+        let canvas_ref = canvas.clone();
+        let closure = Closure::<dyn FnMut(_)>::new(move |event: WheelEvent| {
+            if !pencil.borrow().zoomable {
+                return;
+            }
+            event.prevent_default();
+            let factor = if event.delta_y() < 0.0 { BOARD_ZOOM_STEP } else { 1.0 / BOARD_ZOOM_STEP };
+            let mut viewport = viewport.borrow_mut();
+            viewport.zoom_at(factor, event.offset_x() as f64, event.offset_y() as f64);
+            render(&canvas_ref, &pencil.borrow().board, &mut viewport);
         });
-        canvas.add_event_listener_with_callback("mouseup", closure.as_ref().unchecked_ref()).expect("error add event listener paint mouseup");
+        canvas.add_event_listener_with_callback("wheel", closure.as_ref().unchecked_ref()).expect("error add event listener paint wheel");
         closure.forget();
     }
 }
-
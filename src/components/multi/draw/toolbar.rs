@@ -0,0 +1,126 @@
+use wasm_bindgen::JsCast;
+use web_sys::{File, HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+use crate::{constants::BOARD_WIDTHS, models::{board::{Brush, Tool}, history::HistoryAction}};
+
+#[derive(PartialEq, Properties)]
+pub struct BoardToolbarProps {
+    pub brush: Brush,
+    pub on_change: Callback<Brush>,
+    #[prop_or_default]
+    pub can_undo: bool,
+    #[prop_or_default]
+    pub can_redo: bool,
+    /// Undo and redo are only shown with it.
+    #[prop_or_default]
+    pub on_history: Option<Callback<HistoryAction>>,
+    /// Placing a picture is only offered with it.
+    #[prop_or_default]
+    pub on_image: Option<Callback<File>>,
+}
+
+/// Tool, color and width of the host's brush, undo/redo and pictures of the board.
+#[function_component(BoardToolbar)]
+pub fn board_toolbar(props: &BoardToolbarProps) -> Html {
+    let BoardToolbarProps {
+        brush,
+        on_change,
+        can_undo,
+        can_redo,
+        on_history,
+        on_image,
+    } = props;
+
+    let tools = Tool::ALL
+        .iter()
+        .map(|tool| {
+            let onclick = {
+                let brush = brush.clone();
+                let on_change = on_change.clone();
+                let tool = *tool;
+                Callback::from(move |_| {
+                    let mut brush = brush.clone();
+                    brush.set_tool(tool);
+                    on_change.emit(brush);
+                })
+            };
+            let class = if brush.tool == *tool { "active" } else { "" };
+            html! {
+                <button { class } title={ tool.title() } { onclick }>{ tool.icon() }</button>
+            }
+        })
+        .collect::<Html>();
+
+    let on_color = {
+        let brush = brush.clone();
+        let on_change = on_change.clone();
+        Callback::from(move |e: Event| {
+            let color = e
+                .target()
+                .expect("Event should have a target when dispatched")
+                .unchecked_into::<HtmlInputElement>()
+                .value();
+            let mut brush = brush.clone();
+            brush.set_color(color);
+            on_change.emit(brush);
+        })
+    };
+    let on_width = {
+        let brush = brush.clone();
+        let on_change = on_change.clone();
+        Callback::from(move |e: Event| {
+            let width = e
+                .target()
+                .expect("Event should have a target when dispatched")
+                .unchecked_into::<HtmlSelectElement>()
+                .value()
+                .parse::<f64>();
+            if let Ok(width) = width {
+                let mut brush = brush.clone();
+                brush.set_width(width);
+                on_change.emit(brush);
+            }
+        })
+    };
+    let image_change = |on_image: &Callback<File>| {
+        let on_image = on_image.clone();
+        Callback::from(move |e: Event| {
+            let input = e
+                .target()
+                .expect("Event should have a target when dispatched")
+                .unchecked_into::<HtmlInputElement>();
+            if let Some(file) = input.files().and_then(|files| files.get(0)) {
+                on_image.emit(file);
+            }
+            // the same file can be chosen again
+            input.set_value("");
+        })
+    };
+    let history_click = |on_history: &Callback<HistoryAction>, action: HistoryAction| {
+        let on_history = on_history.clone();
+        Callback::from(move |_| on_history.emit(action))
+    };
+
+    html! {
+        <div class="board-toolbar">
+            { tools }
+            <input type="color" title="Цвет" value={ brush.color.clone() } onchange={ on_color } />
+            <select title="Толщина" onchange={ on_width }>
+                { for BOARD_WIDTHS.iter().map(|width| html! {
+                    <option value={ width.to_string() } selected={ brush.width == *width }>{ format!("{} px", width) }</option>
+                }) }
+            </select>
+            if let Some(on_history) = on_history {
+                <button title="Отменить" disabled={ !can_undo } onclick={ history_click(on_history, HistoryAction::Undo) }>{ "↶" }</button>
+                <button title="Повторить" disabled={ !can_redo } onclick={ history_click(on_history, HistoryAction::Redo) }>{ "↷" }</button>
+            }
+            if let Some(on_image) = on_image {
+                <label class="board-image" title={ Tool::Image.title() }>
+                    { Tool::Image.icon() }
+                    <input type="file" accept="image/*" onchange={ image_change(on_image) } />
+                </label>
+            }
+        </div>
+    }
+}
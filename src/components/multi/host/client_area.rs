@@ -5,7 +5,7 @@ use web_sys::{InputEvent, HtmlTextAreaElement};
 use yew::prelude::*;
 use yewdux::prelude::*;
 
-use crate::{models::commons::AreaKind, components::editor::editor::EditorWrapper, stores::client_props_store::{ClientPropsStore, HostClientMsg}};
+use crate::{utils::dom::history_shortcut, models::{commons::AreaKind, presence::EditorCursor, review::ReviewThread, workspace::{DocumentKey, Workspace, WorkspaceAction}}, components::editor::{diff::DiffView, editor::EditorWrapper, review::ReviewPanel, workspace::WorkspaceBar}, stores::{client_props_store::{ClientPropsStore, HostClientMsg}, host_props_store::HostPropsStore}};
 
 const TEXTAREA_ID_CLIENT: &str = "client-textarea";
 
@@ -14,10 +14,28 @@ pub enum Msg {
     Tick,
 }
 
+#[derive(PartialEq, Clone, Copy)]
+enum DiffSource {
+    Host,
+    Reference,
+}
+
+// The file with the same path as the student's one, or the open one if there is none.
+fn original_content(source: &Workspace, path: &str) -> String {
+    source
+        .file_by_path(path)
+        .or_else(|| source.active_file())
+        .map(|file| file.content.clone())
+        .unwrap_or_default()
+}
+
 #[function_component(ClientArea)]
 pub fn client_area() -> Html {
 
     let (state, dispatch) = use_store::<ClientPropsStore>();     
+    let (host_state, _host_dispatch) = use_store::<HostPropsStore>();
+    let diff_source = use_state(|| Option::<DiffSource>::None);
+    let host_cursor = use_mut_ref(|| Option::<EditorCursor>::None);
 
     let on_host_editor_cb = {
         let dispatch = dispatch.clone();
@@ -28,12 +46,118 @@ pub fn client_area() -> Html {
         let area_kind = state.get_client_props().client_area_kind;
         match area_kind {
             AreaKind::Editor => {
-                let text_model = TextModel::create(&state.get_client_props().client_editor_content, Some("java"), None).unwrap();
+                let workspace = state.get_client_props().workspace.clone();
+                let settings = host_state.get_host_props().editor_settings.clone();
+                let text_model = TextModel::create(workspace.active_content(), Some(&settings.language), None).unwrap();
+                let on_workspace_action = {
+                    let dispatch = dispatch.clone();
+                    Callback::from(move |action| {
+                        if let WorkspaceAction::Open { id } = action {
+                            dispatch.apply(HostClientMsg::OpenFile(id));
+                        }
+                    })
+                };
        
+                let on_cursor = {
+                    let dispatch = dispatch.clone();
+                    let host_cursor = host_cursor.clone();
+                    Callback::from(move |cursor: EditorCursor| {
+                        *host_cursor.borrow_mut() = Some(cursor.clone());
+                        dispatch.apply(HostClientMsg::SendCursor(cursor));
+                    })
+                };
+                let comment_click = {
+                    let dispatch = dispatch.clone();
+                    let host_cursor = host_cursor.clone();
+                    let active_file = workspace.active_file().cloned();
+                    Callback::from(move |_| {
+                        let (start_line, end_line) = host_cursor
+                            .borrow()
+                            .as_ref()
+                            .map_or((1, 1), EditorCursor::selected_lines);
+                        let text = web_sys::window()
+                            .and_then(|window| window.prompt_with_message("Комментарий к выделенным строкам").ok().flatten())
+                            .map(|text| text.trim().to_owned())
+                            .filter(|text| !text.is_empty());
+                        if let (Some(file), Some(text)) = (&active_file, text) {
+                            dispatch.apply(HostClientMsg::AddReviewThread(ReviewThread::new(file, start_line, end_line, text)));
+                        }
+                    })
+                };
+                let on_reply = {
+                    let dispatch = dispatch.clone();
+                    Callback::from(move |(thread_id, text): (String, String)| dispatch.apply(HostClientMsg::ReviewReply(thread_id, text)))
+                };
+                let review = state.get_client_props().active_review();
+                let on_history = {
+                    let dispatch = dispatch.clone();
+                    let document = workspace.active_key();
+                    Callback::from(move |action| dispatch.apply(HostClientMsg::History(document.clone(), action)))
+                };
                 let is_write = state.get_client_props().is_write;
+                let remote_cursor = state.get_client_props().remote_cursor
+                    .clone()
+                    .filter(|remote_cursor| remote_cursor.file_id == workspace.active);
+                let diff_click = |source: Option<DiffSource>| {
+                    let diff_source = diff_source.clone();
+                    Callback::from(move |_| diff_source.set(source))
+                };
+                let reference = host_state.get_host_props().reference.clone();
+                let button_class = |source: Option<DiffSource>| {
+                    if *diff_source == source { "active" } else { "" }
+                };
+                let content = match *diff_source {
+                    Some(source) => {
+                        let student = workspace.active_content().to_owned();
+                        let path = workspace.active_file().map(|file| file.path.clone()).unwrap_or_default();
+                        let (original, original_title) = match (source, &reference) {
+                            (DiffSource::Reference, Some(reference)) => (original_content(reference, &path), "Эталон"),
+                            _ => (original_content(&host_state.get_host_props().workspace, &path), "Учитель"),
+                        };
+                        html! {
+                            <DiffView
+                                { original }
+                                modified={ student }
+                                original_title={ original_title }
+                                modified_title={ "Ученик" }
+                            />
+                        }
+                    },
+                    None => html! {
+                        <EditorWrapper
+                            key={ workspace.active.clone() }
+                            on_cb={ on_host_editor_cb.clone() }
+                            text_model={ text_model.clone() }
+                            is_write={ is_write }
+                            { on_cursor }
+                            { remote_cursor }
+                            { settings }
+                            review={ review.clone() }
+                            on_history={ Some(on_history) }
+                        />
+                    },
+                };
                 html! {
                     <div class="document">
-                        <EditorWrapper on_cb={ on_host_editor_cb.clone() } text_model={ text_model.clone() } is_write={ is_write }/>
+                        <div class="diff-toolbar">
+                            <button class={ button_class(None) } onclick={ diff_click(None) }>{ "Код" }</button>
+                            <button class={ button_class(Some(DiffSource::Host)) } onclick={ diff_click(Some(DiffSource::Host)) }>
+                                { "Сравнить с учителем" }
+                            </button>
+                            <button
+                                class={ button_class(Some(DiffSource::Reference)) }
+                                disabled={ reference.is_none() }
+                                onclick={ diff_click(Some(DiffSource::Reference)) }
+                            >
+                                { "Сравнить с эталоном" }
+                            </button>
+                            <button title="Выделите строки в коде ученика" disabled={ diff_source.is_some() } onclick={ comment_click }>
+                                { "Комментировать" }
+                            </button>
+                        </div>
+                        <WorkspaceBar workspace={ workspace.clone() } on_action={ on_workspace_action } />
+                        { content }
+                        <ReviewPanel threads={ review } { on_reply } />
                     </div>
                 }
             },
@@ -47,11 +171,20 @@ pub fn client_area() -> Html {
                         .value();
                     on_host_editor_cb.emit(content);
                 });
+                let onkeydown = {
+                    let dispatch = dispatch.clone();
+                    Callback::from(move |e: KeyboardEvent| {
+                        if let Some(action) = history_shortcut(&e) {
+                            e.prevent_default();
+                            dispatch.apply(HostClientMsg::History(DocumentKey::TextArea, action));
+                        }
+                    })
+                };
                 let value = state.get_client_props().client_text_area.content.clone();
 
                 html! {
                     <div class="col document">
-                        <textarea id={ TEXTAREA_ID_CLIENT } value={ value } { oninput } class="document" cols="100" rows="30" />
+                        <textarea id={ TEXTAREA_ID_CLIENT } value={ value } { oninput } { onkeydown } class="document" cols="100" rows="30" />
                     </div>
                 }
             },
@@ -4,13 +4,15 @@ use yew::{Properties, html, Html, Callback, function_component, use_effect};
 use yew_icons::{Icon, IconId};
 use yewdux::prelude::use_store;
 
-use crate::{utils::dom::{create_video_id, get_element}, models::commons::AreaKind, stores::{client_items_store::{ClientItemsStore, ClientItemMsg}, host_store::HostStore}};
+use crate::{utils::dom::{create_video_id, format_time, get_element}, models::{assignment::SubmissionStatus, commons::AreaKind}, stores::{client_items_store::{ClientItemsStore, ClientItemMsg}, host_props_store::{HostHostMsg, HostPropsStore}, host_store::HostStore}};
 
 
 #[derive(Properties, PartialEq)]
 pub struct ItemPorps {
     pub key_id: UserId,
     pub value: String,
+    #[prop_or_default]
+    pub status: Option<SubmissionStatus>,
 }
 
 
@@ -18,6 +20,7 @@ pub struct ItemPorps {
 pub fn client_box(props: &ItemPorps) -> Html {
     let (_state, dispatch) = use_store::<ClientItemsStore>();
     let (global_state, _global_dispatch) = use_store::<HostStore>();
+    let (host_state, host_dispatch) = use_store::<HostPropsStore>();
     let key_id = props.key_id.clone();
     let key = key_id.to_string();
     let value = props.value.clone();
@@ -63,15 +66,44 @@ pub fn client_box(props: &ItemPorps) -> Html {
             dispatch.apply(ClientItemMsg::SwitchSpeakers(speakers_id.clone()));
         })
     };
+    let can_draw = host_state.get_board_permission().allows(&key);
+    let on_board_access = {
+        let host_dispatch = host_dispatch.clone();
+        Callback::from(move |_| {
+            host_dispatch.apply(HostHostMsg::SetStudentBoardAccess(key_id, !can_draw));
+        })
+    };
+    let on_clear_board = {
+        let host_dispatch = host_dispatch.clone();
+        Callback::from(move |_| {
+            host_dispatch.apply(HostHostMsg::ClearStudentBoard(key_id));
+        })
+    };
+    let status = match props.status {
+        Some(SubmissionStatus::Submitted(submitted_at)) => html! {
+            <div class="submission-status submitted">{ format!("Сдано в {}", format_time(submitted_at)) }</div>
+        },
+        Some(SubmissionStatus::Pending) => html! {
+            <div class="submission-status">{ "В работе" }</div>
+        },
+        None => html! {},
+    };
     html! {
         <>
             <div key={ key.clone() } class="item-box">
+                { status }
                 <div id={ box_id } client_id={ client_id.clone() } class="col" onclick={ item_click.clone() }>
                     <textarea id={ key } client_id={ client_id.clone() } value={ value } class="doc-item" cols="100" rows="30" />
                     // <video id={ video_id } client_id={ client_id.clone() } autoplay=true class="item-canvas"></video>
                     <div class="col">
                         <button onclick={ on_switch_video } client_id={ client_id.clone() } >{"video ->"}</button>
                         <button onclick={ on_switch_speakers } client_id={ client_id.clone() }>{"audio ->"}</button>
+                        <button class={ if can_draw { "active" } else { "" } } onclick={ on_board_access } client_id={ client_id.clone() }>
+                            { if can_draw { "Запретить рисовать" } else { "Разрешить рисовать" } }
+                        </button>
+                        if host_state.get_board().is_some() {
+                            <button onclick={ on_clear_board } client_id={ client_id.clone() }>{ "Стереть рисунки" }</button>
+                        }
                     </div>
                     // <canvas id={ video_id } client_id={ client_id } class="item-canvas vis" ></canvas>
                     <div id={ client_logo_id } class="unvis">
@@ -98,18 +130,19 @@ pub fn client_items() -> Html {
             .map(|key| {
             match players.get(&key) {
                 Some(client_item) => {
+                    let status = state.get_status(&key);
                     match client_item.area_kind {
                         AreaKind::Editor => {
                             html! {
                                 <>
-                                    <ClientBox key_id={ key } value={ client_item.editor_content.clone() } />
+                                    <ClientBox key_id={ key } value={ client_item.editor_content().to_owned() } { status } />
                                 </>
                             }
                         },
                         AreaKind::TextArea => {
                             html! {
                                 <>
-                                    <ClientBox key_id={ key } value={ client_item.text_area_content.clone() } />
+                                    <ClientBox key_id={ key } value={ client_item.text_area_content.clone() } { status } />
                                 </>
                             }
                             
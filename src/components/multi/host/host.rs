@@ -6,10 +6,15 @@ use crate::components::common::battons::{VideoButton, AudioButton};
 use crate::components::common::video::VideoBox;
 use crate::components::multi::host::client_area::ClientArea;
 use crate::components::multi::host::client_items::ClientItems;
+use crate::components::multi::draw::annotation::Annotation;
 use crate::components::multi::host::host_area::HostArea;
-use crate::constants::VIDEO_ELEMENT_ID;
+use crate::constants::{SCREEN_SHARE_ELEMENT_ID, VIDEO_ELEMENT_ID};
+use crate::models::laser::{LaserPoint, LaserSurface};
 use crate::media_devices::device_selector::DeviceSelector;
+use crate::stores::host_props_store::{HostHostMsg, HostPropsStore};
 use crate::stores::host_store::{HostStore, self};
+use crate::stores::laser_store::{LaserMsg, LaserStore};
+use crate::utils::dom::pointer_fraction;
 use crate::stores::media_store::{MediaStore, HostMediaMsg};
 
 pub enum Msg {
@@ -45,15 +50,37 @@ pub fn devices() -> Html {
 #[function_component(ScreenShare)]
 pub fn screen_share() -> Html {
     let (_state, dispatch) = use_store::<MediaStore>();
+    let (host_state, host_dispatch) = use_store::<HostPropsStore>();
+    let (laser_state, laser_dispatch) = use_store::<LaserStore>();
     let screen_share_cb = {
         Callback::from(move |_| {
             log::error!("in sreen btn");
             dispatch.apply(HostMediaMsg::EnableScreenShare(true));
         })
     };
+    let on_operation = Callback::from(move |operation| host_dispatch.apply(HostHostMsg::Annotation(operation)));
+    let on_laser_move = laser_state.is_enabled().then(|| {
+        let laser_dispatch = laser_dispatch.clone();
+        Callback::from(move |e: PointerEvent| {
+            let point = pointer_fraction(&e).map(|(x, y)| LaserPoint { surface: LaserSurface::Screen, x, y });
+            laser_dispatch.apply(LaserMsg::Move(point));
+        })
+    });
+    let on_laser_leave = laser_state.is_enabled().then(|| {
+        let laser_dispatch = laser_dispatch.clone();
+        Callback::from(move |_: PointerEvent| laser_dispatch.apply(LaserMsg::Move(None)))
+    });
+    let annotation = host_state.get_annotation();
     html! {
         <div>
             <button onclick={ screen_share_cb }>{"Демонстрация экрана"}</button>
+            // the video stays in the page so that the encoder finds it before sharing starts
+            <div class={ classes!("screen-share", annotation.is_none().then_some("unvis")) } onpointermove={ on_laser_move } onpointerleave={ on_laser_leave }>
+                <video id={ SCREEN_SHARE_ELEMENT_ID } autoplay=true muted=true class="screen_canvas"></video>
+                if let Some(board) = annotation {
+                    <Annotation board={ board.clone() } { on_operation } is_host={ true } />
+                }
+            </div>
         </div>
     }
 }
@@ -1,10 +1,10 @@
 use monaco::api::TextModel;
-use web_sys::{InputEvent, MouseEvent};
+use web_sys::{InputEvent, MouseEvent, PointerEvent};
 use yew::{html, Callback, Html, function_component};
 use yew_icons::{Icon, IconId};
 use yewdux::prelude::{use_store, Dispatch};
 
-use crate::{models::commons::AreaKind, components::{editor::editor::EditorWrapper, multi::draw::paint::PaintF}, stores::{host_props_store::{HostHostMsg, HostPropsStore}, host_store::{self, HostStore}}, utils::inputs::Message};
+use crate::{models::{board::PageAction, commons::{AreaKind, LockMode}, laser::{LaserPoint, LaserSurface}}, components::{editor::{editor::EditorWrapper, settings::EditorSettingsBar, workspace::WorkspaceBar}, multi::draw::{pages::PageBar, paint::PaintF}}, stores::{client_items_store::{ClientItemMsg, ClientItemsStore}, host_props_store::{HostHostMsg, HostPropsStore}, laser_store::{LaserMsg, LaserStore}}, utils::dom::pointer_fraction};
 
 
 const TEXTAREA_ID: &str = "document-textarea";
@@ -13,7 +13,7 @@ const TEXTAREA_ID: &str = "document-textarea";
 #[function_component(HostArea)]
 pub fn host_area() -> Html {
     let (state, dispatch) = use_store::<HostPropsStore>();
-    let global_dispatch = Dispatch::<HostStore>::new();
+    let (laser_state, laser_dispatch) = use_store::<LaserStore>();
     let render = || {
         let area_kind = state.get_host_props().host_area_kind;
         match area_kind {
@@ -22,10 +22,26 @@ pub fn host_area() -> Html {
                     let dispatch = dispatch.clone();
                     Callback::from(move |content: String| dispatch.apply(HostHostMsg::HostUpdateValue(content)))
                 };
-                let text_model = TextModel::create(&state.get_host_props().host_editor_content, Some("java"), None).unwrap();
+                let on_workspace_action = {
+                    let dispatch = dispatch.clone();
+                    Callback::from(move |action| dispatch.apply(HostHostMsg::Workspace(action)))
+                };
+                let on_settings_change = {
+                    let dispatch = dispatch.clone();
+                    Callback::from(move |settings| dispatch.apply(HostHostMsg::SetEditorSettings(settings)))
+                };
+                let on_scroll = {
+                    let dispatch = dispatch.clone();
+                    Callback::from(move |line| dispatch.apply(HostHostMsg::Scroll(line)))
+                };
+                let workspace = state.get_host_props().workspace.clone();
+                let settings = state.get_host_props().editor_settings.clone();
+                let text_model = TextModel::create(state.get_host_props().editor_content(), Some(&settings.language), None).unwrap();
                 html! {
                     <div class="document">
-                        <EditorWrapper on_cb={ on_host_editor_cb.clone() } text_model={ text_model.clone() } is_write={ false }/>
+                        <EditorSettingsBar settings={ settings.clone() } on_change={ on_settings_change } />
+                        <WorkspaceBar workspace={ workspace.clone() } on_action={ on_workspace_action } editable={ true } />
+                        <EditorWrapper key={ workspace.active.clone() } on_cb={ on_host_editor_cb.clone() } text_model={ text_model.clone() } is_write={ false } { settings } { on_scroll }/>
                     </div>
                 }
             },
@@ -47,37 +63,57 @@ pub fn host_area() -> Html {
     };
 
     let render_paints = || {
-        let send_message_all_cb = {
-            let global_dispatch = global_dispatch.clone();
-            Callback::from(move |message: Message| {
-                global_dispatch.apply(host_store::Msg::SendMessage(message));
-            })
+        let on_operation = {
+            let dispatch = dispatch.clone();
+            Callback::from(move |operation| dispatch.apply(HostHostMsg::Board(operation)))
         };
-        let out = state.get_paints_f()
-            .values()
-            .map(|content| {
-                html! {
-                    <>
-                        <PaintF
-                            content={ content.clone() }
-                            send_message_all_cb={send_message_all_cb.clone()}
-                            is_host={ true }
-
-                        />
-                    </>
-                }
-            })
-            .collect::<Html>();
-        out
+        let on_show = {
+            let dispatch = dispatch.clone();
+            Callback::from(move |id| dispatch.apply(HostHostMsg::BoardPage(PageAction::Show { id })))
+        };
+        let on_page_action = {
+            let dispatch = dispatch.clone();
+            Callback::from(move |action| dispatch.apply(HostHostMsg::BoardPage(action)))
+        };
+        let on_add = {
+            let dispatch = dispatch.clone();
+            Callback::from(move |_| dispatch.apply(HostHostMsg::AddBoardPage))
+        };
+        let Some(board) = state.get_board() else {
+            return html! {};
+        };
+        match board.current_page() {
+            Some(page) => html! {
+                <>
+                    <PageBar board={ board.clone() } shown={ board.current.clone() } { on_show } on_action={ on_page_action } { on_add } editable={ true } />
+                    <PaintF board={ page.clone() } { on_operation } is_host={ true } />
+                </>
+            },
+            None => html! {},
+        }
     };
 
 
+    let on_laser_move = laser_state.is_enabled().then(|| {
+        let laser_dispatch = laser_dispatch.clone();
+        Callback::from(move |e: PointerEvent| {
+            let point = pointer_fraction(&e).map(|(x, y)| LaserPoint { surface: LaserSurface::Document, x, y });
+            laser_dispatch.apply(LaserMsg::Move(point));
+        })
+    });
+    let on_laser_leave = laser_state.is_enabled().then(|| {
+        let laser_dispatch = laser_dispatch.clone();
+        Callback::from(move |_: PointerEvent| laser_dispatch.apply(LaserMsg::Move(None)))
+    });
+
     html! {
         <>
             <div class="host-box">
                 <HostButtonBar />
                 <div class="host-content-box">
-                    { render() }
+                    <div class="laser-area" onpointermove={ on_laser_move } onpointerleave={ on_laser_leave }>
+                        { render() }
+                    </div>
                     <div id="host-paint" class="host-paint">
                         { render_paints() }
                     </div>
@@ -94,6 +130,7 @@ pub fn host_area() -> Html {
 #[function_component(HostButtonBar)]
 pub fn host_button_bar() -> Html {
     let (state, dispatch) = use_store::<HostPropsStore>();
+    let (laser_state, laser_dispatch) = use_store::<LaserStore>();
 
     let editor_click = {
         let dispatch = dispatch.clone();
@@ -119,6 +156,60 @@ pub fn host_button_bar() -> Html {
             dispatch.apply(HostHostMsg::OnCummunication);
         }
     };
+    let distribute_click = {
+        let dispatch = dispatch.clone();
+        move |_e: MouseEvent| {
+            let title = web_sys::window()
+                .and_then(|window| window.prompt_with_message("Название задания").ok().flatten())
+                .filter(|title| !title.trim().is_empty());
+            if let Some(title) = title {
+                dispatch.apply(HostHostMsg::DistributeAssignment(title));
+            }
+        }
+    };
+    let reference_click = {
+        let dispatch = dispatch.clone();
+        move |_e: MouseEvent| {
+            dispatch.apply(HostHostMsg::SaveReference);
+        }
+    };
+    let bookmark_click = {
+        let dispatch = dispatch.clone();
+        move |_e: MouseEvent| {
+            let title = web_sys::window()
+                .and_then(|window| window.prompt_with_message("Название закладки").ok().flatten())
+                .filter(|title| !title.trim().is_empty());
+            if let Some(title) = title {
+                dispatch.apply(HostHostMsg::AddBookmark(title));
+            }
+        }
+    };
+    let recording_click = {
+        let dispatch = dispatch.clone();
+        move |_e: MouseEvent| {
+            dispatch.apply(HostHostMsg::ExportRecording);
+        }
+    };
+    let export_click = move |_e: MouseEvent| {
+        Dispatch::<ClientItemsStore>::new().apply(ClientItemMsg::ExportSubmissions);
+    };
+    let is_laser = laser_state.is_enabled();
+    let laser_click = move |_e: MouseEvent| {
+        laser_dispatch.apply(LaserMsg::SetEnabled(!is_laser));
+    };
+    let lock_mode = state.get_host_props().lock_mode;
+    let lock_button = |mode: LockMode, title: &'static str, icon_id: IconId| {
+        let dispatch = dispatch.clone();
+        let onclick = move |_e: MouseEvent| {
+            dispatch.apply(HostHostMsg::SetLock(mode));
+        };
+        let class = if mode == lock_mode { "active" } else { "" };
+        html! {
+            <button { class } { title } { onclick }>
+                <Icon { icon_id }/>
+            </button>
+        }
+    };
 
     html! {
         <>
@@ -131,6 +222,19 @@ pub fn host_button_bar() -> Html {
             <button>
                 <Icon icon_id={IconId::HeroiconsSolidPaintBrush} onclick={ paint_click }/>
             </button>
+            <button title="Раздать задание" onclick={ distribute_click }>
+                <Icon icon_id={IconId::BootstrapSend}/>
+            </button>
+            <button title="Сохранить код как эталон" onclick={ reference_click }>{ "Эталон" }</button>
+            <button title="Скачать работы" onclick={ export_click }>
+                <Icon icon_id={IconId::BootstrapDownload}/>
+            </button>
+            <button title="Поставить закладку в записи урока" onclick={ bookmark_click }>{ "Закладка" }</button>
+            <button title="Скачать запись урока" onclick={ recording_click }>{ "Запись" }</button>
+            <button class={ if is_laser { "active" } else { "" } } title="Показывать ученикам указатель вместо рисования" onclick={ laser_click }>{ "Указка" }</button>
+            { lock_button(LockMode::Unlocked, "Разрешить ученикам редактировать", IconId::BootstrapUnlock) }
+            { lock_button(LockMode::Locked, "Запретить ученикам редактировать", IconId::BootstrapLockFill) }
+            { lock_button(LockMode::FollowHost, "Ученики следят за учителем", IconId::BootstrapEyeFill) }
             <button onclick={ on_communication }>
                 { 
                     if state.get_host_props().is_communication.clone() {
@@ -1,14 +1,15 @@
-use std::{collections::HashMap, cell::RefCell, rc::Rc, sync::Arc};
+use std::{collections::HashMap, cell::RefCell, rc::Rc};
 
 use wasm_peers::{UserId, one_to_many::MiniServer, SessionId, ConnectionType};
 
-use crate::{models::{client::ClientItem, video::Video, audio::Audio}, stores::host_store, utils::{dom::create_video_id, device::{create_video_decoder_video, VideoElementKind, create_audio_decoder}, inputs::ClientMessage}};
+use crate::{models::{client::ClientItem, video::Video, audio::Audio, laser::LaserSignal}, stores::host_store, utils::{dom::create_video_id, device::{create_video_decoder_video, VideoElementKind, create_audio_decoder}, inputs::{ClientMessage, Message}, laser_channel::LaserChannel}};
 
 #[derive(Clone, PartialEq)]
 pub struct HostManager {
     pub players: Rc<RefCell<HashMap<UserId, ClientItem>>>,
     pub video_decoders: Rc<RefCell<HashMap<UserId, Rc<RefCell<Video>>>>>,
     pub audio_decoders: Rc<RefCell<HashMap<UserId, Rc<RefCell<Audio>>>>>,
+    pub laser_channels: Rc<RefCell<HashMap<UserId, LaserChannel>>>,
     pub mini_server: MiniServer,
 }
 
@@ -28,11 +29,13 @@ impl HostManager {
         let players = Rc::new(RefCell::new(HashMap::new()));
         let video_decoders = Rc::new(RefCell::new(HashMap::new()));
         let audio_decoders = Rc::new(RefCell::new(HashMap::new()));
+        let laser_channels = Rc::new(RefCell::new(HashMap::new()));
         Self { 
             mini_server,
             players,
             video_decoders,
             audio_decoders,
+            laser_channels,
          }
     }
 
@@ -45,6 +48,7 @@ impl HostManager {
             let on_action = on_action.clone();
             let video_decoders = self.video_decoders.clone();
             let audio_decoders = self.audio_decoders.clone();
+            let laser_channels = self.laser_channels.clone();
             move |user_id: UserId| {
                 log::error!("add client {}", user_id.to_string());
                 let video_id = create_video_id(user_id.into_inner().to_string());
@@ -55,6 +59,21 @@ impl HostManager {
                     );
                 audio_decoders.borrow_mut()
                     .insert(user_id, Rc::new(RefCell::new(create_audio_decoder())));
+                let on_signal = {
+                    let on_action = on_action.clone();
+                    move |signal: LaserSignal| {
+                        let message = Message::HostLaserSignal { signal };
+                        on_action.borrow()(host_store::Msg::SendMessageToUser(user_id, message));
+                    }
+                };
+                match LaserChannel::offer(on_signal) {
+                    Ok(laser_channel) => {
+                        laser_channels.borrow_mut().insert(user_id, laser_channel);
+                    },
+                    Err(err) => {
+                        log::error!("cannot open laser channel {}: {:?}", user_id.to_string(), err);
+                    },
+                }
                 on_action.borrow()(host_store::Msg::AddClient(user_id));
             }
         };
@@ -63,6 +82,7 @@ impl HostManager {
             let on_action = on_action.clone();
             let video_decoders = self.video_decoders.clone();
             let audio_decoders = self.audio_decoders.clone();
+            let laser_channels = self.laser_channels.clone();
             move |user_id: UserId, message: ClientMessage| { 
                 match message {
                     ClientMessage::InitClient { 
@@ -79,7 +99,15 @@ impl HostManager {
                         let video = video_decoders.as_ref().borrow().get(&user_id).unwrap().clone();
                         match video.clone().as_ref().try_borrow_mut() {
                             Ok(mut video) => {
-                                let _ = video.decode_break(Arc::new(message));
+                                let _ = video.decode_break(message);
+                                if let Some(report) = video.take_report() {
+                                    let message = Message::HostVideoReport { report };
+                                    on_action.borrow()(host_store::Msg::SendMessageToUser(user_id, message));
+                                }
+                                if let Some(stream) = video.take_key_request() {
+                                    let message = Message::HostKeyframeRequest { stream };
+                                    on_action.borrow()(host_store::Msg::SendMessageToUser(user_id, message));
+                                }
                             },
                             Err(_) => {
                                 
@@ -98,16 +126,68 @@ impl HostManager {
                         on_action.borrow()(host_store::Msg::ClientSwitchVideo(user_id, message));
                     },
                     ClientMessage::ClientToClient { 
-                        message,
-                        area_kind
+                        revision,
+                        operation,
+                        document
+                    } => {
+                       on_action.borrow()(host_store::Msg::ClientToClient(user_id, revision, operation, document));                                                
+                    },
+                    ClientMessage::ClientWorkspace { 
+                        action
                     } => {
-                       on_action.borrow()(host_store::Msg::ClientToClient(user_id, message, area_kind));                                                
+                        on_action.borrow()(host_store::Msg::ClientWorkspace(user_id, action));
+                    },
+                    ClientMessage::ClientSubmit { 
+                        assignment_id,
+                        workspace
+                    } => {
+                        on_action.borrow()(host_store::Msg::ClientSubmit(user_id, assignment_id, workspace));
                     },
                     ClientMessage::ClientSwitchArea { 
                         message
                     } => {
                         on_action.borrow()(host_store::Msg::ClientSwitchArea(user_id, message));
                     }
+                    ClientMessage::ClientCursor { 
+                        message
+                    } => {
+                        on_action.borrow()(host_store::Msg::ClientCursor(user_id, message));
+                    }
+                    ClientMessage::ClientReviewReply { 
+                        thread_id,
+                        message
+                    } => {
+                        on_action.borrow()(host_store::Msg::ClientReviewReply(user_id, thread_id, message));
+                    }
+                    ClientMessage::ClientBoard { 
+                        page_id,
+                        operation
+                    } => {
+                        on_action.borrow()(host_store::Msg::ClientBoard(user_id, page_id, operation));
+                    }
+                    ClientMessage::ClientAnnotation { 
+                        operation
+                    } => {
+                        on_action.borrow()(host_store::Msg::ClientAnnotation(user_id, operation));
+                    }
+                    ClientMessage::ClientVideoReport { 
+                        report
+                    } => {
+                        on_action.borrow()(host_store::Msg::ClientVideoReport(user_id, report));
+                    }
+                    ClientMessage::ClientKeyframeRequest { 
+                        stream
+                    } => {
+                        on_action.borrow()(host_store::Msg::ClientKeyframeRequest(stream));
+                    }
+                    ClientMessage::ClientLaserSignal { 
+                        signal
+                    } => {
+                        match laser_channels.borrow().get(&user_id) {
+                            Some(laser_channel) => laser_channel.signal(signal),
+                            None => log::error!("not found laser channel {}", user_id.to_string()),
+                        }
+                    }
                 }            
             }
         };
@@ -116,6 +196,7 @@ impl HostManager {
             let on_action = on_action.clone();
             let video_decoders = self.video_decoders.clone();
             let audio_decoders = self.audio_decoders.clone();
+            let laser_channels = self.laser_channels.clone();
             move |user_id: UserId| {
                 log::error!("disconected {}", user_id);
                 
@@ -151,6 +232,10 @@ impl HostManager {
                     Err(_) => todo!(),
                 }
 
+                if let Some(laser_channel) = laser_channels.borrow_mut().remove(&user_id) {
+                    laser_channel.close();
+                }
+
                 on_action.borrow()(host_store::Msg::DisconnectClient(user_id));
             }
         };
@@ -0,0 +1,69 @@
+use std::{cell::RefCell, rc::Rc};
+
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::{components::editor::replay::ReplayPlayer, models::{editor_settings::EditorSettings, replay::LessonRecording}};
+
+/// Player for a recording the host downloaded, for students who missed the lesson.
+#[function_component(Replay)]
+pub fn replay() -> Html {
+    let recording = use_state(|| Option::<Rc<RefCell<LessonRecording>>>::None);
+    let error = use_state(|| Option::<String>::None);
+
+    let on_file = {
+        let recording = recording.clone();
+        let error = error.clone();
+        Callback::from(move |e: Event| {
+            let file = e
+                .target()
+                .expect("Event should have a target when dispatched")
+                .unchecked_into::<HtmlInputElement>()
+                .files()
+                .and_then(|files| files.get(0));
+            let Some(file) = file else {
+                return;
+            };
+            let recording = recording.clone();
+            let error = error.clone();
+            spawn_local(async move {
+                let text = JsFuture::from(file.text())
+                    .await
+                    .ok()
+                    .and_then(|text| text.as_string());
+                match text.map(|text| serde_json::from_str::<LessonRecording>(&text)) {
+                    Some(Ok(loaded)) => {
+                        recording.set(Some(Rc::new(RefCell::new(loaded))));
+                        error.set(None);
+                    },
+                    Some(Err(err)) => {
+                        log::error!("cannot read recording: {}", err);
+                        error.set(Some("Файл не похож на запись урока".to_owned()));
+                    },
+                    None => {
+                        error.set(Some("Не удалось прочитать файл".to_owned()));
+                    },
+                }
+            });
+        })
+    };
+
+    html! {
+        <div class="main">
+            <div class="replay-open">
+                <label>
+                    { "Запись урока: " }
+                    <input type="file" accept=".json,application/json" onchange={ on_file } />
+                </label>
+                if let Some(error) = (*error).clone() {
+                    <span class="replay-error">{ error }</span>
+                }
+            </div>
+            if let Some(recording) = (*recording).clone() {
+                <ReplayPlayer { recording } settings={ EditorSettings::new() } />
+            }
+        </div>
+    }
+}
@@ -22,8 +22,102 @@ pub const VIDEO_WIDTH: i32 = 640i32;
 // setting for screen sharing
 pub const SCREEN_VIDEO_HEIGHT: i32 = 1080i32;
 pub const SCREEN_VIDEO_WIDTH: i32 = 1920i32;
+// shared screen, annotations over it are kept in pixels of its frame
+pub const SCREEN_SHARE_ELEMENT_ID: &str = "screen_share";
 
 pub const VIDEO_ELEMENT_ID: &str = "webcam";
 
+// adaptive video quality, receivers report this often and the sender adjusts at most as often
+pub const VIDEO_REPORT_INTERVAL_MS: f64 = 1000.0;
+// a receiver that stopped reporting no longer holds the quality down
+pub const VIDEO_REPORT_TIMEOUT_MS: f64 = 5000.0;
+// frames waiting in a receiver's decoder before the picture is made smaller
+pub const VIDEO_DECODE_QUEUE_MAX: u32 = 3;
+pub const VIDEO_FRAMERATE: f64 = 30.0;
+pub const CAMERA_MIN_BITRATE: f64 = 30_000f64;
+pub const CAMERA_START_BITRATE: f64 = 100_000f64;
+pub const CAMERA_MAX_BITRATE: f64 = 500_000f64;
+pub const SCREEN_MIN_BITRATE: f64 = 100_000f64;
+pub const SCREEN_START_BITRATE: f64 = 250_000f64;
+pub const SCREEN_MAX_BITRATE: f64 = 1_500_000f64;
+// a key frame is sent as soon as a receiver asks for one, but not more often than this
+pub const KEYFRAME_MIN_INTERVAL_MS: f64 = 500.0;
+// and this often without being asked, in case nobody does
+pub const KEYFRAME_FALLBACK_MS: f64 = 10000.0;
+// a receiver still waiting for its key frame asks again after this long
+pub const KEYFRAME_RETRY_MS: f64 = 1000.0;
+// frames held back while an earlier one is missing, and how long the missing one is waited for
+pub const VIDEO_JITTER_BUFFER_SIZE: usize = 30;
+pub const VIDEO_JITTER_WAIT_MS: f64 = 100.0;
+// the largest data channel message every browser takes
+pub const DATA_CHANNEL_MAX_MESSAGE: usize = 65_536;
+// bytes of a frame sent in one message, serialized a byte takes up to four characters ("255,")
+// so a fragment stays under DATA_CHANNEL_MAX_MESSAGE with room for the rest of the packet
+pub const VIDEO_FRAGMENT_SIZE: usize = 16_000;
+// a frame announced larger than this is not put together, a screen key frame is far smaller
+pub const VIDEO_MAX_FRAME_BYTES: usize = 4_000_000;
+// a frame still missing fragments after this long is dropped
+pub const VIDEO_FRAGMENT_TIMEOUT_MS: f64 = 1000.0;
 
+// names shown next to the remote cursor in the editor, the host adds the student id
+pub const HOST_CURSOR_NAME: &str = "Учитель";
+pub const CLIENT_CURSOR_NAME: &str = "Ученик";
 
+// file created in a new lesson workspace
+pub const DEFAULT_FILE_PATH: &str = "main.rs";
+
+// editor configuration of a new lesson
+pub const DEFAULT_EDITOR_LANGUAGE: &str = "rust";
+pub const DEFAULT_TAB_SIZE: u32 = 4;
+pub const DEFAULT_FONT_SIZE: u32 = 14;
+
+// monaco language id and the name shown to the host
+pub const EDITOR_LANGUAGES: [(&str, &str); 8] = [
+    ("rust", "Rust"),
+    ("python", "Python"),
+    ("sql", "SQL"),
+    ("java", "Java"),
+    ("javascript", "JavaScript"),
+    ("typescript", "TypeScript"),
+    ("cpp", "C++"),
+    ("plaintext", "Текст"),
+];
+
+// local edits closer than this are undone together
+pub const HISTORY_MERGE_INTERVAL_MS: f64 = 1000.0;
+pub const HISTORY_MAX_LEN: usize = 200;
+// host edits a student has not confirmed, one that falls further behind is sent everything again
+pub const SYNC_HISTORY_MAX_LEN: usize = 500;
+
+// whiteboard size in board units, every viewer scales it to their canvas
+pub const BOARD_WIDTH: u32 = 600;
+pub const BOARD_HEIGHT: u32 = 500;
+pub const BOARD_MIN_ZOOM: f64 = 0.25;
+pub const BOARD_MAX_ZOOM: f64 = 8.0;
+pub const BOARD_ZOOM_STEP: f64 = 1.2;
+pub const BOARD_PAGE_COLOR: &str = "#99c6e3";
+pub const BOARD_DEFAULT_COLOR: &str = "#000000";
+pub const BOARD_DEFAULT_WIDTH: f64 = 2.0;
+pub const BOARD_HISTORY_MAX_LEN: usize = 100;
+pub const BOARD_WIDTHS: [f64; 5] = [1.0, 2.0, 4.0, 8.0, 16.0];
+
+// touches ignored this long after the pen was seen, and touches this large in css pixels
+pub const BOARD_PALM_REJECTION_MS: f64 = 1000.0;
+pub const BOARD_PALM_MIN_SIZE: f64 = 40.0;
+
+// page backgrounds, a grid cell is as high as a line of the page text
+pub const BOARD_GRID_STEP: f64 = 20.0;
+pub const BOARD_GRID_COLOR: &str = "#6fa3c8";
+
+// uploaded images are shrunk to this many pixels on the longest side and sent as jpeg
+pub const BOARD_IMAGE_MAX_SIZE: f64 = 800.0;
+pub const BOARD_IMAGE_QUALITY: f64 = 0.8;
+// a larger encoded image is encoded again at a lower quality, down to the minimum,
+// and one a student sends is refused
+pub const BOARD_IMAGE_MIN_QUALITY: f64 = 0.3;
+pub const BOARD_IMAGE_MAX_BYTES: usize = 200_000;
+// how close to its end corner, in board units, a shape is grabbed to resize it
+pub const BOARD_HANDLE_SIZE: f64 = 10.0;
+
+// the host's laser pointer is sent at most this often
+pub const LASER_INTERVAL_MS: f64 = 50.0;
@@ -8,7 +8,6 @@ use wasm_bindgen::JsCast;
 use wasm_bindgen::JsValue;
 use wasm_bindgen_futures::JsFuture;
 use web_sys::HtmlVideoElement;
-use web_sys::LatencyMode;
 use web_sys::MediaStream;
 use web_sys::MediaStreamConstraints;
 use web_sys::MediaStreamTrack;
@@ -16,17 +15,18 @@ use web_sys::MediaStreamTrackProcessor;
 use web_sys::MediaStreamTrackProcessorInit;
 use web_sys::ReadableStreamDefaultReader;
 use web_sys::VideoEncoder;
-use web_sys::VideoEncoderConfig;
 use web_sys::VideoEncoderEncodeOptions;
 use web_sys::VideoEncoderInit;
 use web_sys::VideoFrame;
 use web_sys::VideoTrack;
 
 use super::encoder_state::EncoderState;
+use super::video_quality::{self, VideoQuality};
 
-use crate::constants::VIDEO_CODEC;
+use crate::constants::VIDEO_FRAGMENT_SIZE;
 use crate::constants::VIDEO_HEIGHT;
 use crate::constants::VIDEO_WIDTH;
+use crate::models::bitrate::{BitrateLimits, FramePacer, ReceiverReport};
 use crate::models::packet::VideoPacket;
 use crate::utils::dom::get_window;
 
@@ -34,6 +34,7 @@ use crate::utils::dom::get_window;
 pub struct CameraEncoder {
     state: EncoderState,
     device: Option<MediaStream>,
+    quality: VideoQuality,
 }
 
 impl CameraEncoder {
@@ -41,6 +42,7 @@ impl CameraEncoder {
         Self {
             state: EncoderState::new(),
             device: None,
+            quality: VideoQuality::new(BitrateLimits::camera()),
         }
     }
 
@@ -64,6 +66,14 @@ impl CameraEncoder {
         self.state.stop()
     }
 
+    pub fn on_report(&self, receiver: &str, report: ReceiverReport) {
+        self.quality.on_report(receiver, report);
+    }
+
+    pub fn request_keyframe(&self) {
+        self.quality.request_keyframe();
+    }
+
     pub fn init(&self, video_elem_id: &str) {
         let device_id = if let Some(vid) = &self.state.selected {
             vid.to_string()
@@ -114,13 +124,16 @@ impl CameraEncoder {
             switching,
             ..
         } = self.state.clone();
+        let quality = self.quality.clone();
         let video_output_handler = {
             let on_frame = on_frame;
             let mut sequence_number: u64 = 0;
             Box::new(move |chunk: JsValue| {
                 let chunk = web_sys::EncodedVideoChunk::from(chunk);
                 let packet = VideoPacket::new(chunk, sequence_number);
-                on_frame(packet);
+                for fragment in packet.into_fragments(VIDEO_FRAGMENT_SIZE) {
+                    on_frame(fragment);
+                }
                 sequence_number += 1;
             })
         };
@@ -189,12 +202,9 @@ impl CameraEncoder {
             video_settings.width(VIDEO_WIDTH);
             video_settings.height(VIDEO_HEIGHT);
 
-            let mut video_encoder_config =
-                VideoEncoderConfig::new(VIDEO_CODEC, VIDEO_HEIGHT as u32, VIDEO_WIDTH as u32);
-
-            video_encoder_config.bitrate(100_000f64);
-            video_encoder_config.latency_mode(LatencyMode::Realtime);
-            video_encoder.configure(&video_encoder_config);
+            let mut settings = quality.settings();
+            video_quality::configure(&video_encoder, &settings);
+            let mut pacer = FramePacer::default();
 
             let video_processor =
                 MediaStreamTrackProcessor::new(&MediaStreamTrackProcessorInit::new(
@@ -207,7 +217,8 @@ impl CameraEncoder {
                 .unchecked_into::<ReadableStreamDefaultReader>();
 
             // Start encoding video and audio.
-            let mut video_frame_counter = 0;
+            // a restarted stream begins with a key frame
+            let mut is_first_frame = true;
             let poll_video = async {
                 loop {
                     if (!*enabled.borrow())
@@ -227,9 +238,22 @@ impl CameraEncoder {
                             let video_frame = Reflect::get(&js_frame, &JsString::from("value"))
                                 .unwrap()
                                 .unchecked_into::<VideoFrame>();
+                            // the decoders need a key frame after a change
+                            let is_changed = quality.settings() != settings;
+                            if is_changed {
+                                settings = quality.settings();
+                                video_quality::configure(&video_encoder, &settings);
+                            }
+                            let is_skipped = video_frame
+                                .timestamp()
+                                .is_some_and(|timestamp| !pacer.should_encode(timestamp, settings.framerate));
+                            if is_skipped && !is_changed {
+                                video_frame.close();
+                                continue;
+                            }
                             let mut opts = VideoEncoderEncodeOptions::new();
-                            video_frame_counter = (video_frame_counter + 1) % 50;
-                            opts.key_frame(video_frame_counter == 0);
+                            opts.key_frame(quality.is_key_frame(is_changed || is_first_frame));
+                            is_first_frame = false;
                             video_encoder.encode_with_options(&video_frame, &opts);
                             video_frame.close();
                         }
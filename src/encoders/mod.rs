@@ -1,4 +1,5 @@
 pub mod camera_encoder;
 pub mod microphone_encoder;
 pub mod screen_encoder;
-pub mod encoder_state;
\ No newline at end of file
+pub mod encoder_state;
+pub mod video_quality;
\ No newline at end of file
@@ -6,36 +6,37 @@ use wasm_bindgen::prelude::Closure;
 use wasm_bindgen::JsCast;
 use wasm_bindgen::JsValue;
 use wasm_bindgen_futures::JsFuture;
-use web_sys::LatencyMode;
+use web_sys::HtmlVideoElement;
 use web_sys::MediaStream;
 use web_sys::MediaStreamTrack;
 use web_sys::MediaStreamTrackProcessor;
 use web_sys::MediaStreamTrackProcessorInit;
 use web_sys::ReadableStreamDefaultReader;
 use web_sys::VideoEncoder;
-use web_sys::VideoEncoderConfig;
 use web_sys::VideoEncoderEncodeOptions;
 use web_sys::VideoEncoderInit;
 use web_sys::VideoFrame;
 use web_sys::VideoTrack;
 
 use super::encoder_state::EncoderState;
+use super::video_quality::{self, VideoQuality};
 
-use crate::constants::SCREEN_VIDEO_HEIGHT;
-use crate::constants::SCREEN_VIDEO_WIDTH;
-use crate::constants::VIDEO_CODEC;
+use crate::constants::VIDEO_FRAGMENT_SIZE;
+use crate::models::bitrate::{BitrateLimits, FramePacer, ReceiverReport};
 use crate::models::packet::VideoPacket;
 use crate::utils;
 
 #[derive(Clone, PartialEq)]
 pub struct ScreenEncoder {
     state: EncoderState,
+    quality: VideoQuality,
 }
 
 impl ScreenEncoder {
     pub fn new() -> Self {
         Self {
             state: EncoderState::new(),
+            quality: VideoQuality::new(BitrateLimits::screen()),
         }
     }
 
@@ -46,28 +47,36 @@ impl ScreenEncoder {
         self.state.stop()
     }
 
+    pub fn on_report(&self, receiver: &str, report: ReceiverReport) {
+        self.quality.on_report(receiver, report);
+    }
+
+    pub fn request_keyframe(&self) {
+        self.quality.request_keyframe();
+    }
+
     pub fn start(
         &mut self, 
         on_frame: impl Fn(VideoPacket) + 'static,
         on_stop_share: impl Fn() + 'static,
+        video_elem_id: &str,
     ) {
+        let video_elem_id = video_elem_id.to_string();
         let EncoderState {
             enabled, destroy, ..
         } = self.state.clone();
+        let quality = self.quality.clone();
         let on_frame = Box::new(on_frame);
         let screen_output_handler = {
             let on_frame = on_frame;
             let mut sequence_number: u64 = 0;
             Box::new(move |chunk: JsValue| {
                 let chunk = web_sys::EncodedVideoChunk::from(chunk);
-                if chunk.byte_length() < 170_000 {
-                    let packet = VideoPacket::new(chunk, sequence_number);
-                    on_frame(packet);
-                   
-                } else {
-                    log::error!("large chank len {}", chunk.byte_length());
+                let packet = VideoPacket::new(chunk, sequence_number);
+                for fragment in packet.into_fragments(VIDEO_FRAGMENT_SIZE) {
+                    on_frame(fragment);
                 }
-                 sequence_number += 1;              
+                sequence_number += 1;
             })
         };
         wasm_bindgen_futures::spawn_local(async move {
@@ -78,6 +87,15 @@ impl ScreenEncoder {
                     .await
                     .unwrap()
                     .unchecked_into::<MediaStream>();
+            // the host watches what is shared, to annotate it
+            let video_element = utils::dom::get_window()
+                .unwrap()
+                .document()
+                .and_then(|document| document.get_element_by_id(&video_elem_id))
+                .map(|element| element.unchecked_into::<HtmlVideoElement>());
+            if let Some(video_element) = &video_element {
+                video_element.set_src_object(Some(&screen_to_share));
+            }
 
             let screen_track = Box::new(
                 screen_to_share
@@ -102,11 +120,9 @@ impl ScreenEncoder {
             screen_error_handler.forget();
 
             let screen_encoder = Box::new(VideoEncoder::new(&screen_encoder_init).unwrap());
-            let mut screen_encoder_config =
-                VideoEncoderConfig::new(VIDEO_CODEC, SCREEN_VIDEO_HEIGHT as u32, SCREEN_VIDEO_WIDTH as u32);
-            screen_encoder_config.bitrate(250_000f64);
-            screen_encoder_config.latency_mode(LatencyMode::Realtime);
-            screen_encoder.configure(&screen_encoder_config);
+            let mut settings = quality.settings();
+            video_quality::configure(&screen_encoder, &settings);
+            let mut pacer = FramePacer::default();
 
             let screen_processor =
                 MediaStreamTrackProcessor::new(&MediaStreamTrackProcessorInit::new(
@@ -119,8 +135,8 @@ impl ScreenEncoder {
                 .get_reader()
                 .unchecked_into::<ReadableStreamDefaultReader>();
 
-            let mut screen_frame_counter = 0;
-
+            // a restarted stream begins with a key frame
+            let mut is_first_frame = true;
             let poll_screen = async {
                 loop {
                     if *destroy.borrow() {
@@ -134,13 +150,29 @@ impl ScreenEncoder {
                             let video_frame = Reflect::get(&js_frame, &JsString::from("value"))
                                 .unwrap()
                                 .unchecked_into::<VideoFrame>();
-                            let mut opts = VideoEncoderEncodeOptions::new();
-                            screen_frame_counter = (screen_frame_counter + 1) % 25;
-                            opts.key_frame(screen_frame_counter == 0);
                             if video_frame.is_undefined() {
+                                if let Some(video_element) = &video_element {
+                                    video_element.set_src_object(None);
+                                }
                                 on_stop_share();
                                 return;
                             }
+                            // the decoders need a key frame after a change
+                            let is_changed = quality.settings() != settings;
+                            if is_changed {
+                                settings = quality.settings();
+                                video_quality::configure(&screen_encoder, &settings);
+                            }
+                            let is_skipped = video_frame
+                                .timestamp()
+                                .is_some_and(|timestamp| !pacer.should_encode(timestamp, settings.framerate));
+                            if is_skipped && !is_changed {
+                                video_frame.close();
+                                continue;
+                            }
+                            let mut opts = VideoEncoderEncodeOptions::new();
+                            opts.key_frame(quality.is_key_frame(is_changed || is_first_frame));
+                            is_first_frame = false;
                             screen_encoder.encode_with_options(&video_frame, &opts);
                             video_frame.close();
                         }
@@ -0,0 +1,55 @@
+use std::{rc::Rc, cell::RefCell};
+
+use web_sys::{LatencyMode, VideoEncoder, VideoEncoderConfig};
+
+use crate::constants::VIDEO_CODEC;
+use crate::models::bitrate::{BitrateController, BitrateLimits, EncoderSettings, ReceiverReport};
+use crate::models::keyframe::KeyframeScheduler;
+
+/// Adaptive quality of one video encoder. Reports and key frame requests of
+/// the receivers come in through the media store, the encoding loop picks
+/// them up on the next frame.
+#[derive(Clone, PartialEq)]
+pub struct VideoQuality {
+    controller: Rc<RefCell<BitrateController>>,
+    keyframes: Rc<RefCell<KeyframeScheduler>>,
+}
+
+impl VideoQuality {
+    pub fn new(limits: BitrateLimits) -> Self {
+        Self {
+            controller: Rc::new(RefCell::new(BitrateController::new(limits))),
+            keyframes: Rc::new(RefCell::new(KeyframeScheduler::default())),
+        }
+    }
+
+    pub fn on_report(&self, receiver: &str, report: ReceiverReport) {
+        log::debug!("video report from {}: {:?}", receiver, report);
+        let settings = self.controller
+            .borrow_mut()
+            .on_report(receiver, report, js_sys::Date::now());
+        if let Some(settings) = settings {
+            log::info!("video quality changed: {:?}", settings);
+        }
+    }
+
+    pub fn request_keyframe(&self) {
+        self.keyframes.borrow_mut().request();
+    }
+
+    pub(super) fn settings(&self) -> EncoderSettings {
+        self.controller.borrow().settings()
+    }
+
+    pub(super) fn is_key_frame(&self, is_forced: bool) -> bool {
+        self.keyframes.borrow_mut().should_send_key(is_forced, js_sys::Date::now())
+    }
+}
+
+pub(super) fn configure(encoder: &VideoEncoder, settings: &EncoderSettings) {
+    let mut config = VideoEncoderConfig::new(VIDEO_CODEC, settings.height, settings.width);
+    config.bitrate(settings.bitrate);
+    config.framerate(settings.framerate);
+    config.latency_mode(LatencyMode::Realtime);
+    encoder.configure(&config);
+}
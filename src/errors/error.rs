@@ -11,4 +11,14 @@ pub enum LiveDocumentError {
     UnexpectedElement(String),
     #[error("failed to create URL search params: {0}")]
     FailedToCreateUrlSearchParams(String),
+    #[error("invalid text operation: {0}")]
+    InvalidOperation(String),
+    #[error("invalid workspace action: {0}")]
+    InvalidWorkspace(String),
+    #[error("failed to export file: {0}")]
+    FailedToExport(String),
+    #[error("unknown review thread: {0}")]
+    UnknownReviewThread(String),
+    #[error("invalid board operation: {0}")]
+    InvalidBoard(String),
 }
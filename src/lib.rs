@@ -66,7 +66,7 @@
 
 mod app;
 mod errors;
-mod constants;
+pub mod constants;
 mod sleep;
 mod wrappers;
 pub mod crypto;
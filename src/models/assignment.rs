@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+use super::{review::ReviewThread, workspace::{Workspace, WorkspaceFile}};
+
+/// Starter files the host hands out to every student.
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct Assignment {
+    pub id: String,
+    pub title: String,
+    pub workspace: Workspace,
+}
+
+impl Assignment {
+    /// Copies the template with fresh file ids, so edits still in flight
+    /// for the replaced files can't land in the new ones.
+    pub fn new(title: String, template: &Workspace) -> Self {
+        let files: Vec<WorkspaceFile> = template
+            .files
+            .iter()
+            .map(|file| WorkspaceFile::new(file.path.clone(), file.content.clone()))
+            .collect();
+        let new_id = |old_id: &str| {
+            template
+                .files
+                .iter()
+                .position(|file| file.id == old_id)
+                .map(|index| files[index].id.clone())
+        };
+        let tabs = template.tabs.iter().filter_map(|id| new_id(id)).collect();
+        let active = new_id(&template.active).unwrap_or_default();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            title,
+            workspace: Workspace {
+                files,
+                tabs,
+                active,
+            },
+        }
+    }
+}
+
+/// Frozen copy of a student's work, `submitted_at` is in milliseconds since the epoch.
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct Submission {
+    pub assignment_id: String,
+    pub title: String,
+    pub workspace: Workspace,
+    pub submitted_at: f64,
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum SubmissionStatus {
+    Pending,
+    Submitted(f64),
+}
+
+impl SubmissionStatus {
+    pub fn of(assignment: &Assignment, submissions: &[Submission]) -> Self {
+        submissions
+            .iter()
+            .rev()
+            .find(|submission| submission.assignment_id == assignment.id)
+            .map_or(SubmissionStatus::Pending, |submission| SubmissionStatus::Submitted(submission.submitted_at))
+    }
+}
+
+/// Submissions and review comments of one student in the exported bundle.
+#[derive(Serialize, Debug)]
+pub struct StudentSubmissions<'a> {
+    pub user_id: String,
+    pub submissions: &'a [Submission],
+    pub review: &'a [ReviewThread],
+}
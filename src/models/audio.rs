@@ -29,7 +29,7 @@ impl Audio {
     }
 
     pub fn decode(&self, packet: AudioPacket) {
-        let encoded_audio_chunk = AudioPacket::get_encoded_audio_chunk(packet);
+        let encoded_audio_chunk = AudioPacket::get_encoded_audio_chunk(&packet);
         let state = self.audio_decoder.state();
         match state {
             web_sys::CodecState::Unconfigured => {
@@ -0,0 +1,470 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{CAMERA_MAX_BITRATE, CAMERA_MIN_BITRATE, CAMERA_START_BITRATE, SCREEN_MAX_BITRATE, SCREEN_MIN_BITRATE, SCREEN_START_BITRATE, SCREEN_VIDEO_HEIGHT, SCREEN_VIDEO_WIDTH, VIDEO_DECODE_QUEUE_MAX, VIDEO_FRAMERATE, VIDEO_HEIGHT, VIDEO_REPORT_INTERVAL_MS, VIDEO_REPORT_TIMEOUT_MS, VIDEO_WIDTH};
+
+// above this share of lost frames the bitrate goes down, below the lower one it may go up
+const LOSS_HIGH: f64 = 0.1;
+const LOSS_LOW: f64 = 0.02;
+// the same for frames arriving late, in milliseconds
+const LAG_HIGH_MS: f64 = 200.0;
+const LAG_LOW_MS: f64 = 50.0;
+const INCREASE: f64 = 1.08;
+const DECREASE: f64 = 0.85;
+// smaller changes are not worth reconfiguring the encoder for
+const MIN_CHANGE: f64 = 0.05;
+
+/// Which of the sender's encoders a report is about.
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Debug)]
+pub enum VideoStream {
+    Camera,
+    Screen,
+}
+
+/// What a receiver got of one stream during `interval_ms`.
+#[derive(PartialEq, Clone, Copy, Serialize, Deserialize, Debug)]
+pub struct ReceiverReport {
+    pub stream: VideoStream,
+    pub received: u32,
+    /// Frames missing from the sequence numbers.
+    pub lost: u32,
+    pub bytes: u64,
+    pub interval_ms: f64,
+    /// How much later than the quickest frame so far the frames arrived,
+    /// it grows while the network queues them.
+    pub lag_ms: f64,
+    /// Most frames waiting in the decoder, a slow device falls behind here.
+    pub decode_queue: u32,
+    /// Frames that came after a later one, still in time to be played.
+    #[serde(default)]
+    pub reordered: u32,
+    /// Frames that came after their place was given up.
+    #[serde(default)]
+    pub late: u32,
+    /// Frames dropped with fragments missing.
+    #[serde(default)]
+    pub incomplete: u32,
+}
+
+impl ReceiverReport {
+    pub fn loss(&self) -> f64 {
+        let total = self.received + self.lost;
+        if total == 0 {
+            0.0
+        } else {
+            self.lost as f64 / total as f64
+        }
+    }
+
+    /// Bits per second that reached the receiver.
+    pub fn throughput(&self) -> f64 {
+        if self.interval_ms <= 0.0 {
+            0.0
+        } else {
+            self.bytes as f64 * 8.0 * 1000.0 / self.interval_ms
+        }
+    }
+}
+
+/// Receiver side of one stream: counts what arrives and sums it up in a
+/// report every `VIDEO_REPORT_INTERVAL_MS`.
+#[derive(PartialEq, Clone, Debug)]
+pub struct ReceiverStats {
+    stream: VideoStream,
+    started_at: Option<f64>,
+    // arrival time minus capture time of the quickest frame, the clocks differ by a constant
+    base_delay: Option<f64>,
+    received: u32,
+    lost: u32,
+    bytes: u64,
+    lag_ms: f64,
+    decode_queue: u32,
+}
+
+impl ReceiverStats {
+    pub fn new(stream: VideoStream) -> Self {
+        Self {
+            stream,
+            started_at: None,
+            base_delay: None,
+            received: 0,
+            lost: 0,
+            bytes: 0,
+            lag_ms: 0.0,
+            decode_queue: 0,
+        }
+    }
+
+    /// `timestamp` is the capture time of the frame in microseconds, `now`
+    /// the arrival in milliseconds.
+    pub fn on_packet(&mut self, bytes: usize, timestamp: f64, now: f64) {
+        self.started_at.get_or_insert(now);
+        self.received += 1;
+        self.bytes += bytes as u64;
+        let delay = now - timestamp / 1000.0;
+        let base_delay = *self.base_delay.get_or_insert(delay);
+        if delay < base_delay {
+            self.base_delay = Some(delay);
+        }
+        self.lag_ms = self.lag_ms.max(delay - base_delay);
+    }
+
+    /// Frames the jitter buffer gave up on.
+    pub fn on_lost(&mut self, frames: u64) {
+        self.lost += frames as u32;
+    }
+
+    /// The sender started over, its clock may have too.
+    pub fn on_restart(&mut self) {
+        self.base_delay = None;
+    }
+
+    pub fn set_decode_queue(&mut self, frames: u32) {
+        self.decode_queue = self.decode_queue.max(frames);
+    }
+
+    /// The report once the interval is over, counting starts again after it.
+    pub fn report(&mut self, now: f64) -> Option<ReceiverReport> {
+        let started_at = self.started_at?;
+        if now - started_at < VIDEO_REPORT_INTERVAL_MS {
+            return None;
+        }
+        let report = ReceiverReport {
+            stream: self.stream,
+            received: self.received,
+            lost: self.lost,
+            bytes: self.bytes,
+            interval_ms: now - started_at,
+            lag_ms: self.lag_ms,
+            decode_queue: self.decode_queue,
+            reordered: 0,
+            late: 0,
+            incomplete: 0,
+        };
+        self.started_at = Some(now);
+        self.received = 0;
+        self.lost = 0;
+        self.bytes = 0;
+        self.lag_ms = 0.0;
+        self.decode_queue = 0;
+        Some(report)
+    }
+}
+
+/// What a video encoder is configured with.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct EncoderSettings {
+    pub bitrate: f64,
+    pub width: u32,
+    pub height: u32,
+    pub framerate: f64,
+}
+
+/// Picture size and frame rate, as shares of the full ones, used while the
+/// bitrate is at least `min_share` of the maximum.
+#[derive(PartialEq, Clone, Copy, Debug)]
+struct Rung {
+    min_share: f64,
+    scale: f64,
+    framerate: f64,
+}
+
+// a face stays recognizable when blurry, so the camera gives up size first
+const CAMERA_LADDER: [Rung; 3] = [
+    Rung { min_share: 0.5, scale: 1.0, framerate: 1.0 },
+    Rung { min_share: 0.2, scale: 0.75, framerate: 1.0 },
+    Rung { min_share: 0.0, scale: 0.5, framerate: 0.5 },
+];
+
+// text on a shared screen has to stay readable, so it gives up frames first
+const SCREEN_LADDER: [Rung; 3] = [
+    Rung { min_share: 0.4, scale: 1.0, framerate: 1.0 },
+    Rung { min_share: 0.15, scale: 1.0, framerate: 0.5 },
+    Rung { min_share: 0.0, scale: 0.75, framerate: 0.25 },
+];
+
+/// Bounds of one encoder.
+#[derive(PartialEq, Clone, Debug)]
+pub struct BitrateLimits {
+    pub min_bitrate: f64,
+    pub start_bitrate: f64,
+    pub max_bitrate: f64,
+    pub width: u32,
+    pub height: u32,
+    pub framerate: f64,
+    ladder: &'static [Rung],
+}
+
+impl BitrateLimits {
+    pub fn camera() -> Self {
+        Self {
+            min_bitrate: CAMERA_MIN_BITRATE,
+            start_bitrate: CAMERA_START_BITRATE,
+            max_bitrate: CAMERA_MAX_BITRATE,
+            width: VIDEO_WIDTH as u32,
+            height: VIDEO_HEIGHT as u32,
+            framerate: VIDEO_FRAMERATE,
+            ladder: &CAMERA_LADDER,
+        }
+    }
+
+    pub fn screen() -> Self {
+        Self {
+            min_bitrate: SCREEN_MIN_BITRATE,
+            start_bitrate: SCREEN_START_BITRATE,
+            max_bitrate: SCREEN_MAX_BITRATE,
+            width: SCREEN_VIDEO_WIDTH as u32,
+            height: SCREEN_VIDEO_HEIGHT as u32,
+            framerate: VIDEO_FRAMERATE,
+            ladder: &SCREEN_LADDER,
+        }
+    }
+
+    fn rung(&self, bitrate: f64) -> usize {
+        let share = bitrate / self.max_bitrate;
+        self.ladder
+            .iter()
+            .position(|rung| share >= rung.min_share)
+            .unwrap_or(self.ladder.len() - 1)
+    }
+
+    fn settings(&self, bitrate: f64, rung: usize) -> EncoderSettings {
+        let rung = self.ladder[rung.min(self.ladder.len() - 1)];
+        // encoders want even sizes
+        let even = |size: u32| ((size as f64 * rung.scale / 2.0).round() * 2.0) as u32;
+        EncoderSettings {
+            bitrate,
+            width: even(self.width),
+            height: even(self.height),
+            framerate: self.framerate * rung.framerate,
+        }
+    }
+}
+
+/// Sender side of one encoder. Everybody gets the same encoding, so the
+/// worst receiver decides: loss or growing lag in a new report lowers the
+/// bitrate right away, and it goes up a little once the last reports of
+/// everybody are clean. The picture size and frame rate follow the bitrate,
+/// and drop one more step while a receiver cannot decode fast enough.
+#[derive(PartialEq, Clone, Debug)]
+pub struct BitrateController {
+    limits: BitrateLimits,
+    bitrate: f64,
+    // extra steps down the ladder for slow decoders
+    slow_down: usize,
+    // the last report of each receiver, when it came and whether it was acted on
+    reports: HashMap<String, (ReceiverReport, f64, bool)>,
+    decided_at: Option<f64>,
+    settings: EncoderSettings,
+}
+
+impl BitrateController {
+    pub fn new(limits: BitrateLimits) -> Self {
+        let bitrate = limits.start_bitrate;
+        let settings = limits.settings(bitrate, limits.rung(bitrate));
+        Self {
+            limits,
+            bitrate,
+            slow_down: 0,
+            reports: HashMap::new(),
+            decided_at: None,
+            settings,
+        }
+    }
+
+    pub fn settings(&self) -> EncoderSettings {
+        self.settings
+    }
+
+    /// Takes a report of `receiver`, `now` is in milliseconds. Returns the new
+    /// settings when the encoder has to be reconfigured.
+    pub fn on_report(&mut self, receiver: &str, report: ReceiverReport, now: f64) -> Option<EncoderSettings> {
+        self.reports.insert(receiver.to_owned(), (report, now, false));
+        self.reports.retain(|_, (_, at, _)| now - *at < VIDEO_REPORT_TIMEOUT_MS);
+        // a change needs a report interval to show up
+        if self.decided_at.is_some_and(|at| now - at < VIDEO_REPORT_INTERVAL_MS) {
+            return None;
+        }
+        self.decided_at = Some(now);
+
+        let reports = self.reports.values().map(|(report, _, _)| report);
+        // an old report has already lowered the bitrate once
+        let new_reports = self.reports
+            .values()
+            .filter(|(_, _, is_used)| !is_used)
+            .map(|(report, _, _)| report);
+        let loss = new_reports.clone().map(ReceiverReport::loss).fold(0.0, f64::max);
+        let lagging = new_reports
+            .filter(|report| report.lag_ms > LAG_HIGH_MS)
+            .map(ReceiverReport::throughput)
+            .reduce(f64::min);
+        let is_clear = reports.clone().all(|report| report.loss() < LOSS_LOW && report.lag_ms < LAG_LOW_MS);
+        let is_slow = reports.clone().any(|report| report.decode_queue > VIDEO_DECODE_QUEUE_MAX);
+        self.reports.values_mut().for_each(|(_, _, is_used)| *is_used = true);
+
+        let bitrate = if loss > LOSS_HIGH {
+            self.bitrate * (1.0 - 0.5 * loss)
+        } else if let Some(throughput) = lagging {
+            // what still got through is about what the link carries, once
+            // below it the queue drains by itself
+            if self.bitrate > throughput * 0.9 {
+                (self.bitrate * DECREASE).min(throughput * 0.9).max(self.bitrate * 0.5)
+            } else {
+                self.bitrate
+            }
+        } else if is_clear && !is_slow {
+            self.bitrate * INCREASE
+        } else {
+            self.bitrate
+        };
+        self.bitrate = bitrate.clamp(self.limits.min_bitrate, self.limits.max_bitrate);
+        if is_slow {
+            self.slow_down = (self.slow_down + 1).min(self.limits.ladder.len() - 1);
+        } else if is_clear {
+            self.slow_down = self.slow_down.saturating_sub(1);
+        }
+
+        let settings = self.limits.settings(self.bitrate, self.limits.rung(self.bitrate) + self.slow_down);
+        let is_same_picture = settings.width == self.settings.width && settings.framerate == self.settings.framerate;
+        if is_same_picture && (settings.bitrate - self.settings.bitrate).abs() < self.settings.bitrate * MIN_CHANGE {
+            return None;
+        }
+        self.settings = settings;
+        Some(settings)
+    }
+}
+
+/// Skips captured frames to keep to the frame rate of the settings.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct FramePacer {
+    last: Option<f64>,
+}
+
+impl FramePacer {
+    /// `timestamp` is the capture time in microseconds.
+    pub fn should_encode(&mut self, timestamp: f64, framerate: f64) -> bool {
+        // capture times jitter, a frame a little early still counts
+        let interval = 1_000_000.0 / framerate * 0.9;
+        if self.last.is_some_and(|last| timestamp >= last && timestamp - last < interval) {
+            return false;
+        }
+        self.last = Some(timestamp);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BitrateController, BitrateLimits, ReceiverReport, VideoStream};
+    use crate::constants::{CAMERA_MAX_BITRATE, CAMERA_START_BITRATE, VIDEO_REPORT_TIMEOUT_MS, VIDEO_WIDTH};
+
+    fn report(lost: u32, bytes: u64, lag_ms: f64, decode_queue: u32) -> ReceiverReport {
+        ReceiverReport {
+            stream: VideoStream::Camera,
+            received: 100 - lost,
+            lost,
+            bytes,
+            interval_ms: 1000.0,
+            lag_ms,
+            decode_queue,
+            reordered: 0,
+            late: 0,
+            incomplete: 0,
+        }
+    }
+
+    fn clean() -> ReceiverReport {
+        report(0, 50_000, 0.0, 0)
+    }
+
+    fn assert_near(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1.0, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn loss_lowers_the_bitrate() {
+        let mut controller = BitrateController::new(BitrateLimits::camera());
+        let settings = controller.on_report("a", report(30, 50_000, 0.0, 0), 0.0).unwrap();
+        assert_near(settings.bitrate, CAMERA_START_BITRATE * 0.85);
+    }
+
+    #[test]
+    fn small_loss_keeps_the_bitrate() {
+        let mut controller = BitrateController::new(BitrateLimits::camera());
+        assert_eq!(controller.on_report("a", report(5, 50_000, 0.0, 0), 0.0), None);
+        assert_near(controller.settings().bitrate, CAMERA_START_BITRATE);
+    }
+
+    #[test]
+    fn lag_lowers_the_bitrate_towards_the_throughput() {
+        let mut controller = BitrateController::new(BitrateLimits::camera());
+        // 80 kbit/s got through
+        let settings = controller.on_report("a", report(0, 10_000, 300.0, 0), 0.0).unwrap();
+        assert_near(settings.bitrate, 72_000.0);
+    }
+
+    #[test]
+    fn lag_with_enough_throughput_keeps_the_bitrate() {
+        let mut controller = BitrateController::new(BitrateLimits::camera());
+        assert_eq!(controller.on_report("a", report(0, 50_000, 300.0, 0), 0.0), None);
+    }
+
+    #[test]
+    fn slow_decoder_gets_a_smaller_picture() {
+        let mut controller = BitrateController::new(BitrateLimits::camera());
+        let before = controller.settings();
+        let settings = controller.on_report("a", report(0, 50_000, 0.0, 10), 0.0).unwrap();
+        assert_near(settings.bitrate, before.bitrate);
+        assert!(settings.width < before.width);
+        assert!(settings.framerate < before.framerate);
+    }
+
+    #[test]
+    fn worst_receiver_wins() {
+        let mut controller = BitrateController::new(BitrateLimits::camera());
+        controller.on_report("a", clean(), 0.0);
+        let before = controller.settings().bitrate;
+        let settings = controller.on_report("b", report(20, 50_000, 0.0, 0), 1000.0).unwrap();
+        assert_near(settings.bitrate, before * 0.9);
+        // a clean report of one receiver does not raise it while the other one is not
+        assert_eq!(controller.on_report("a", clean(), 2000.0), None);
+    }
+
+    #[test]
+    fn decides_once_per_interval() {
+        let mut controller = BitrateController::new(BitrateLimits::camera());
+        assert!(controller.on_report("a", report(30, 50_000, 0.0, 0), 0.0).is_some());
+        assert_eq!(controller.on_report("b", report(50, 50_000, 0.0, 0), 500.0), None);
+    }
+
+    #[test]
+    fn silent_receiver_stops_holding_the_bitrate_down() {
+        let mut controller = BitrateController::new(BitrateLimits::camera());
+        controller.on_report("b", report(20, 50_000, 0.0, 0), 0.0);
+        let held = controller.settings().bitrate;
+        assert_eq!(controller.on_report("a", clean(), 1000.0), None);
+        controller.on_report("a", clean(), VIDEO_REPORT_TIMEOUT_MS + 1000.0);
+        assert!(controller.settings().bitrate > held);
+    }
+
+    #[test]
+    fn recovers_step_by_step() {
+        let mut controller = BitrateController::new(BitrateLimits::camera());
+        controller.on_report("a", report(50, 50_000, 0.0, 10), 0.0);
+        let mut bitrate = controller.settings().bitrate;
+        let mut now = 0.0;
+        for _ in 0..60 {
+            now += 1000.0;
+            controller.on_report("a", clean(), now);
+            let settings = controller.settings();
+            assert!(settings.bitrate >= bitrate);
+            // one step is never more than the increase
+            assert!(settings.bitrate <= bitrate * 1.08 + 1.0);
+            bitrate = settings.bitrate;
+        }
+        let settings = controller.settings();
+        assert_near(settings.bitrate, CAMERA_MAX_BITRATE);
+        assert_eq!(settings.width, VIDEO_WIDTH as u32);
+    }
+}
@@ -0,0 +1,884 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{BOARD_DEFAULT_COLOR, BOARD_DEFAULT_WIDTH, BOARD_GRID_STEP, BOARD_HEIGHT, BOARD_HISTORY_MAX_LEN, BOARD_IMAGE_MAX_BYTES, BOARD_MAX_ZOOM, BOARD_MIN_ZOOM, BOARD_WIDTH, HISTORY_MERGE_INTERVAL_MS};
+
+use super::history::HistoryAction;
+
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Debug)]
+pub enum Tool {
+    Pen,
+    /// Removes whole strokes under the pointer, it never ends up on the board itself.
+    Eraser,
+    Line,
+    Rect,
+    Ellipse,
+    Arrow,
+    Text,
+    /// Drags a stroke around, or resizes a shape or an image by its end corner.
+    Move,
+    /// An uploaded picture, placed from a file rather than drawn with the brush.
+    Image,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 8] = [Tool::Pen, Tool::Eraser, Tool::Line, Tool::Rect, Tool::Ellipse, Tool::Arrow, Tool::Text, Tool::Move];
+
+    /// Shapes are drawn by dragging, their points are the two corners.
+    pub fn is_shape(&self) -> bool {
+        matches!(self, Tool::Line | Tool::Rect | Tool::Ellipse | Tool::Arrow)
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Tool::Pen => "Карандаш",
+            Tool::Eraser => "Ластик",
+            Tool::Line => "Линия",
+            Tool::Rect => "Прямоугольник",
+            Tool::Ellipse => "Эллипс",
+            Tool::Arrow => "Стрелка",
+            Tool::Text => "Текст",
+            Tool::Move => "Перемещение",
+            Tool::Image => "Картинка",
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            Tool::Pen => "✎",
+            Tool::Eraser => "⌫",
+            Tool::Line => "╱",
+            Tool::Rect => "▭",
+            Tool::Ellipse => "◯",
+            Tool::Arrow => "➝",
+            Tool::Text => "T",
+            Tool::Move => "✥",
+            Tool::Image => "🖼",
+        }
+    }
+}
+
+/// What the next stroke will be drawn with.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Brush {
+    pub tool: Tool,
+    pub color: String,
+    pub width: f64,
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Self {
+            tool: Tool::Pen,
+            color: BOARD_DEFAULT_COLOR.to_owned(),
+            width: BOARD_DEFAULT_WIDTH,
+        }
+    }
+}
+
+impl Brush {
+    pub fn set_tool(&mut self, tool: Tool) {
+        self.tool = tool;
+    }
+
+    pub fn set_color(&mut self, color: String) {
+        self.color = color;
+    }
+
+    pub fn set_width(&mut self, width: f64) {
+        self.width = width;
+    }
+
+    pub fn stroke(&self, start: Point) -> Stroke {
+        Stroke::new(self.tool, self.color.clone(), self.width, start)
+    }
+
+    pub fn eraser_radius(&self) -> f64 {
+        self.width.max(4.0)
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Serialize, Deserialize, Debug)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+    /// Pen pressure from 0 to 1, `None` for mouse and touch input.
+    #[serde(default)]
+    pub pressure: Option<f64>,
+}
+
+impl Point {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y, pressure: None }
+    }
+
+    pub fn with_pressure(self, pressure: f64) -> Self {
+        Self {
+            pressure: Some(pressure.clamp(0.0, 1.0)),
+            ..self
+        }
+    }
+
+    pub fn distance_to(&self, other: &Point) -> f64 {
+        self.distance_to_segment(other, other)
+    }
+
+    fn distance_to_segment(&self, a: &Point, b: &Point) -> f64 {
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let length = dx * dx + dy * dy;
+        let t = if length == 0.0 {
+            0.0
+        } else {
+            (((self.x - a.x) * dx + (self.y - a.y) * dy) / length).clamp(0.0, 1.0)
+        };
+        let (x, y) = (a.x + t * dx, a.y + t * dy);
+        ((self.x - x).powi(2) + (self.y - y).powi(2)).sqrt()
+    }
+}
+
+/// How one viewer sees a page: the fit of the board into the canvas, their own
+/// zoom and scroll, and the device pixel ratio. Strokes are kept in units of
+/// `area`, `BOARD_WIDTH` x `BOARD_HEIGHT` for the board, so this is never sent to others.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Viewport {
+    /// Size of the drawing in stroke units.
+    pub area: Point,
+    /// Canvas pixels per board unit at 100% zoom.
+    pub fit: f64,
+    pub zoom: f64,
+    /// Offset of the board origin in canvas pixels.
+    pub pan: Point,
+    pub pixel_ratio: f64,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            area: Point::new(BOARD_WIDTH as f64, BOARD_HEIGHT as f64),
+            fit: 1.0,
+            zoom: 1.0,
+            pan: Point::new(0.0, 0.0),
+            pixel_ratio: 1.0,
+        }
+    }
+}
+
+impl Viewport {
+    pub fn with_area(width: f64, height: f64) -> Self {
+        Self {
+            area: Point::new(width, height),
+            ..Self::default()
+        }
+    }
+
+    fn scale(&self) -> f64 {
+        self.fit * self.zoom
+    }
+
+    /// Fits the whole area into a canvas of `width` x `height` css pixels.
+    pub fn fit_to(&mut self, width: f64, height: f64, pixel_ratio: f64) {
+        self.fit = (width / self.area.x).min(height / self.area.y);
+        self.pixel_ratio = pixel_ratio;
+    }
+
+    /// Scale and offset of the canvas transform, in device pixels.
+    pub fn transform(&self) -> (f64, f64, f64) {
+        (
+            self.scale() * self.pixel_ratio,
+            self.pan.x * self.pixel_ratio,
+            self.pan.y * self.pixel_ratio,
+        )
+    }
+
+    /// Board point under a pointer at `x`, `y` css pixels from the canvas corner.
+    pub fn to_board(&self, x: f64, y: f64) -> Point {
+        Point::new((x - self.pan.x) / self.scale(), (y - self.pan.y) / self.scale())
+    }
+
+    /// Css pixels from the canvas corner of a board point.
+    pub fn to_css(&self, point: &Point) -> Point {
+        Point::new(point.x * self.scale() + self.pan.x, point.y * self.scale() + self.pan.y)
+    }
+
+    /// Zooms by `factor` keeping the board point under `x`, `y` in place.
+    pub fn zoom_at(&mut self, factor: f64, x: f64, y: f64) {
+        let anchor = self.to_board(x, y);
+        self.zoom = (self.zoom * factor).clamp(BOARD_MIN_ZOOM, BOARD_MAX_ZOOM);
+        self.pan = Point::new(x - anchor.x * self.scale(), y - anchor.y * self.scale());
+    }
+
+    pub fn pan_by(&mut self, dx: f64, dy: f64) {
+        self.pan = Point::new(self.pan.x + dx, self.pan.y + dy);
+    }
+
+    pub fn reset(&mut self) {
+        self.zoom = 1.0;
+        self.pan = Point::new(0.0, 0.0);
+    }
+}
+
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct Stroke {
+    pub id: String,
+    pub tool: Tool,
+    pub color: String,
+    pub width: f64,
+    pub points: Vec<Point>,
+    /// Content of a text box or the data url of an image, empty for every other tool.
+    #[serde(default)]
+    pub text: String,
+    /// Id of the student who drew it, `None` for the host.
+    #[serde(default)]
+    pub author: Option<String>,
+}
+
+impl Stroke {
+    /// A shape starts with both corners at `start`, a pen stroke with a single point.
+    pub fn new(tool: Tool, color: String, width: f64, start: Point) -> Self {
+        let points = if tool.is_shape() { vec![start, start] } else { vec![start] };
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            tool,
+            color,
+            width,
+            points,
+            text: String::default(),
+            author: None,
+        }
+    }
+
+    pub fn text_box(color: String, width: f64, at: Point, text: String) -> Self {
+        Self {
+            text,
+            ..Self::new(Tool::Text, color, width, at)
+        }
+    }
+
+    /// An image of `width` x `height` board units centered on `center`, its points are the two corners.
+    pub fn image(src: String, center: Point, width: f64, height: f64) -> Self {
+        let start = Point::new(center.x - width / 2.0, center.y - height / 2.0);
+        Self {
+            points: vec![start, Point::new(start.x + width, start.y + height)],
+            text: src,
+            ..Self::new(Tool::Image, String::default(), 0.0, start)
+        }
+    }
+
+    /// The data url of an image, `None` for other strokes and for anything
+    /// but a picture embedded within `BOARD_IMAGE_MAX_BYTES`.
+    pub fn image_src(&self) -> Option<&str> {
+        let is_image = self.tool == Tool::Image
+            && self.text.starts_with("data:image/")
+            && self.text.len() <= BOARD_IMAGE_MAX_BYTES;
+        is_image.then_some(self.text.as_str())
+    }
+
+    pub fn set_author(&mut self, author: Option<String>) {
+        self.author = author;
+    }
+
+    /// Line width around `point`: a light touch of the pen draws thinner,
+    /// a firm one thicker, and the usual half pressure gives `width`.
+    pub fn width_at(&self, point: &Point) -> f64 {
+        point.pressure.map_or(self.width, |pressure| self.width * (0.25 + 1.5 * pressure))
+    }
+
+    pub fn has_pressure(&self) -> bool {
+        self.points.iter().any(|point| point.pressure.is_some())
+    }
+
+    pub fn font_size(&self) -> f64 {
+        12.0 + self.width * 4.0
+    }
+
+    /// Moves the dragged corner of a shape.
+    pub fn set_end(&mut self, end: Point) {
+        if let Some(last) = self.points.last_mut() {
+            *last = end;
+        }
+    }
+
+    /// Shapes and images are resized by dragging their end corner.
+    pub fn is_resizable(&self) -> bool {
+        self.tool.is_shape() || self.tool == Tool::Image
+    }
+
+    /// Points of the stroke shifted by `dx`, `dy`.
+    pub fn moved_by(&self, dx: f64, dy: f64) -> Vec<Point> {
+        self.points
+            .iter()
+            .map(|point| Point {
+                x: point.x + dx,
+                y: point.y + dy,
+                ..*point
+            })
+            .collect()
+    }
+
+    /// Polyline close to what is drawn, used to find strokes under the eraser.
+    pub fn outline(&self) -> Vec<Point> {
+        let (Some(start), Some(end)) = (self.points.first(), self.points.last()) else {
+            return Vec::new();
+        };
+        match self.tool {
+            Tool::Pen | Tool::Eraser | Tool::Move | Tool::Line | Tool::Arrow => self.points.clone(),
+            Tool::Rect | Tool::Image => vec![
+                *start,
+                Point::new(end.x, start.y),
+                *end,
+                Point::new(start.x, end.y),
+                *start,
+            ],
+            Tool::Ellipse => {
+                let (cx, cy) = ((start.x + end.x) / 2.0, (start.y + end.y) / 2.0);
+                let (rx, ry) = ((end.x - start.x).abs() / 2.0, (end.y - start.y).abs() / 2.0);
+                (0..=32)
+                    .map(|step| {
+                        let angle = step as f64 * std::f64::consts::TAU / 32.0;
+                        Point::new(cx + rx * angle.cos(), cy + ry * angle.sin())
+                    })
+                    .collect()
+            }
+            Tool::Text => {
+                // glyphs are about half as wide as the font is high
+                let size = self.font_size();
+                let width = self.text.chars().count() as f64 * size * 0.6;
+                vec![
+                    *start,
+                    Point::new(start.x + width, start.y),
+                    Point::new(start.x + width, start.y - size),
+                    Point::new(start.x, start.y - size),
+                    *start,
+                ]
+            }
+        }
+    }
+
+    pub fn is_hit(&self, point: &Point, radius: f64) -> bool {
+        // an image is picked up anywhere inside, not only by its edge
+        if let (Tool::Image, Some(start), Some(end)) = (self.tool, self.points.first(), self.points.last()) {
+            let inside_x = start.x.min(end.x) <= point.x && point.x <= start.x.max(end.x);
+            let inside_y = start.y.min(end.y) <= point.y && point.y <= start.y.max(end.y);
+            if inside_x && inside_y {
+                return true;
+            }
+        }
+        let outline = self.outline();
+        let reach = radius + self.width / 2.0;
+        match outline.as_slice() {
+            [] => false,
+            [single] => point.distance_to_segment(single, single) <= reach,
+            _ => outline
+                .windows(2)
+                .any(|segment| point.distance_to_segment(&segment[0], &segment[1]) <= reach),
+        }
+    }
+}
+
+/// Change to a board, small enough to send on every pointer move.
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub enum BoardOperation {
+    Add {
+        stroke: Stroke,
+    },
+    /// Appends points to a stroke that is still being drawn.
+    Extend {
+        id: String,
+        points: Vec<Point>,
+    },
+    /// Replaces a stroke, shapes are resized this way while dragging.
+    Update {
+        stroke: Stroke,
+    },
+    Delete {
+        id: String,
+    },
+    /// Replaces the points of a stroke, so that a moved image is not sent again.
+    Move {
+        id: String,
+        points: Vec<Point>,
+    },
+}
+
+impl BoardOperation {
+    pub fn stroke_id(&self) -> &str {
+        match self {
+            BoardOperation::Add { stroke } | BoardOperation::Update { stroke } => &stroke.id,
+            BoardOperation::Extend { id, .. } | BoardOperation::Move { id, .. } | BoardOperation::Delete { id } => id,
+        }
+    }
+
+    /// Marks new and replaced strokes as drawn by `author`, whatever the sender claimed.
+    pub fn with_author(self, author: &str) -> Self {
+        match self {
+            BoardOperation::Add { mut stroke } => {
+                stroke.set_author(Some(author.to_owned()));
+                BoardOperation::Add { stroke }
+            }
+            BoardOperation::Update { mut stroke } => {
+                stroke.set_author(Some(author.to_owned()));
+                BoardOperation::Update { stroke }
+            }
+            operation => operation,
+        }
+    }
+}
+
+/// Students allowed to draw on the board, either all of them or the listed ids.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct BoardPermission {
+    pub everyone: bool,
+    students: HashSet<String>,
+}
+
+impl BoardPermission {
+    pub fn allows(&self, user_id: &str) -> bool {
+        self.everyone || self.students.contains(user_id)
+    }
+
+    pub fn set_everyone(&mut self, everyone: bool) {
+        self.everyone = everyone;
+    }
+
+    pub fn set_student(&mut self, user_id: String, allowed: bool) {
+        if allowed {
+            self.students.insert(user_id);
+        } else {
+            self.students.remove(&user_id);
+        }
+    }
+}
+
+/// What is printed on the sheet under the text and the strokes.
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Debug, Default)]
+pub enum Background {
+    #[default]
+    Plain,
+    /// Graph paper, every fifth line is thicker.
+    Graph,
+    Dots,
+    /// Ruled lines under the lines of the page text.
+    Lined,
+}
+
+/// A line of the background, in board units.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct GridLine {
+    pub start: Point,
+    pub end: Point,
+    pub width: f64,
+}
+
+impl Background {
+    pub const ALL: [Background; 4] = [Background::Plain, Background::Graph, Background::Dots, Background::Lined];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Background::Plain => "Без фона",
+            Background::Graph => "Клетка",
+            Background::Dots => "Точки",
+            Background::Lined => "Линейка",
+        }
+    }
+
+    /// Offsets of the grid along a side of `length`, with the index of the cell.
+    fn steps(length: u32) -> impl Iterator<Item = (usize, f64)> {
+        (1..)
+            .map(|index| (index, index as f64 * BOARD_GRID_STEP))
+            .take_while(move |(_, offset)| *offset < length as f64)
+    }
+
+    pub fn lines(&self) -> Vec<GridLine> {
+        let (width, height) = (BOARD_WIDTH as f64, BOARD_HEIGHT as f64);
+        match self {
+            Background::Plain | Background::Dots => Vec::new(),
+            Background::Graph => {
+                let width_of = |index: usize| if index % 5 == 0 { 1.0 } else { 0.5 };
+                let vertical = Self::steps(BOARD_WIDTH).map(|(index, x)| GridLine {
+                    start: Point::new(x, 0.0),
+                    end: Point::new(x, height),
+                    width: width_of(index),
+                });
+                let horizontal = Self::steps(BOARD_HEIGHT).map(|(index, y)| GridLine {
+                    start: Point::new(0.0, y),
+                    end: Point::new(width, y),
+                    width: width_of(index),
+                });
+                vertical.chain(horizontal).collect()
+            }
+            Background::Lined => {
+                // just below the baseline, where the page text is written
+                Self::steps(BOARD_HEIGHT)
+                    .map(|(_, y)| y + 4.0)
+                    .filter(|y| *y < height)
+                    .map(|y| GridLine {
+                        start: Point::new(0.0, y),
+                        end: Point::new(width, y),
+                        width: 0.5,
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    pub fn dots(&self) -> Vec<Point> {
+        match self {
+            Background::Dots => Self::steps(BOARD_WIDTH)
+                .flat_map(|(_, x)| Self::steps(BOARD_HEIGHT).map(move |(_, y)| Point::new(x, y)))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// One page of the whiteboard: the code it was opened over and the strokes on top.
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug, Default)]
+pub struct Board {
+    pub id: String,
+    pub text: String,
+    pub strokes: Vec<Stroke>,
+    #[serde(default)]
+    pub background: Background,
+}
+
+impl Board {
+    pub fn new(text: String) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            text,
+            strokes: Vec::new(),
+            background: Background::default(),
+        }
+    }
+
+    pub fn stroke(&self, id: &str) -> Option<&Stroke> {
+        self.strokes.iter().find(|stroke| stroke.id == id)
+    }
+
+    fn stroke_mut(&mut self, id: &str) -> crate::Result<&mut Stroke> {
+        self.strokes
+            .iter_mut()
+            .find(|stroke| stroke.id == id)
+            .ok_or_else(|| crate::Error::InvalidBoard(format!("stroke with id '{}' not found", id)))
+    }
+
+    /// Operations that bring a copy of this page back in line on stroke `id`,
+    /// whatever the copy did to it.
+    pub fn resync_stroke(&self, id: &str) -> Vec<BoardOperation> {
+        let delete = BoardOperation::Delete { id: id.to_owned() };
+        match self.stroke(id) {
+            Some(stroke) => vec![delete, BoardOperation::Add { stroke: stroke.clone() }],
+            None => vec![delete],
+        }
+    }
+
+    /// Ids of the strokes within `radius` of `point`, topmost first.
+    pub fn hits(&self, point: &Point, radius: f64) -> Vec<String> {
+        self.strokes
+            .iter()
+            .rev()
+            .filter(|stroke| stroke.is_hit(point, radius))
+            .map(|stroke| stroke.id.clone())
+            .collect()
+    }
+
+    /// Ids of the strokes drawn by `author`.
+    pub fn strokes_by(&self, author: &str) -> Vec<String> {
+        self.strokes
+            .iter()
+            .filter(|stroke| stroke.author.as_deref() == Some(author))
+            .map(|stroke| stroke.id.clone())
+            .collect()
+    }
+
+    /// Applies an operation of a student, who may only touch their own strokes.
+    pub fn apply_as(&mut self, operation: &BoardOperation, author: &str) -> crate::Result<()> {
+        let is_own = |stroke: &Stroke| stroke.author.as_deref() == Some(author);
+        let allowed = match operation {
+            BoardOperation::Add { stroke } => is_own(stroke),
+            BoardOperation::Update { stroke } => is_own(stroke) && self.stroke(&stroke.id).map_or(true, is_own),
+            BoardOperation::Extend { id, .. } | BoardOperation::Move { id, .. } | BoardOperation::Delete { id } => {
+                self.stroke(id).map_or(true, is_own)
+            }
+        };
+        if !allowed {
+            return Err(crate::Error::InvalidBoard(format!("stroke '{}' belongs to somebody else", operation.stroke_id())));
+        }
+        if let BoardOperation::Add { stroke } | BoardOperation::Update { stroke } = operation {
+            if stroke.tool == Tool::Image && stroke.image_src().is_none() {
+                return Err(crate::Error::InvalidBoard(format!("image '{}' is not an embedded picture or too large", stroke.id)));
+            }
+        }
+        self.apply(operation)
+    }
+
+    pub fn apply(&mut self, operation: &BoardOperation) -> crate::Result<()> {
+        match operation {
+            BoardOperation::Add { stroke } => {
+                if self.stroke(&stroke.id).is_some() {
+                    return Err(crate::Error::InvalidBoard(format!("stroke with id '{}' already exists", stroke.id)));
+                }
+                self.strokes.push(stroke.clone());
+            }
+            BoardOperation::Extend { id, points } => {
+                self.stroke_mut(id)?.points.extend_from_slice(points);
+            }
+            BoardOperation::Update { stroke } => {
+                *self.stroke_mut(&stroke.id)? = stroke.clone();
+            }
+            BoardOperation::Delete { id } => {
+                self.stroke_mut(id)?;
+                self.strokes.retain(|stroke| &stroke.id != id);
+            }
+            BoardOperation::Move { id, points } => {
+                self.stroke_mut(id)?.points = points.clone();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Changes to the page list of a whiteboard. Like workspace files, pages are
+/// addressed by id so that reordering doesn't break strokes in flight.
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub enum PageAction {
+    /// Adds a page after the current one and shows it.
+    Create {
+        id: String,
+        text: String,
+        #[serde(default)]
+        background: Background,
+    },
+    Delete {
+        id: String,
+    },
+    Move {
+        id: String,
+        index: usize,
+    },
+    Show {
+        id: String,
+    },
+    SetBackground {
+        id: String,
+        background: Background,
+    },
+}
+
+impl PageAction {
+    pub fn create(text: String, background: Background) -> Self {
+        Self::Create {
+            id: uuid::Uuid::new_v4().to_string(),
+            text,
+            background,
+        }
+    }
+}
+
+/// Pages of the board and the one the host is showing.
+/// A whiteboard always has at least one page.
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct Whiteboard {
+    pub pages: Vec<Board>,
+    pub current: String,
+}
+
+impl Whiteboard {
+    pub fn new(text: String) -> Self {
+        let page = Board::new(text);
+        Self {
+            current: page.id.clone(),
+            pages: vec![page],
+        }
+    }
+
+    pub fn page(&self, id: &str) -> Option<&Board> {
+        self.pages.iter().find(|page| page.id == id)
+    }
+
+    pub fn page_mut(&mut self, id: &str) -> crate::Result<&mut Board> {
+        self.pages
+            .iter_mut()
+            .find(|page| page.id == id)
+            .ok_or_else(|| crate::Error::InvalidBoard(format!("page with id '{}' not found", id)))
+    }
+
+    pub fn page_index(&self, id: &str) -> Option<usize> {
+        self.pages.iter().position(|page| page.id == id)
+    }
+
+    pub fn current_page(&self) -> Option<&Board> {
+        self.page(&self.current)
+    }
+
+    fn check_page(&self, id: &str) -> crate::Result<usize> {
+        self.page_index(id)
+            .ok_or_else(|| crate::Error::InvalidBoard(format!("page with id '{}' not found", id)))
+    }
+
+    pub fn apply(&mut self, action: &PageAction) -> crate::Result<()> {
+        match action {
+            PageAction::Create { id, text, background } => {
+                if self.page(id).is_some() {
+                    return Err(crate::Error::InvalidBoard(format!("page with id '{}' already exists", id)));
+                }
+                let index = self.page_index(&self.current).map_or(self.pages.len(), |index| index + 1);
+                self.pages.insert(index, Board {
+                    id: id.clone(),
+                    text: text.clone(),
+                    strokes: Vec::new(),
+                    background: *background,
+                });
+                self.current = id.clone();
+            }
+            PageAction::Delete { id } => {
+                let index = self.check_page(id)?;
+                if self.pages.len() == 1 {
+                    return Err(crate::Error::InvalidBoard("cannot delete the last page".to_owned()));
+                }
+                self.pages.remove(index);
+                if &self.current == id {
+                    self.current = self.pages[index.min(self.pages.len() - 1)].id.clone();
+                }
+            }
+            PageAction::Move { id, index } => {
+                let from = self.check_page(id)?;
+                let page = self.pages.remove(from);
+                self.pages.insert((*index).min(self.pages.len()), page);
+            }
+            PageAction::Show { id } => {
+                self.check_page(id)?;
+                self.current = id.clone();
+            }
+            PageAction::SetBackground { id, background } => {
+                self.page_mut(id)?.background = *background;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A stroke before and after an edit, `None` when it did not exist.
+#[derive(PartialEq, Clone, Debug)]
+struct StrokeChange {
+    before: Option<Stroke>,
+    after: Option<Stroke>,
+}
+
+impl StrokeChange {
+    fn id(&self) -> Option<&str> {
+        self.before
+            .as_ref()
+            .or(self.after.as_ref())
+            .map(|stroke| stroke.id.as_str())
+    }
+
+    /// Operation that brings the board from one side of the change to the other.
+    fn operation(id: &str, from: &Option<Stroke>, to: &Option<Stroke>) -> Option<BoardOperation> {
+        match (from, to) {
+            (None, Some(stroke)) => Some(BoardOperation::Add { stroke: stroke.clone() }),
+            (Some(_), Some(stroke)) => Some(BoardOperation::Update { stroke: stroke.clone() }),
+            (Some(_), None) => Some(BoardOperation::Delete { id: id.to_owned() }),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Undo and redo of the host's own board edits. One entry is one gesture:
+/// a stroke with all its points, or everything wiped by one eraser drag.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct BoardHistory {
+    undo_stack: Vec<Vec<StrokeChange>>,
+    redo_stack: Vec<Vec<StrokeChange>>,
+    last_delete_at: Option<f64>,
+    last_move_at: Option<f64>,
+}
+
+impl BoardHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Stores an operation that was applied to `board`, `before` is the stroke
+    /// it touched as it was before, `now` is in milliseconds.
+    pub fn record(&mut self, board: &Board, operation: &BoardOperation, before: Option<Stroke>, now: f64) {
+        let id = operation.stroke_id();
+        let change = StrokeChange { before, after: board.stroke(id).cloned() };
+        let last_delete_at = self.last_delete_at.take();
+        let last_move_at = self.last_move_at.take();
+        self.redo_stack.clear();
+        match operation {
+            BoardOperation::Extend { .. } | BoardOperation::Update { .. } => {
+                // points of the stroke that is still being drawn
+                let current = self.undo_stack
+                    .last_mut()
+                    .and_then(|entry| entry.iter_mut().find(|current| current.id() == Some(id)));
+                if let Some(current) = current {
+                    current.after = change.after;
+                    return;
+                }
+            }
+            BoardOperation::Delete { .. } => {
+                self.last_delete_at = Some(now);
+                let is_erasing = last_delete_at.is_some_and(|last| now - last < HISTORY_MERGE_INTERVAL_MS);
+                if let Some(entry) = self.undo_stack.last_mut().filter(|_| is_erasing) {
+                    entry.push(change);
+                    return;
+                }
+            }
+            BoardOperation::Move { .. } => {
+                // one drag is one entry, even right after the stroke was drawn
+                self.last_move_at = Some(now);
+                let is_dragging = last_move_at.is_some_and(|last| now - last < HISTORY_MERGE_INTERVAL_MS);
+                let current = self.undo_stack
+                    .last_mut()
+                    .filter(|_| is_dragging)
+                    .and_then(|entry| entry.iter_mut().find(|current| current.id() == Some(id)));
+                if let Some(current) = current {
+                    current.after = change.after;
+                    return;
+                }
+            }
+            BoardOperation::Add { .. } => {}
+        }
+        self.undo_stack.push(vec![change]);
+        if self.undo_stack.len() > BOARD_HISTORY_MAX_LEN {
+            self.undo_stack.remove(0);
+        }
+    }
+
+    /// Returns the operations to apply to the board, in order.
+    pub fn apply(&mut self, action: HistoryAction) -> Vec<BoardOperation> {
+        let (from, to) = match action {
+            HistoryAction::Undo => (&mut self.undo_stack, &mut self.redo_stack),
+            HistoryAction::Redo => (&mut self.redo_stack, &mut self.undo_stack),
+        };
+        let Some(entry) = from.pop() else {
+            return Vec::new();
+        };
+        let operations = match action {
+            HistoryAction::Undo => entry
+                .iter()
+                .rev()
+                .filter_map(|change| StrokeChange::operation(change.id()?, &change.after, &change.before))
+                .collect(),
+            HistoryAction::Redo => entry
+                .iter()
+                .filter_map(|change| StrokeChange::operation(change.id()?, &change.before, &change.after))
+                .collect(),
+        };
+        to.push(entry);
+        self.last_delete_at = None;
+        self.last_move_at = None;
+        operations
+    }
+}
@@ -0,0 +1,359 @@
+use std::f64::consts::PI;
+use std::fmt::Write;
+
+use crate::constants::{BOARD_DEFAULT_COLOR, BOARD_GRID_COLOR, BOARD_HEIGHT, BOARD_WIDTH};
+
+use super::board::{Background, Board, Point, Stroke, Tool, Whiteboard};
+
+/// Escapes text and attribute values for XML.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Coordinates with at most two decimals, which is far below a pixel.
+fn number(value: f64) -> String {
+    let rounded = (value * 100.0).round() / 100.0;
+    // avoids "-0"
+    if rounded == 0.0 {
+        "0".to_owned()
+    } else {
+        rounded.to_string()
+    }
+}
+
+fn path_data(points: &[Point]) -> String {
+    points
+        .iter()
+        .enumerate()
+        .map(|(index, point)| format!("{}{} {}", if index == 0 { "M" } else { "L" }, number(point.x), number(point.y)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn line_attributes(stroke: &Stroke) -> String {
+    format!(
+        r#"fill="none" stroke="{}" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round""#,
+        escape(&stroke.color),
+        number(stroke.width),
+    )
+}
+
+/// The same shapes `PaintF` draws on the canvas, as SVG elements.
+fn stroke_svg(stroke: &Stroke) -> String {
+    let (Some(start), Some(end)) = (stroke.points.first(), stroke.points.last()) else {
+        return String::new();
+    };
+    match stroke.tool {
+        Tool::Pen if stroke.has_pressure() => {
+            let mut previous = start;
+            let mut segments = String::new();
+            for point in &stroke.points {
+                let _ = write!(
+                    segments,
+                    r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke-width="{}"/>"#,
+                    number(previous.x),
+                    number(previous.y),
+                    number(point.x),
+                    number(point.y),
+                    number((stroke.width_at(previous) + stroke.width_at(point)) / 2.0),
+                );
+                previous = point;
+            }
+            format!(
+                r#"<g stroke="{}" stroke-linecap="round">{}</g>"#,
+                escape(&stroke.color),
+                segments,
+            )
+        },
+        Tool::Pen => {
+            // a single click still leaves a dot
+            let points = if stroke.points.len() == 1 { vec![*start, *start] } else { stroke.points.clone() };
+            format!(r#"<path d="{}" {}/>"#, path_data(&points), line_attributes(stroke))
+        },
+        Tool::Line => {
+            format!(r#"<path d="{}" {}/>"#, path_data(&[*start, *end]), line_attributes(stroke))
+        },
+        Tool::Arrow => {
+            let angle = (end.y - start.y).atan2(end.x - start.x);
+            let head = (stroke.width * 4.0).max(10.0);
+            let mut data = path_data(&[*start, *end]);
+            for side in [PI / 6.0, -PI / 6.0] {
+                let tip = Point::new(end.x - head * (angle + side).cos(), end.y - head * (angle + side).sin());
+                let _ = write!(data, " {}", path_data(&[*end, tip]));
+            }
+            format!(r#"<path d="{}" {}/>"#, data, line_attributes(stroke))
+        },
+        Tool::Rect => {
+            format!(
+                r#"<rect x="{}" y="{}" width="{}" height="{}" {}/>"#,
+                number(start.x.min(end.x)),
+                number(start.y.min(end.y)),
+                number((end.x - start.x).abs()),
+                number((end.y - start.y).abs()),
+                line_attributes(stroke),
+            )
+        },
+        Tool::Ellipse => {
+            format!(
+                r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}" {}/>"#,
+                number((start.x + end.x) / 2.0),
+                number((start.y + end.y) / 2.0),
+                number((end.x - start.x).abs() / 2.0),
+                number((end.y - start.y).abs() / 2.0),
+                line_attributes(stroke),
+            )
+        },
+        Tool::Text => {
+            format!(
+                r#"<text x="{}" y="{}" font-family="Arial" font-size="{}" fill="{}" xml:space="preserve">{}</text>"#,
+                number(start.x),
+                number(start.y),
+                number(stroke.font_size()),
+                escape(&stroke.color),
+                escape(&stroke.text),
+            )
+        },
+        Tool::Image => {
+            // nothing is fetched when the file is opened
+            let Some(src) = stroke.image_src() else {
+                return String::new();
+            };
+            format!(
+                r#"<image href="{}" x="{}" y="{}" width="{}" height="{}" preserveAspectRatio="none"/>"#,
+                escape(src),
+                number(start.x.min(end.x)),
+                number(start.y.min(end.y)),
+                number((end.x - start.x).abs()),
+                number((end.y - start.y).abs()),
+            )
+        },
+        Tool::Eraser | Tool::Move => String::new(),
+    }
+}
+
+fn background_svg(background: Background) -> String {
+    let mut svg = format!(r#"<g stroke="{color}" fill="{color}">"#, color = BOARD_GRID_COLOR);
+    for line in background.lines() {
+        let _ = write!(
+            svg,
+            r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke-width="{}"/>"#,
+            number(line.start.x),
+            number(line.start.y),
+            number(line.end.x),
+            number(line.end.y),
+            number(line.width),
+        );
+    }
+    for dot in background.dots() {
+        let _ = write!(svg, r#"<circle cx="{}" cy="{}" r="1" stroke="none"/>"#, number(dot.x), number(dot.y));
+    }
+    svg.push_str("</g>");
+    svg
+}
+
+/// A page as a standalone SVG document: a white sheet with its background,
+/// the code the board was opened over, and the strokes in drawing order.
+pub fn board_svg(board: &Board) -> String {
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#,
+        width = BOARD_WIDTH,
+        height = BOARD_HEIGHT,
+    );
+    let _ = write!(svg, r#"<rect width="{}" height="{}" fill="white"/>"#, BOARD_WIDTH, BOARD_HEIGHT);
+    if board.background != Background::Plain {
+        svg.push_str(&background_svg(board.background));
+    }
+    let _ = write!(svg, r#"<g font-family="Arial" font-size="20" fill="{}" xml:space="preserve">"#, BOARD_DEFAULT_COLOR);
+    for (index, line) in board.text.lines().enumerate() {
+        let _ = write!(svg, r#"<text x="10" y="{}">{}</text>"#, 20 * (index + 1), escape(line));
+    }
+    svg.push_str("</g>");
+    board.strokes
+        .iter()
+        .for_each(|stroke| svg.push_str(&stroke_svg(stroke)));
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Every page of the whiteboard in one HTML file, one page under another,
+/// which opens in any browser and prints a page per sheet.
+pub fn whiteboard_html(whiteboard: &Whiteboard) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Доска</title>\
+        <style>svg { display: block; margin: 0 auto 16px; border: 1px solid #ccc; break-after: page; }</style>\
+        </head><body>",
+    );
+    whiteboard.pages
+        .iter()
+        .for_each(|page| html.push_str(&board_svg(page)));
+    html.push_str("</body></html>");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{board_svg, escape, number, stroke_svg, whiteboard_html};
+    use crate::{constants::BOARD_GRID_COLOR, models::board::{Background, Board, PageAction, Point, Stroke, Tool, Whiteboard}};
+
+    fn stroke(tool: Tool, points: &[(f64, f64)]) -> Stroke {
+        Stroke {
+            points: points.iter().map(|&(x, y)| Point::new(x, y)).collect(),
+            ..Stroke::new(tool, "red".to_owned(), 2.0, Point::new(0.0, 0.0))
+        }
+    }
+
+    #[test]
+    fn escapes_markup() {
+        assert_eq!(escape(r#"a & b < c > "d" 'e'"#), "a &amp; b &lt; c &gt; &quot;d&quot; &apos;e&apos;");
+        assert_eq!(escape("Доска"), "Доска");
+    }
+
+    #[test]
+    fn rounds_numbers() {
+        assert_eq!(number(1.234), "1.23");
+        assert_eq!(number(2.999), "3");
+        assert_eq!(number(1.0), "1");
+        assert_eq!(number(-1.5), "-1.5");
+        assert_eq!(number(-0.001), "0");
+        assert_eq!(number(-0.0), "0");
+    }
+
+    #[test]
+    fn pen_is_a_path() {
+        let svg = stroke_svg(&stroke(Tool::Pen, &[(0.0, 0.0), (10.5, 20.25), (30.0, 40.0)]));
+        assert!(svg.starts_with(r#"<path d="M0 0 L10.5 20.25 L30 40" fill="none" stroke="red" stroke-width="2""#), "{}", svg);
+    }
+
+    #[test]
+    fn single_point_pen_leaves_a_dot() {
+        let svg = stroke_svg(&stroke(Tool::Pen, &[(5.0, 5.0)]));
+        assert!(svg.contains(r#"d="M5 5 L5 5""#), "{}", svg);
+        assert!(svg.contains(r#"stroke-linecap="round""#));
+    }
+
+    #[test]
+    fn pressure_pen_is_segments_of_their_own_width() {
+        let mut pen = stroke(Tool::Pen, &[]);
+        pen.points = vec![
+            Point::new(0.0, 0.0).with_pressure(0.5),
+            Point::new(10.0, 0.0).with_pressure(0.5),
+            Point::new(20.0, 0.0).with_pressure(1.0),
+        ];
+        let svg = stroke_svg(&pen);
+        assert!(svg.starts_with(r#"<g stroke="red" stroke-linecap="round">"#), "{}", svg);
+        assert_eq!(svg.matches("<line ").count(), 3);
+        assert!(svg.contains(r#"<line x1="0" y1="0" x2="10" y2="0" stroke-width="2"/>"#), "{}", svg);
+        assert!(svg.contains(r#"<line x1="10" y1="0" x2="20" y2="0" stroke-width="2.75"/>"#), "{}", svg);
+    }
+
+    #[test]
+    fn line_goes_from_the_first_point_to_the_last() {
+        let svg = stroke_svg(&stroke(Tool::Line, &[(1.0, 2.0), (5.0, 5.0), (3.0, 4.0)]));
+        assert!(svg.starts_with(r#"<path d="M1 2 L3 4""#), "{}", svg);
+    }
+
+    #[test]
+    fn arrow_has_a_head() {
+        let svg = stroke_svg(&stroke(Tool::Arrow, &[(0.0, 0.0), (100.0, 0.0)]));
+        assert_eq!(svg.matches('M').count(), 3, "{}", svg);
+        assert!(svg.contains("M0 0 L100 0 M100 0 L91.34 -5 M100 0 L91.34 5"), "{}", svg);
+    }
+
+    #[test]
+    fn shapes_are_drawn_from_either_corner() {
+        let svg = stroke_svg(&stroke(Tool::Rect, &[(30.0, 40.0), (10.0, 20.0)]));
+        assert!(svg.starts_with(r#"<rect x="10" y="20" width="20" height="20""#), "{}", svg);
+        let svg = stroke_svg(&stroke(Tool::Ellipse, &[(30.0, 40.0), (10.0, 20.0)]));
+        assert!(svg.starts_with(r#"<ellipse cx="20" cy="30" rx="10" ry="10""#), "{}", svg);
+    }
+
+    #[test]
+    fn text_is_escaped() {
+        let mut text = Stroke::text_box(r#"red" onload="x"#.to_owned(), 2.0, Point::new(5.0, 6.0), "a < b && c".to_owned());
+        text.points = vec![Point::new(5.0, 6.0)];
+        let svg = stroke_svg(&text);
+        assert!(svg.contains(r#"fill="red&quot; onload=&quot;x""#), "{}", svg);
+        assert!(svg.contains(">a &lt; b &amp;&amp; c</text>"), "{}", svg);
+        assert!(svg.contains(r#"x="5" y="6" font-family="Arial" font-size="20""#), "{}", svg);
+    }
+
+    #[test]
+    fn image_is_placed_between_its_corners() {
+        let image = Stroke::image("data:image/png;base64,AAAA".to_owned(), Point::new(50.0, 50.0), 40.0, 20.0);
+        let svg = stroke_svg(&image);
+        assert_eq!(
+            svg,
+            r#"<image href="data:image/png;base64,AAAA" x="30" y="40" width="40" height="20" preserveAspectRatio="none"/>"#,
+        );
+    }
+
+    #[test]
+    fn image_from_elsewhere_is_left_out() {
+        let image = Stroke::image("https://example.com/a.png".to_owned(), Point::new(50.0, 50.0), 40.0, 20.0);
+        assert_eq!(stroke_svg(&image), "");
+    }
+
+    #[test]
+    fn tools_leaving_nothing() {
+        assert_eq!(stroke_svg(&stroke(Tool::Eraser, &[(0.0, 0.0)])), "");
+        assert_eq!(stroke_svg(&stroke(Tool::Move, &[(0.0, 0.0)])), "");
+        assert_eq!(stroke_svg(&stroke(Tool::Pen, &[])), "");
+    }
+
+    #[test]
+    fn backgrounds() {
+        let mut board = Board::new("fn main() {}".to_owned());
+        assert!(!board_svg(&board).contains(BOARD_GRID_COLOR));
+        board.background = Background::Graph;
+        let svg = board_svg(&board);
+        assert!(svg.contains(BOARD_GRID_COLOR));
+        assert!(svg.contains("<line "));
+        assert!(!svg.contains("<circle "));
+        board.background = Background::Dots;
+        let svg = board_svg(&board);
+        assert!(svg.contains("<circle "));
+        assert!(!svg.contains("<line "));
+        board.background = Background::Lined;
+        let svg = board_svg(&board);
+        assert_eq!(svg.matches("<line ").count(), Background::Lined.lines().len());
+    }
+
+    #[test]
+    fn board_has_its_text_under_the_strokes() {
+        let mut board = Board::new("let a = 1 < 2;\nlet b = 3;".to_owned());
+        board.strokes.push(stroke(Tool::Line, &[(0.0, 0.0), (1.0, 1.0)]));
+        let svg = board_svg(&board);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.ends_with("</svg>"));
+        let first = svg.find(r#"<text x="10" y="20">let a = 1 &lt; 2;</text>"#).unwrap();
+        let second = svg.find(r#"<text x="10" y="40">let b = 3;</text>"#).unwrap();
+        let line = svg.find("<path ").unwrap();
+        assert!(first < second && second < line);
+    }
+
+    #[test]
+    fn whiteboard_has_every_page_in_order() {
+        let mut whiteboard = Whiteboard::new("первая".to_owned());
+        whiteboard.apply(&PageAction::create("вторая".to_owned(), Background::Plain)).unwrap();
+        whiteboard.apply(&PageAction::create("третья".to_owned(), Background::Dots)).unwrap();
+        let html = whiteboard_html(&whiteboard);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.ends_with("</body></html>"));
+        assert_eq!(html.matches("<svg ").count(), 3);
+        let first = html.find("первая").unwrap();
+        let second = html.find("вторая").unwrap();
+        let third = html.find("третья").unwrap();
+        assert!(first < second && second < third);
+    }
+}
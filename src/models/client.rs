@@ -1,38 +1,142 @@
+use std::collections::HashMap;
+
 use yew::Properties;
 
-use super::commons::{AreaKind, TextAreaProps, PaintProps};
+use super::{assignment::Assignment, commons::{AreaKind, InitUser, TextAreaProps, PaintProps}, history::{EditHistory, HistoryAction}, presence::RemoteCursor, review::{Review, ReviewMessage, ReviewThread}, sync::{SyncClient, SyncServer}, text_operation::TextOperation, workspace::{DocumentKey, Workspace, WorkspaceAction}};
+
+
+fn document_content_mut<'a>(workspace: &'a mut Workspace, text_area: &'a mut TextAreaProps, document: &DocumentKey) -> crate::Result<&'a mut String> {
+    match document {
+        DocumentKey::File(id) => workspace.content_mut(id),
+        DocumentKey::TextArea => Some(&mut text_area.content),
+    }
+    .ok_or_else(|| crate::Error::InvalidWorkspace(format!("unknown document {:?}", document)))
+}
 
+// A history that can't follow a remote edit is dropped, the edit itself still has to be applied.
+fn transform_history(history: &mut EditHistory, operation: &TextOperation) {
+    if let Err(err) = history.transform(operation) {
+        log::error!("cannot transform edit history: {}", err);
+        *history = EditHistory::new();
+    }
+}
 
 #[derive(Clone, PartialEq, Properties)]
 pub struct ClientProps {
-    pub client_editor_content: String,
+    pub workspace: Workspace,
     pub client_text_area: TextAreaProps,
     pub client_id: String,
     pub is_write: bool,
     pub client_area_kind: AreaKind,
     pub paint_props: PaintProps,
+    pub syncs: HashMap<DocumentKey, SyncClient>,
+    pub histories: HashMap<DocumentKey, EditHistory>,
+    pub remote_cursor: Option<RemoteCursor>,
+    pub assignment: Option<Assignment>,
+    pub submitted_at: Option<f64>,
+    pub review: Review,
 }
 
 impl ClientProps {
     pub fn new() -> Self {
         Self { 
-            client_editor_content: String::default(),
+            workspace: Workspace::new(),
             client_text_area: TextAreaProps::new(),
             client_id: String::default(),
             is_write: false,
             client_area_kind: AreaKind::TextArea,
             paint_props: PaintProps::new(),  
+            syncs: HashMap::new(),
+            histories: HashMap::new(),
+            remote_cursor: None,
+            assignment: None,
+            submitted_at: None,
+            review: Review::default(),
          }
     }
 
-    pub fn set_editor_content(&mut self, content: String) {
-        self.client_editor_content = content;
-    }
-
     pub fn set_text_area_content(&mut self, content: String) {
         self.client_text_area.set_content(content);
     }
 
+    pub fn set_workspace(&mut self, workspace: Workspace) {
+        self.workspace = workspace;
+        self.histories.clear();
+    }
+
+    /// Replaces all files, the text area keeps its history.
+    pub fn replace_workspace(&mut self, workspace: Workspace) {
+        self.workspace = workspace;
+        self.syncs.retain(|document, _| *document == DocumentKey::TextArea);
+        self.histories.retain(|document, _| *document == DocumentKey::TextArea);
+    }
+
+    pub fn set_assignment(&mut self, assignment: Assignment) {
+        self.replace_workspace(assignment.workspace.clone());
+        self.assignment = Some(assignment);
+        self.submitted_at = None;
+    }
+
+    pub fn set_submitted_at(&mut self, submitted_at: f64) {
+        self.submitted_at = Some(submitted_at);
+    }
+
+    /// Sets the content of a document changed by the other side, keeping the local history.
+    /// Returns `false` if the document doesn't exist.
+    pub fn set_document_content(&mut self, document: &DocumentKey, content: String) -> bool {
+        let Ok(current) = document_content_mut(&mut self.workspace, &mut self.client_text_area, document) else {
+            return false;
+        };
+        let operation = TextOperation::diff(current, &content);
+        self.review.transform(document, current, &operation);
+        *current = content;
+        if let Some(history) = self.histories.get_mut(document) {
+            transform_history(history, &operation);
+        }
+        true
+    }
+
+    /// Sets the content of a document edited here, so it can be undone.
+    pub fn set_own_document_content(&mut self, document: &DocumentKey, content: String) -> crate::Result<()> {
+        let current = document_content_mut(&mut self.workspace, &mut self.client_text_area, document)?;
+        let operation = TextOperation::diff(current, &content);
+        if operation.is_noop() {
+            return Ok(());
+        }
+        self.review.transform(document, current, &operation);
+        let previous = std::mem::replace(current, content);
+        self.histories
+            .entry(document.clone())
+            .or_default()
+            .record(&previous, &operation, js_sys::Date::now())
+    }
+
+    /// Undoes or redoes a local edit on the host side and returns the new content.
+    pub fn apply_own_history(&mut self, document: &DocumentKey, action: HistoryAction) -> crate::Result<Option<String>> {
+        let Some(history) = self.histories.get_mut(document) else {
+            return Ok(None);
+        };
+        let current = document_content_mut(&mut self.workspace, &mut self.client_text_area, document)?;
+        match history.apply(action, current)? {
+            Some(operation) => {
+                let content = operation.apply(current)?;
+                self.review.transform(document, current, &operation);
+                *current = content;
+                Ok(Some(current.clone()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn apply_workspace_action(&mut self, action: &WorkspaceAction) -> crate::Result<()> {
+        self.workspace.apply(action)?;
+        if let WorkspaceAction::Delete { id } = action {
+            self.syncs.remove(&DocumentKey::File(id.clone()));
+            self.histories.remove(&DocumentKey::File(id.clone()));
+        }
+        Ok(())
+    }
+
     pub fn set_area_kind(&mut self, area_kind: AreaKind) {
         self.client_area_kind = area_kind;
     }
@@ -44,26 +148,118 @@ impl ClientProps {
     pub fn set_is_write(&mut self, is_write: bool) {
         self.is_write = is_write;
     }
+
+    pub fn set_remote_cursor(&mut self, remote_cursor: Option<RemoteCursor>) {
+        self.remote_cursor = remote_cursor;
+    }
+
+    pub fn set_review(&mut self, review: Review) {
+        self.review = review;
+    }
+
+    pub fn active_review(&self) -> Vec<ReviewThread> {
+        self.review.for_file(&self.workspace.active).cloned().collect()
+    }
+
+    fn sync_parts_mut(&mut self, document: &DocumentKey) -> crate::Result<(&mut String, &mut SyncClient, &mut EditHistory, &mut Review)> {
+        let current = document_content_mut(&mut self.workspace, &mut self.client_text_area, document)?;
+        Ok((
+            current,
+            self.syncs.entry(document.clone()).or_default(),
+            self.histories.entry(document.clone()).or_default(),
+            &mut self.review,
+        ))
+    }
+
+    pub fn reset_sync(&mut self) {
+        self.syncs.clear();
+    }
+
+    /// Stores a local edit and returns the operation that has to be sent to the host, if any.
+    pub fn apply_local_content(&mut self, document: &DocumentKey, content: String) -> crate::Result<Option<(u64, TextOperation)>> {
+        let (current, sync, history, review) = self.sync_parts_mut(document)?;
+        let operation = TextOperation::diff(current, &content);
+        if operation.is_noop() {
+            return Ok(None);
+        }
+        history.record(current, &operation, js_sys::Date::now())?;
+        review.transform(document, current, &operation);
+        *current = content;
+        sync.apply_local(operation)
+    }
+
+    /// Undoes or redoes a local edit, returning the operation to send like any other local edit.
+    pub fn apply_local_history(&mut self, document: &DocumentKey, action: HistoryAction) -> crate::Result<Option<(u64, TextOperation)>> {
+        let (current, sync, history, review) = self.sync_parts_mut(document)?;
+        match history.apply(action, current)? {
+            Some(operation) => {
+                let content = operation.apply(current)?;
+                review.transform(document, current, &operation);
+                *current = content;
+                sync.apply_local(operation)
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn apply_host_operation(&mut self, document: &DocumentKey, operation: TextOperation) -> crate::Result<()> {
+        let (current, sync, history, review) = self.sync_parts_mut(document)?;
+        let operation = sync.apply_server(operation)?;
+        transform_history(history, &operation);
+        let content = operation.apply(current)?;
+        review.transform(document, current, &operation);
+        *current = content;
+        Ok(())
+    }
+
+    pub fn host_ack(&mut self, document: &DocumentKey) -> Option<(u64, TextOperation)> {
+        self.syncs.get_mut(document)?.server_ack()
+    }
 }
 
 #[derive(PartialEq, Clone, Properties)]
 pub struct ClientItem {
-    pub editor_content: String,
+    pub workspace: Workspace,
     pub text_area_content: String,
     pub area_kind: AreaKind,
+    pub syncs: HashMap<DocumentKey, SyncServer>,
+    pub review: Review,
+    /// Set from a failed edit until the client sends its state again.
+    pub is_resyncing: bool,
 }
 
 impl ClientItem {
     pub fn new(area_kind: AreaKind) -> Self {
         Self { 
-            editor_content: String::default(), 
+            workspace: Workspace::new(),
             text_area_content: String::default(),
             area_kind,
+            syncs: HashMap::new(),
+            review: Review::default(),
+            is_resyncing: false,
         }
     }
 
-    pub fn set_editor_content(&mut self, content: String) {
-        self.editor_content = content;
+    pub fn set_workspace(&mut self, workspace: Workspace) {
+        self.workspace = workspace;
+    }
+
+    /// Replaces all files, the text area keeps its history.
+    pub fn replace_workspace(&mut self, workspace: Workspace) {
+        self.workspace = workspace;
+        self.syncs.retain(|document, _| *document == DocumentKey::TextArea);
+    }
+
+    pub fn add_review_thread(&mut self, thread: ReviewThread) {
+        self.review.add(thread);
+    }
+
+    pub fn review_reply(&mut self, thread_id: &str, message: ReviewMessage) -> crate::Result<()> {
+        self.review.reply(thread_id, message)
+    }
+
+    pub fn editor_content(&self) -> &str {
+        self.workspace.active_content()
     }
 
     pub fn set_text_area_content(&mut self, content: String) {
@@ -73,4 +269,64 @@ impl ClientItem {
     pub fn set_area_kind(&mut self, area_kind: AreaKind) {
         self.area_kind = area_kind;
     }
+
+    pub fn apply_workspace_action(&mut self, action: &WorkspaceAction) -> crate::Result<()> {
+        self.workspace.apply(action)?;
+        if let WorkspaceAction::Delete { id } = action {
+            self.syncs.remove(&DocumentKey::File(id.clone()));
+        }
+        Ok(())
+    }
+
+    fn sync_parts_mut(&mut self, document: &DocumentKey) -> crate::Result<(&mut String, &mut SyncServer, &mut Review)> {
+        let current = match document {
+            DocumentKey::File(id) => self.workspace.content_mut(id),
+            DocumentKey::TextArea => Some(&mut self.text_area_content),
+        }
+        .ok_or_else(|| crate::Error::InvalidWorkspace(format!("unknown document {:?}", document)))?;
+        Ok((current, self.syncs.entry(document.clone()).or_default(), &mut self.review))
+    }
+
+    pub fn reset_sync(&mut self) {
+        self.syncs.clear();
+        self.is_resyncing = false;
+    }
+
+    /// The state the client has to start over from after an edit of it could
+    /// not be applied. Its edits are ignored until it sends its state back.
+    pub fn start_resync(&mut self) -> InitUser {
+        self.syncs.clear();
+        self.is_resyncing = true;
+        InitUser {
+            workspace: self.workspace.clone(),
+            text_area_content: self.text_area_content.clone(),
+            area_kind: self.area_kind,
+            is_communication: false,
+        }
+    }
+
+    /// Applies an edit the client made against `revision` and returns the resulting content.
+    pub fn apply_client_operation(&mut self, document: &DocumentKey, revision: u64, operation: TextOperation) -> crate::Result<String> {
+        let (current, sync, review) = self.sync_parts_mut(document)?;
+        sync.acknowledge(revision);
+        let operation = sync.transform(revision, operation)?;
+        let content = operation.apply(current)?;
+        review.transform(document, current, &operation);
+        *current = content;
+        sync.push(operation);
+        Ok(current.clone())
+    }
+
+    /// Stores an edit the host made and returns the operation that has to be sent to the client.
+    pub fn apply_host_content(&mut self, document: &DocumentKey, content: String) -> Option<TextOperation> {
+        let (current, sync, review) = self.sync_parts_mut(document).ok()?;
+        let operation = TextOperation::diff(current, &content);
+        if operation.is_noop() {
+            return None;
+        }
+        review.transform(document, current, &operation);
+        *current = content;
+        sync.push(operation.clone());
+        Some(operation)
+    }
 }
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use super::workspace::Workspace;
+
 
 #[derive(PartialEq, Clone, Copy, Serialize, Deserialize, Debug)]
 pub enum AreaKind {
@@ -7,6 +9,20 @@ pub enum AreaKind {
     TextArea
 }
 
+/// How much control students have over their own editor.
+#[derive(PartialEq, Clone, Copy, Serialize, Deserialize, Debug)]
+pub enum LockMode {
+    Unlocked,
+    Locked,
+    FollowHost,
+}
+
+impl LockMode {
+    pub fn is_locked(&self) -> bool {
+        *self != LockMode::Unlocked
+    }
+}
+
 #[derive(Clone, PartialEq)]
 pub struct TextAreaProps {
     pub content: String,
@@ -19,7 +35,7 @@ impl TextAreaProps {
         Self {
             content: String::default(),
             placeholder: String::default(),
-            is_disabled: true
+            is_disabled: false
         }
     }
 
@@ -27,6 +43,10 @@ impl TextAreaProps {
         self.content = content;
     }
 
+    pub fn set_disabled(&mut self, is_disabled: bool) {
+        self.is_disabled = is_disabled;
+    }
+
     pub fn set_placeholder(&mut self, placeholder: String) {
         self.placeholder = placeholder;
     }
@@ -57,7 +77,7 @@ impl PaintProps {
 
 #[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
 pub struct InitUser {
-    pub editor_content: String,
+    pub workspace: Workspace,
     pub text_area_content: String,
     pub area_kind: AreaKind,
     pub is_communication: bool,
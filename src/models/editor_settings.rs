@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{DEFAULT_EDITOR_LANGUAGE, DEFAULT_FONT_SIZE, DEFAULT_TAB_SIZE};
+
+/// Editor configuration chosen by the host and shared with every student.
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct EditorSettings {
+    pub language: String,
+    pub tab_size: u32,
+    pub font_size: u32,
+    pub minimap: bool,
+}
+
+impl Default for EditorSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EditorSettings {
+    pub fn new() -> Self {
+        Self {
+            language: DEFAULT_EDITOR_LANGUAGE.to_owned(),
+            tab_size: DEFAULT_TAB_SIZE,
+            font_size: DEFAULT_FONT_SIZE,
+            minimap: true,
+        }
+    }
+
+    pub fn set_language(&mut self, language: String) {
+        self.language = language;
+    }
+
+    pub fn set_tab_size(&mut self, tab_size: u32) {
+        self.tab_size = tab_size;
+    }
+
+    pub fn set_font_size(&mut self, font_size: u32) {
+        self.font_size = font_size;
+    }
+
+    pub fn set_minimap(&mut self, minimap: bool) {
+        self.minimap = minimap;
+    }
+}
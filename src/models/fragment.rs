@@ -0,0 +1,166 @@
+use std::{collections::BTreeMap, ops::Range};
+
+use serde::{Deserialize, Serialize};
+
+/// Place of a piece of a frame too large for one message.
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Debug)]
+pub struct Fragment {
+    pub index: u32,
+    pub count: u32,
+    /// Bytes of the whole frame.
+    pub size: u32,
+}
+
+/// Cuts `len` bytes into ranges of at most `max_len`.
+pub fn split(len: usize, max_len: usize) -> impl Iterator<Item = (Fragment, Range<usize>)> {
+    let max_len = max_len.max(1);
+    let count = ((len + max_len - 1) / max_len).max(1) as u32;
+    (0..count).map(move |index| {
+        let start = index as usize * max_len;
+        let end = (start + max_len).min(len);
+        (Fragment { index, count, size: len as u32 }, start..end)
+    })
+}
+
+#[derive(PartialEq, Clone, Debug)]
+struct Partial {
+    data: Vec<u8>,
+    received: Vec<bool>,
+    missing: u32,
+    started_at: f64,
+}
+
+/// Puts frames back together from their fragments, cut by `split` with
+/// `max_len`. The whole frame is reserved when its first piece arrives and
+/// each piece is written into place. A frame still missing pieces
+/// `timeout_ms` after its first one arrived is dropped, the jitter buffer
+/// then counts it lost. Fragments of a frame larger than `max_size`, or not
+/// agreeing with it, are ignored.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Reassembler {
+    frames: BTreeMap<u64, Partial>,
+    max_len: usize,
+    max_size: usize,
+    timeout_ms: f64,
+    dropped: u32,
+}
+
+impl Reassembler {
+    pub fn new(max_len: usize, max_size: usize, timeout_ms: f64) -> Self {
+        Self {
+            frames: BTreeMap::new(),
+            max_len: max_len.max(1),
+            max_size,
+            timeout_ms,
+            dropped: 0,
+        }
+    }
+
+    /// Frames dropped incomplete since the last call.
+    pub fn take_dropped(&mut self) -> u32 {
+        std::mem::take(&mut self.dropped)
+    }
+
+    /// Where the piece goes in the frame, `None` if the fragment is not one
+    /// `split` makes of a frame of at most `max_size`.
+    fn range(&self, fragment: Fragment) -> Option<Range<usize>> {
+        let size = fragment.size as usize;
+        let is_valid = size <= self.max_size
+            && fragment.count == ((size + self.max_len - 1) / self.max_len).max(1) as u32
+            && fragment.index < fragment.count;
+        let start = fragment.index as usize * self.max_len;
+        is_valid.then(|| start..(start + self.max_len).min(size))
+    }
+
+    /// The data of the whole frame once its last piece arrives.
+    pub fn push(&mut self, sequence: u64, fragment: Fragment, piece: &[u8], now: f64) -> Option<Vec<u8>> {
+        let timeout_ms = self.timeout_ms;
+        let before = self.frames.len();
+        self.frames.retain(|_, partial| now - partial.started_at < timeout_ms);
+        self.dropped += (before - self.frames.len()) as u32;
+
+        let range = self.range(fragment).filter(|range| range.len() == piece.len())?;
+        let partial = self.frames.entry(sequence).or_insert_with(|| Partial {
+            data: vec![0; fragment.size as usize],
+            received: vec![false; fragment.count as usize],
+            missing: fragment.count,
+            started_at: now,
+        });
+        if partial.data.len() != fragment.size as usize {
+            return None;
+        }
+        let received = partial.received.get_mut(fragment.index as usize)?;
+        if !*received {
+            *received = true;
+            partial.data.get_mut(range)?.copy_from_slice(piece);
+            partial.missing -= 1;
+        }
+        if partial.missing > 0 {
+            return None;
+        }
+        self.frames.remove(&sequence).map(|partial| partial.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{split, Fragment, Reassembler};
+
+    fn piece(index: u32, count: u32, size: u32) -> Fragment {
+        Fragment { index, count, size }
+    }
+
+    #[test]
+    fn splits_into_pieces_of_at_most_max_len() {
+        let ranges: Vec<_> = split(250, 100).map(|(_, range)| range).collect();
+        assert_eq!(ranges, [0..100, 100..200, 200..250]);
+        assert_eq!(split(0, 100).count(), 1);
+        assert_eq!(split(100, 100).count(), 1);
+    }
+
+    #[test]
+    fn puts_the_pieces_back_in_order() {
+        let data: Vec<u8> = (0..250u32).map(|i| i as u8).collect();
+        let pieces: Vec<_> = split(data.len(), 100).collect();
+        let mut reassembler = Reassembler::new(100, 1000, 1000.0);
+        for (fragment, range) in pieces.iter().skip(1).rev() {
+            assert_eq!(reassembler.push(1, *fragment, &data[range.clone()], 0.0), None);
+        }
+        let (fragment, range) = pieces[0].clone();
+        assert_eq!(reassembler.push(1, fragment, &data[range], 0.0), Some(data));
+    }
+
+    #[test]
+    fn rejects_impossible_fragments() {
+        let mut reassembler = Reassembler::new(100, 1000, 1000.0);
+        assert_eq!(reassembler.push(1, piece(0, 0, 100), &[1; 100], 0.0), None);
+        assert_eq!(reassembler.push(2, piece(0, 11, 1100), &[1; 100], 0.0), None);
+        assert_eq!(reassembler.push(3, piece(2, 2, 200), &[1; 100], 0.0), None);
+        // the count does not fit the size
+        assert_eq!(reassembler.push(4, piece(0, 3, 200), &[1; 100], 0.0), None);
+        // a piece of another length than its place
+        assert_eq!(reassembler.push(5, piece(1, 2, 150), &[1; 100], 0.0), None);
+        // none of them started a frame
+        assert_eq!(reassembler.push(6, piece(0, 1, 1), &[1], 0.0), Some(vec![1]));
+        reassembler.push(7, piece(0, 1, 1), &[1], 2000.0);
+        assert_eq!(reassembler.take_dropped(), 0);
+    }
+
+    #[test]
+    fn rejects_pieces_disagreeing_with_the_first_one() {
+        let mut reassembler = Reassembler::new(1, 1000, 1000.0);
+        assert_eq!(reassembler.push(1, piece(0, 2, 2), &[1], 0.0), None);
+        assert_eq!(reassembler.push(1, piece(1, 3, 3), &[2], 0.0), None);
+        assert_eq!(reassembler.push(1, piece(1, 2, 2), &[2], 0.0), Some(vec![1, 2]));
+    }
+
+    #[test]
+    fn drops_a_frame_missing_pieces() {
+        let mut reassembler = Reassembler::new(1, 1000, 1000.0);
+        reassembler.push(1, piece(0, 2, 2), &[1], 0.0);
+        reassembler.push(2, piece(0, 2, 2), &[1], 1000.0);
+        assert_eq!(reassembler.take_dropped(), 1);
+        assert_eq!(reassembler.take_dropped(), 0);
+        assert_eq!(reassembler.push(1, piece(1, 2, 2), &[2], 1000.0), None);
+    }
+}
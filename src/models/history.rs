@@ -0,0 +1,211 @@
+use crate::constants::{HISTORY_MAX_LEN, HISTORY_MERGE_INTERVAL_MS};
+
+use super::text_operation::TextOperation;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum HistoryAction {
+    Undo,
+    Redo,
+}
+
+/// Undo and redo stacks of one document that only hold the local user's edits.
+/// Remote edits don't get an entry, instead the stored operations are
+/// transformed against them so they still apply to the current text.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct EditHistory {
+    undo_stack: Vec<TextOperation>,
+    redo_stack: Vec<TextOperation>,
+    last_edit_at: Option<f64>,
+}
+
+// Rebases every operation of the stack, newest first, onto a concurrent operation.
+fn transform_stack(stack: &mut Vec<TextOperation>, operation: &TextOperation) -> crate::Result<()> {
+    let mut operation = operation.clone();
+    let mut transformed = Vec::with_capacity(stack.len());
+    for entry in stack.iter().rev() {
+        let (entry, rest) = TextOperation::transform(entry, &operation)?;
+        if !entry.is_noop() {
+            transformed.push(entry);
+        }
+        operation = rest;
+    }
+    transformed.reverse();
+    *stack = transformed;
+    Ok(())
+}
+
+impl EditHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Stores a local edit, `text` is the document before it and `now` is in milliseconds.
+    pub fn record(&mut self, text: &str, operation: &TextOperation, now: f64) -> crate::Result<()> {
+        let inverse = operation.invert(text)?;
+        let is_typing = self.last_edit_at.is_some_and(|last| now - last < HISTORY_MERGE_INTERVAL_MS);
+        let inverse = match self.undo_stack.pop() {
+            Some(previous) if is_typing => inverse.compose(&previous)?,
+            Some(previous) => {
+                self.undo_stack.push(previous);
+                inverse
+            }
+            None => inverse,
+        };
+        self.undo_stack.push(inverse);
+        if self.undo_stack.len() > HISTORY_MAX_LEN {
+            self.undo_stack.remove(0);
+        }
+        self.redo_stack.clear();
+        self.last_edit_at = Some(now);
+        Ok(())
+    }
+
+    /// Keeps the stacks valid after an edit made by somebody else.
+    pub fn transform(&mut self, operation: &TextOperation) -> crate::Result<()> {
+        transform_stack(&mut self.undo_stack, operation)?;
+        transform_stack(&mut self.redo_stack, operation)?;
+        self.last_edit_at = None;
+        Ok(())
+    }
+
+    /// Returns the operation to apply to `text`, if there is anything to undo or redo.
+    pub fn apply(&mut self, action: HistoryAction, text: &str) -> crate::Result<Option<TextOperation>> {
+        let (from, to) = match action {
+            HistoryAction::Undo => (&mut self.undo_stack, &mut self.redo_stack),
+            HistoryAction::Redo => (&mut self.redo_stack, &mut self.undo_stack),
+        };
+        let operation = match from.pop() {
+            Some(operation) => operation,
+            None => return Ok(None),
+        };
+        to.push(operation.invert(text)?);
+        self.last_edit_at = None;
+        Ok(Some(operation))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EditHistory, HistoryAction};
+    use crate::{constants::HISTORY_MERGE_INTERVAL_MS, models::text_operation::TextOperation};
+
+    /// A document with local edits recorded in the history and remote ones transforming it.
+    struct Document {
+        text: String,
+        history: EditHistory,
+    }
+
+    impl Document {
+        fn new(text: &str) -> Self {
+            Self { text: text.to_owned(), history: EditHistory::new() }
+        }
+
+        fn local(&mut self, new: &str, now: f64) {
+            let operation = TextOperation::diff(&self.text, new);
+            self.history.record(&self.text, &operation, now).unwrap();
+            self.text = new.to_owned();
+        }
+
+        fn remote(&mut self, new: &str) {
+            let operation = TextOperation::diff(&self.text, new);
+            self.history.transform(&operation).unwrap();
+            self.text = new.to_owned();
+        }
+
+        fn apply(&mut self, action: HistoryAction) -> Option<&str> {
+            let operation = self.history.apply(action, &self.text).unwrap()?;
+            self.text = operation.apply(&self.text).unwrap();
+            Some(&self.text)
+        }
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut document = Document::new("a");
+        document.local("ab", 0.0);
+        document.local("abc", HISTORY_MERGE_INTERVAL_MS * 2.0);
+        assert_eq!(document.apply(HistoryAction::Undo), Some("ab"));
+        assert_eq!(document.apply(HistoryAction::Undo), Some("a"));
+        assert_eq!(document.apply(HistoryAction::Undo), None);
+        assert_eq!(document.apply(HistoryAction::Redo), Some("ab"));
+        assert_eq!(document.apply(HistoryAction::Redo), Some("abc"));
+        assert_eq!(document.apply(HistoryAction::Redo), None);
+    }
+
+    #[test]
+    fn typing_is_undone_at_once() {
+        let mut document = Document::new("");
+        document.local("a", 0.0);
+        document.local("ab", HISTORY_MERGE_INTERVAL_MS / 2.0);
+        assert_eq!(document.apply(HistoryAction::Undo), Some(""));
+        assert!(!document.history.can_undo());
+    }
+
+    #[test]
+    fn new_edit_drops_the_redo_stack() {
+        let mut document = Document::new("a");
+        document.local("ab", 0.0);
+        document.apply(HistoryAction::Undo);
+        document.local("ax", HISTORY_MERGE_INTERVAL_MS * 2.0);
+        assert!(!document.history.can_redo());
+    }
+
+    #[test]
+    fn undo_keeps_remote_edits() {
+        let mut document = Document::new("hello");
+        document.local("hello world", 0.0);
+        document.remote("> hello world");
+        assert_eq!(document.apply(HistoryAction::Undo), Some("> hello"));
+        assert_eq!(document.apply(HistoryAction::Redo), Some("> hello world"));
+    }
+
+    #[test]
+    fn every_entry_is_rebased() {
+        let mut document = Document::new("a");
+        document.local("ab", 0.0);
+        document.local("abc", HISTORY_MERGE_INTERVAL_MS * 2.0);
+        document.remote("Xabc");
+        assert_eq!(document.apply(HistoryAction::Undo), Some("Xab"));
+        document.remote("XabY");
+        assert_eq!(document.apply(HistoryAction::Undo), Some("XaY"));
+        document.remote("ZXaY");
+        assert_eq!(document.apply(HistoryAction::Redo), Some("ZXabY"));
+        assert_eq!(document.apply(HistoryAction::Redo), Some("ZXabcY"));
+    }
+
+    #[test]
+    fn remote_edit_inside_an_undone_insert() {
+        let mut document = Document::new("");
+        document.local("abc", 0.0);
+        document.remote("ac");
+        assert_eq!(document.apply(HistoryAction::Undo), Some(""));
+    }
+
+    #[test]
+    fn entry_removed_by_a_remote_edit_is_dropped() {
+        let mut document = Document::new("x");
+        document.local("xabc", 0.0);
+        document.remote("x");
+        assert!(!document.history.can_undo());
+        assert_eq!(document.apply(HistoryAction::Undo), None);
+        assert_eq!(document.text, "x");
+    }
+
+    #[test]
+    fn remote_edit_stops_the_typing_merge() {
+        let mut document = Document::new("");
+        document.local("a", 0.0);
+        document.remote("aZ");
+        document.local("abZ", 1.0);
+        assert_eq!(document.apply(HistoryAction::Undo), Some("aZ"));
+        assert_eq!(document.apply(HistoryAction::Undo), Some("Z"));
+    }
+}
@@ -1,23 +1,36 @@
+use std::{cell::RefCell, rc::Rc};
 
 use crate::models::commons::TextAreaProps;
 
-use super::commons::AreaKind;
+use super::{commons::{AreaKind, LockMode}, editor_settings::EditorSettings, replay::{Bookmark, LessonRecording, ReplayChange}, text_operation::TextOperation, workspace::{DocumentKey, Workspace, WorkspaceAction}};
 
 #[derive(Clone, PartialEq)]
 pub struct HostPorps {
-    pub host_editor_content: String,
+    pub workspace: Workspace,
     pub host_area_content: TextAreaProps,
     pub host_area_kind: AreaKind,
     pub is_communication: bool,
+    pub editor_settings: EditorSettings,
+    pub reference: Option<Workspace>,
+    pub lock_mode: LockMode,
+    pub scroll_line: Option<u32>,
+    // shared, so that cloning the props on every keystroke doesn't copy the whole lesson
+    pub recording: Rc<RefCell<LessonRecording>>,
 }
 
 impl HostPorps {
     pub fn new() -> Self {
+        let workspace = Workspace::new();
         Self {
-            host_editor_content: String::default(),
+            recording: Rc::new(RefCell::new(LessonRecording::new(workspace.clone(), js_sys::Date::now()))),
+            workspace,
             host_area_content: TextAreaProps::new(),
             host_area_kind: AreaKind::Editor,
             is_communication: true,
+            editor_settings: EditorSettings::new(),
+            reference: None,
+            lock_mode: LockMode::Unlocked,
+            scroll_line: None,
         }
     }
 
@@ -25,14 +38,53 @@ impl HostPorps {
         self.host_area_kind = host_area_kind;
     }
 
-    pub fn set_editor_content(&mut self, content: String) {
-        self.host_editor_content = content;
+    /// Replaces the workspace, the recording starts over from it.
+    pub fn set_workspace(&mut self, workspace: Workspace) {
+        *self.recording.borrow_mut() = LessonRecording::new(workspace.clone(), js_sys::Date::now());
+        self.workspace = workspace;
+    }
+
+    pub fn add_bookmark(&mut self, title: String) -> Bookmark {
+        self.recording.borrow_mut().add_bookmark(title, js_sys::Date::now())
+    }
+
+    fn record_edit(&mut self, document: &DocumentKey, operation: &TextOperation) {
+        if let DocumentKey::File(file_id) = document {
+            let change = ReplayChange::Edit {
+                file_id: file_id.clone(),
+                operation: operation.clone(),
+            };
+            self.recording.borrow_mut().record(change, js_sys::Date::now());
+        }
+    }
+
+    pub fn editor_content(&self) -> &str {
+        self.workspace.active_content()
     }
 
     pub fn set_text_area_content(&mut self, content: String) {
         self.host_area_content.set_content(content);
     }
 
+    pub fn set_lock_mode(&mut self, lock_mode: LockMode) {
+        self.lock_mode = lock_mode;
+    }
+
+    /// Returns `true` if the first visible line has changed.
+    pub fn update_scroll_line(&mut self, line: u32) -> bool {
+        let is_changed = self.scroll_line != Some(line);
+        self.scroll_line = Some(line);
+        is_changed
+    }
+
+    pub fn save_reference(&mut self) {
+        self.reference = Some(self.workspace.clone());
+    }
+
+    pub fn set_editor_settings(&mut self, editor_settings: EditorSettings) {
+        self.editor_settings = editor_settings;
+    }
+
     pub fn set_communication(&mut self, communication: bool) {
         self.is_communication = communication;
     }
@@ -42,4 +94,39 @@ impl HostPorps {
         self.set_communication(!is_communication);
         self.is_communication.clone()
     }
+
+    pub fn apply_workspace_action(&mut self, action: &WorkspaceAction) -> crate::Result<()> {
+        self.workspace.apply(action)?;
+        let change = ReplayChange::Workspace { action: action.clone() };
+        self.recording.borrow_mut().record(change, js_sys::Date::now());
+        Ok(())
+    }
+
+    fn content_mut(&mut self, document: &DocumentKey) -> Option<&mut String> {
+        match document {
+            DocumentKey::File(id) => self.workspace.content_mut(id),
+            DocumentKey::TextArea => Some(&mut self.host_area_content.content),
+        }
+    }
+
+    /// Replaces the content and returns the operation describing the change, if any.
+    pub fn update_content(&mut self, document: &DocumentKey, content: String) -> Option<TextOperation> {
+        let current = self.content_mut(document)?;
+        let operation = TextOperation::diff(current, &content);
+        if operation.is_noop() {
+            return None;
+        }
+        *current = content;
+        self.record_edit(document, &operation);
+        Some(operation)
+    }
+
+    pub fn apply_operation(&mut self, document: &DocumentKey, operation: &TextOperation) -> crate::Result<()> {
+        let current = self
+            .content_mut(document)
+            .ok_or_else(|| crate::Error::InvalidWorkspace(format!("unknown document {:?}", document)))?;
+        *current = operation.apply(current)?;
+        self.record_edit(document, operation);
+        Ok(())
+    }
 }
@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+
+/// What became of a pushed packet.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Arrival {
+    Held,
+    /// Its place was given up already, the packet is dropped.
+    Late,
+    /// A key frame far behind the others, the sender started over and the
+    /// buffer with it.
+    Restart,
+}
+
+/// What comes out of the buffer, in sequence order.
+#[derive(PartialEq, Debug)]
+pub enum Release<T> {
+    Frame(T),
+    /// This many frames were given up on, the decoder needs a key frame
+    /// unless the next one is.
+    Lost(u64),
+}
+
+/// Counts since they were last taken.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct JitterStats {
+    /// Packets that arrived after one with a higher sequence number, in time
+    /// to be played.
+    pub reordered: u32,
+    /// Packets that arrived after their place was given up.
+    pub late: u32,
+    pub lost: u64,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+struct Held<T> {
+    packet: T,
+    is_key: bool,
+    arrived_at: f64,
+}
+
+/// Holds packets that arrive ahead of a missing one and releases them in
+/// order. The missing packet is given up on once the next one has waited
+/// `max_wait_ms`, `capacity` packets are held, or a key frame is waiting
+/// behind it. Time only moves with `pop`, so a gap is noticed when the next
+/// packet arrives.
+#[derive(PartialEq, Clone, Debug)]
+pub struct JitterBuffer<T> {
+    packets: BTreeMap<u64, Held<T>>,
+    next: Option<u64>,
+    highest: Option<u64>,
+    capacity: usize,
+    max_wait_ms: f64,
+    stats: JitterStats,
+}
+
+impl<T> JitterBuffer<T> {
+    pub fn new(capacity: usize, max_wait_ms: f64) -> Self {
+        Self {
+            packets: BTreeMap::new(),
+            next: None,
+            highest: None,
+            capacity: capacity.max(1),
+            max_wait_ms,
+            stats: JitterStats::default(),
+        }
+    }
+
+    pub fn take_stats(&mut self) -> JitterStats {
+        std::mem::take(&mut self.stats)
+    }
+
+    pub fn push(&mut self, sequence: u64, is_key: bool, packet: T, now: f64) -> Arrival {
+        let next = *self.next.get_or_insert(sequence);
+        let mut arrival = Arrival::Held;
+        if sequence < next {
+            if !is_key || next - sequence <= self.capacity as u64 {
+                self.stats.late += 1;
+                return Arrival::Late;
+            }
+            self.packets.clear();
+            self.next = Some(sequence);
+            self.highest = None;
+            arrival = Arrival::Restart;
+        }
+        if self.highest.is_some_and(|highest| sequence < highest) {
+            self.stats.reordered += 1;
+        }
+        if self.highest.map_or(true, |highest| sequence > highest) {
+            self.highest = Some(sequence);
+        }
+        self.packets.entry(sequence).or_insert(Held { packet, is_key, arrived_at: now });
+        arrival
+    }
+
+    /// The next packet in order, or the frames given up on before it.
+    pub fn pop(&mut self, now: f64) -> Option<Release<T>> {
+        let next = self.next?;
+        if let Some(held) = self.packets.remove(&next) {
+            self.next = Some(next + 1);
+            return Some(Release::Frame(held.packet));
+        }
+        let (&first, held) = self.packets.iter().next()?;
+        // frames before a key frame are not needed to decode it
+        let key = self.packets.iter().find(|(_, held)| held.is_key).map(|(&sequence, _)| sequence);
+        let resume_at = if let Some(key) = key {
+            key
+        } else if now - held.arrived_at >= self.max_wait_ms || self.packets.len() >= self.capacity {
+            first
+        } else {
+            return None;
+        };
+        self.packets = self.packets.split_off(&resume_at);
+        let lost = resume_at - next;
+        self.stats.lost += lost;
+        self.next = Some(resume_at);
+        Some(Release::Lost(lost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Arrival, JitterBuffer, JitterStats, Release};
+
+    fn drain(buffer: &mut JitterBuffer<u64>, now: f64) -> Vec<Release<u64>> {
+        std::iter::from_fn(|| buffer.pop(now)).collect()
+    }
+
+    #[test]
+    fn releases_in_order_after_reordering() {
+        let mut buffer = JitterBuffer::new(10, 100.0);
+        assert_eq!(buffer.push(0, true, 0, 0.0), Arrival::Held);
+        assert_eq!(buffer.push(2, false, 2, 0.0), Arrival::Held);
+        assert_eq!(drain(&mut buffer, 0.0), [Release::Frame(0)]);
+        assert_eq!(buffer.push(1, false, 1, 10.0), Arrival::Held);
+        assert_eq!(drain(&mut buffer, 10.0), [Release::Frame(1), Release::Frame(2)]);
+        assert_eq!(buffer.take_stats(), JitterStats { reordered: 1, late: 0, lost: 0 });
+        assert_eq!(buffer.take_stats(), JitterStats::default());
+    }
+
+    #[test]
+    fn gives_up_after_max_wait() {
+        let mut buffer = JitterBuffer::new(10, 100.0);
+        buffer.push(0, true, 0, 0.0);
+        drain(&mut buffer, 0.0);
+        buffer.push(2, false, 2, 0.0);
+        assert_eq!(buffer.pop(99.0), None);
+        assert_eq!(drain(&mut buffer, 100.0), [Release::Lost(1), Release::Frame(2)]);
+        assert_eq!(buffer.take_stats().lost, 1);
+    }
+
+    #[test]
+    fn gives_up_at_capacity() {
+        let mut buffer = JitterBuffer::new(3, 1000.0);
+        buffer.push(0, true, 0, 0.0);
+        drain(&mut buffer, 0.0);
+        buffer.push(2, false, 2, 0.0);
+        buffer.push(3, false, 3, 0.0);
+        assert_eq!(buffer.pop(0.0), None);
+        buffer.push(4, false, 4, 0.0);
+        assert_eq!(
+            drain(&mut buffer, 0.0),
+            [Release::Lost(1), Release::Frame(2), Release::Frame(3), Release::Frame(4)],
+        );
+    }
+
+    #[test]
+    fn skips_ahead_to_a_key_frame() {
+        let mut buffer = JitterBuffer::new(10, 1000.0);
+        buffer.push(0, true, 0, 0.0);
+        drain(&mut buffer, 0.0);
+        buffer.push(2, false, 2, 0.0);
+        buffer.push(3, true, 3, 0.0);
+        buffer.push(4, false, 4, 0.0);
+        // 2 cannot be decoded without 1, so it goes with it
+        assert_eq!(
+            drain(&mut buffer, 0.0),
+            [Release::Lost(2), Release::Frame(3), Release::Frame(4)],
+        );
+    }
+
+    #[test]
+    fn drops_a_late_packet() {
+        let mut buffer = JitterBuffer::new(10, 100.0);
+        buffer.push(0, true, 0, 0.0);
+        buffer.push(2, false, 2, 0.0);
+        drain(&mut buffer, 100.0);
+        assert_eq!(buffer.push(1, false, 1, 150.0), Arrival::Late);
+        // a key frame close behind is late as well
+        assert_eq!(buffer.push(1, true, 1, 150.0), Arrival::Late);
+        assert_eq!(buffer.pop(150.0), None);
+        assert_eq!(buffer.take_stats(), JitterStats { reordered: 0, late: 2, lost: 1 });
+    }
+
+    #[test]
+    fn restarts_from_a_key_frame_far_behind() {
+        let mut buffer = JitterBuffer::new(3, 100.0);
+        for sequence in 100..105 {
+            buffer.push(sequence, sequence == 100, sequence, 0.0);
+        }
+        buffer.push(106, false, 106, 0.0);
+        drain(&mut buffer, 0.0);
+        assert_eq!(buffer.push(0, true, 0, 10.0), Arrival::Restart);
+        assert_eq!(buffer.push(1, false, 1, 10.0), Arrival::Held);
+        // what was held from before the restart is gone
+        assert_eq!(drain(&mut buffer, 1000.0), [Release::Frame(0), Release::Frame(1)]);
+    }
+}
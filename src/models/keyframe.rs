@@ -0,0 +1,53 @@
+use crate::constants::{KEYFRAME_FALLBACK_MS, KEYFRAME_MIN_INTERVAL_MS, KEYFRAME_RETRY_MS};
+
+/// Receiver side: asks the sender for a key frame while the decoder waits for
+/// one, and asks again if it does not come.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct KeyframeRequester {
+    requested_at: Option<f64>,
+}
+
+impl KeyframeRequester {
+    /// Whether a request should go out now, `is_waiting` tells if the
+    /// decoder can only go on from a key frame.
+    pub fn should_request(&mut self, is_waiting: bool, now: f64) -> bool {
+        if !is_waiting {
+            self.requested_at = None;
+            return false;
+        }
+        if self.requested_at.is_some_and(|at| now - at < KEYFRAME_RETRY_MS) {
+            return false;
+        }
+        self.requested_at = Some(now);
+        true
+    }
+}
+
+/// Sender side: decides which frames are encoded as key frames. Requests of
+/// all receivers are served together, at most one key frame per
+/// `KEYFRAME_MIN_INTERVAL_MS`.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct KeyframeScheduler {
+    is_requested: bool,
+    last_key_at: Option<f64>,
+}
+
+impl KeyframeScheduler {
+    pub fn request(&mut self) {
+        self.is_requested = true;
+    }
+
+    /// `is_forced` for a frame that has to be a key frame anyway, like the
+    /// first one after the encoder was reconfigured.
+    pub fn should_send_key(&mut self, is_forced: bool, now: f64) -> bool {
+        let since_key = self.last_key_at.map(|at| now - at);
+        let is_key = is_forced
+            || since_key.map_or(true, |since| since >= KEYFRAME_FALLBACK_MS)
+            || (self.is_requested && since_key.map_or(true, |since| since >= KEYFRAME_MIN_INTERVAL_MS));
+        if is_key {
+            self.is_requested = false;
+            self.last_key_at = Some(now);
+        }
+        is_key
+    }
+}
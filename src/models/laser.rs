@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+
+use crate::constants::LASER_INTERVAL_MS;
+
+/// What the host is pointing at.
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Debug)]
+pub enum LaserSurface {
+    /// The editor or the text area.
+    Document,
+    Board,
+    Screen,
+}
+
+/// Position of the laser. On the board it is in board units, since every
+/// viewer zooms on their own; elsewhere it is a fraction of the surface's
+/// width and height.
+#[derive(PartialEq, Clone, Copy, Serialize, Deserialize, Debug)]
+pub struct LaserPoint {
+    pub surface: LaserSurface,
+    pub x: f64,
+    pub y: f64,
+}
+
+/// One message of the laser stream, `point` is `None` once the pointer left.
+/// Only the newest update matters, so an older one arriving late is dropped.
+#[derive(PartialEq, Clone, Copy, Serialize, Deserialize, Debug)]
+pub struct LaserUpdate {
+    pub sequence: u64,
+    pub point: Option<LaserPoint>,
+}
+
+impl LaserUpdate {
+    /// A host that reconnected starts counting again from the first update.
+    pub fn is_newer_than(&self, other: &LaserUpdate) -> bool {
+        self.sequence > other.sequence || self.sequence == 1
+    }
+}
+
+/// Sets up the laser channel, over the lesson connection: the host offers
+/// it, the student answers and both send their ICE candidates.
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub enum LaserSignal {
+    Offer {
+        sdp: String,
+    },
+    Answer {
+        sdp: String,
+    },
+    Candidate {
+        candidate: String,
+        sdp_mid: Option<String>,
+        sdp_m_line_index: Option<u16>,
+    },
+}
+
+/// Sends at most one update per `LASER_INTERVAL_MS`. Positions in between
+/// overwrite each other and only the last one goes out, when flushed.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct LaserThrottle {
+    pending: Option<Option<LaserPoint>>,
+    sent_at: Option<f64>,
+    sequence: u64,
+    scheduled: bool,
+}
+
+impl LaserThrottle {
+    pub fn push(&mut self, point: Option<LaserPoint>, now: f64) -> Option<LaserUpdate> {
+        self.pending = Some(point);
+        self.take(now)
+    }
+
+    /// Called by the timer that `schedule` asked for.
+    pub fn flush(&mut self, now: f64) -> Option<LaserUpdate> {
+        self.scheduled = false;
+        self.take(now)
+    }
+
+    /// Whether a flush has to be scheduled for a held back position, once per wait.
+    pub fn schedule(&mut self) -> bool {
+        let should_schedule = self.pending.is_some() && !self.scheduled;
+        self.scheduled |= should_schedule;
+        should_schedule
+    }
+
+    fn take(&mut self, now: f64) -> Option<LaserUpdate> {
+        if self.sent_at.is_some_and(|sent_at| now - sent_at < LASER_INTERVAL_MS) {
+            return None;
+        }
+        let point = self.pending.take()?;
+        self.sent_at = Some(now);
+        self.sequence += 1;
+        Some(LaserUpdate {
+            sequence: self.sequence,
+            point,
+        })
+    }
+}
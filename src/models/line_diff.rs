@@ -0,0 +1,272 @@
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ChangeKind {
+    Same,
+    Removed,
+    Added,
+}
+
+/// One line of a diff, line numbers start from 1.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct LineChange {
+    pub kind: ChangeKind,
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+    pub text: String,
+}
+
+/// A row of the side-by-side view.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct DiffRow {
+    pub old: Option<LineChange>,
+    pub new: Option<LineChange>,
+}
+
+fn change(kind: ChangeKind, old_line: Option<usize>, new_line: Option<usize>, text: &str) -> LineChange {
+    LineChange {
+        kind,
+        old_line,
+        new_line,
+        text: text.to_owned(),
+    }
+}
+
+// past this many removed and added lines a block is shown as replaced as a whole,
+// the diff would take too long and tell little
+const MAX_EDITS: usize = 1000;
+
+/// Shortest edit script turning `old` into `new`, Myers' O((n + m) d)
+/// algorithm. `None` if it takes more than `MAX_EDITS` edits.
+fn shortest_edit(old: &[&str], new: &[&str]) -> Option<Vec<ChangeKind>> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = (old.len() + new.len()).min(MAX_EDITS) as isize;
+    // furthest x on each diagonal k = x - y, at index k + offset
+    let offset = max + 1;
+    let mut furthest = vec![0isize; 2 * max as usize + 3];
+    // the diagonals -d..=d before each step d, to walk back along
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    for d in 0..=max {
+        trace.push(furthest[(offset - d) as usize..=(offset + d) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let at = |k: isize| furthest[(k + offset) as usize];
+            let mut x = if k == -d || (k != d && at(k - 1) < at(k + 1)) { at(k + 1) } else { at(k - 1) + 1 };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            furthest[(k + offset) as usize] = x;
+            if x >= n && y >= m {
+                return Some(walk_back(&trace, n, m));
+            }
+        }
+    }
+    None
+}
+
+fn walk_back(trace: &[Vec<isize>], n: isize, m: isize) -> Vec<ChangeKind> {
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, furthest) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let (previous_x, previous_y) = if d == 0 {
+            (0, 0)
+        } else {
+            let at = |k: isize| furthest[(k + d) as usize];
+            let k = x - y;
+            let previous_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) { k + 1 } else { k - 1 };
+            (at(previous_k), at(previous_k) - previous_k)
+        };
+        while x > previous_x && y > previous_y {
+            edits.push(ChangeKind::Same);
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            edits.push(if x == previous_x { ChangeKind::Added } else { ChangeKind::Removed });
+        }
+        (x, y) = (previous_x, previous_y);
+    }
+    edits.reverse();
+    edits
+}
+
+/// Line diff with the fewest removed and added lines, the common prefix and
+/// suffix are cut off first so that small edits of long files stay cheap.
+pub fn diff_lines(old: &str, new: &str) -> Vec<LineChange> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+
+    let prefix = old_lines
+        .iter()
+        .zip(new_lines.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_middle = &old_lines[prefix..old_lines.len() - suffix];
+    let new_middle = &new_lines[prefix..new_lines.len() - suffix];
+    let edits = shortest_edit(old_middle, new_middle).unwrap_or_else(|| {
+        std::iter::repeat(ChangeKind::Removed)
+            .take(old_middle.len())
+            .chain(std::iter::repeat(ChangeKind::Added).take(new_middle.len()))
+            .collect()
+    });
+
+    let mut changes: Vec<LineChange> = old_lines[..prefix]
+        .iter()
+        .enumerate()
+        .map(|(index, text)| change(ChangeKind::Same, Some(index + 1), Some(index + 1), text))
+        .collect();
+    let (mut i, mut j) = (0, 0);
+    for kind in edits {
+        let old_line = Some(prefix + i + 1);
+        let new_line = Some(prefix + j + 1);
+        match kind {
+            ChangeKind::Same => {
+                changes.push(change(kind, old_line, new_line, old_middle[i]));
+                i += 1;
+                j += 1;
+            }
+            ChangeKind::Removed => {
+                changes.push(change(kind, old_line, None, old_middle[i]));
+                i += 1;
+            }
+            ChangeKind::Added => {
+                changes.push(change(kind, None, new_line, new_middle[j]));
+                j += 1;
+            }
+        }
+    }
+    let old_start = old_lines.len() - suffix;
+    let new_start = new_lines.len() - suffix;
+    changes.extend(
+        old_lines[old_start..]
+            .iter()
+            .enumerate()
+            .map(|(index, text)| change(ChangeKind::Same, Some(old_start + index + 1), Some(new_start + index + 1), text)),
+    );
+    changes
+}
+
+/// Pairs removed and added lines of the same hunk so they end up on one row.
+pub fn side_by_side(changes: Vec<LineChange>) -> Vec<DiffRow> {
+    let mut rows = Vec::new();
+    let mut removed: Vec<LineChange> = Vec::new();
+    let mut added: Vec<LineChange> = Vec::new();
+    let flush = |rows: &mut Vec<DiffRow>, removed: &mut Vec<LineChange>, added: &mut Vec<LineChange>| {
+        let count = removed.len().max(added.len());
+        let mut removed = removed.drain(..);
+        let mut added = added.drain(..);
+        for _ in 0..count {
+            rows.push(DiffRow {
+                old: removed.next(),
+                new: added.next(),
+            });
+        }
+    };
+    for line in changes {
+        match line.kind {
+            ChangeKind::Same => {
+                flush(&mut rows, &mut removed, &mut added);
+                rows.push(DiffRow {
+                    old: Some(line.clone()),
+                    new: Some(line),
+                });
+            }
+            ChangeKind::Removed => removed.push(line),
+            ChangeKind::Added => added.push(line),
+        }
+    }
+    flush(&mut rows, &mut removed, &mut added);
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_lines, side_by_side, ChangeKind, LineChange, MAX_EDITS};
+
+    fn kinds(changes: &[LineChange]) -> String {
+        changes
+            .iter()
+            .map(|change| match change.kind {
+                ChangeKind::Same => '=',
+                ChangeKind::Removed => '-',
+                ChangeKind::Added => '+',
+            })
+            .collect()
+    }
+
+    // both texts come back out of the diff, with their line numbers
+    fn assert_consistent(old: &str, new: &str, changes: &[LineChange]) {
+        let old_side: Vec<_> = changes.iter().filter_map(|change| change.old_line.map(|line| (line, change.text.as_str()))).collect();
+        let new_side: Vec<_> = changes.iter().filter_map(|change| change.new_line.map(|line| (line, change.text.as_str()))).collect();
+        assert_eq!(old_side, old.lines().enumerate().map(|(index, text)| (index + 1, text)).collect::<Vec<_>>());
+        assert_eq!(new_side, new.lines().enumerate().map(|(index, text)| (index + 1, text)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn same_text() {
+        let changes = diff_lines("a\nb", "a\nb");
+        assert_eq!(kinds(&changes), "==");
+        assert_eq!(diff_lines("", ""), []);
+    }
+
+    #[test]
+    fn everything_added_or_removed() {
+        assert_eq!(kinds(&diff_lines("", "a\nb")), "++");
+        assert_eq!(kinds(&diff_lines("a\nb", "")), "--");
+    }
+
+    #[test]
+    fn changed_line() {
+        let changes = diff_lines("a\nb\nc", "a\nx\nc");
+        assert_eq!(kinds(&changes), "=-+=");
+        assert_eq!(changes[1].old_line, Some(2));
+        assert_eq!(changes[2].new_line, Some(2));
+        assert_consistent("a\nb\nc", "a\nx\nc", &changes);
+    }
+
+    #[test]
+    fn fewest_changes() {
+        // the example of Myers' paper, five edits apart
+        let (old, new) = ("a\nb\nc\na\nb\nb\na", "c\nb\na\nb\na\nc");
+        let changes = diff_lines(old, new);
+        assert_eq!(changes.iter().filter(|change| change.kind != ChangeKind::Same).count(), 5);
+        assert_consistent(old, new, &changes);
+    }
+
+    #[test]
+    fn moved_block() {
+        let old = "fn a() {}\nfn b() {}\nfn c() {}\nfn d() {}";
+        let new = "fn c() {}\nfn a() {}\nfn b() {}\nfn d() {}";
+        let changes = diff_lines(old, new);
+        assert_eq!(kinds(&changes), "+==-=");
+        assert_consistent(old, new, &changes);
+    }
+
+    #[test]
+    fn very_different_block_is_replaced_as_a_whole() {
+        let old: String = (0..MAX_EDITS).map(|index| format!("old {}\n", index)).collect();
+        let new: String = (0..MAX_EDITS).map(|index| format!("new {}\n", index)).collect();
+        let old = format!("first\n{}last", old);
+        let new = format!("first\n{}last", new);
+        let changes = diff_lines(&old, &new);
+        let expected = format!("={}{}=", "-".repeat(MAX_EDITS), "+".repeat(MAX_EDITS));
+        assert_eq!(kinds(&changes), expected);
+        assert_consistent(&old, &new, &changes);
+    }
+
+    #[test]
+    fn pairs_a_hunk_on_rows() {
+        let rows = side_by_side(diff_lines("a\nb\nc\nd", "a\nx\ny\nd"));
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[1].old.as_ref().map(|line| line.text.as_str()), Some("b"));
+        assert_eq!(rows[1].new.as_ref().map(|line| line.text.as_str()), Some("x"));
+        assert_eq!(rows[2].old.as_ref().map(|line| line.text.as_str()), Some("c"));
+        assert_eq!(rows[2].new.as_ref().map(|line| line.text.as_str()), Some("y"));
+    }
+}
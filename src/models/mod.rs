@@ -3,4 +3,21 @@ pub mod video;
 pub mod packet;
 pub mod client;
 pub mod host;
-pub mod commons;
\ No newline at end of file
+pub mod commons;
+pub mod text_operation;
+pub mod sync;
+pub mod presence;
+pub mod workspace;
+pub mod editor_settings;
+pub mod assignment;
+pub mod line_diff;
+pub mod review;
+pub mod history;
+pub mod replay;
+pub mod board;
+pub mod board_svg;
+pub mod laser;
+pub mod bitrate;
+pub mod keyframe;
+pub mod jitter_buffer;
+pub mod fragment;
\ No newline at end of file
@@ -1,17 +1,55 @@
-use std::sync::Arc;
+use std::{ops::{Deref, Range}, sync::Arc};
 
 use js_sys::Uint8Array;
-use serde::{Serialize, Deserialize};
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
 use web_sys::{EncodedAudioChunkInit, EncodedAudioChunk, EncodedVideoChunk, EncodedVideoChunkInit};
 
 use crate::wrappers::{EncodedVideoChunkTypeWrapper, EncodedAudioChunkTypeWrapper};
+
+use super::fragment::{self, Fragment};
+
+/// Bytes of an encoded frame. The sender copies a chunk once into a shared
+/// buffer and every fragment of it serializes its own range, what is
+/// received is owned. Both go over the wire like a `Vec<u8>`.
+#[derive(Clone, PartialEq, Debug)]
+pub enum PacketData {
+    Shared(Arc<[u8]>, Range<usize>),
+    Owned(Vec<u8>),
+}
+
+impl Deref for PacketData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            PacketData::Shared(data, range) => &data[range.clone()],
+            PacketData::Owned(data) => data,
+        }
+    }
+}
+
+impl Serialize for PacketData {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for PacketData {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(PacketData::Owned)
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct VideoPacket {
-    pub data: Vec<u8>,
+    pub data: PacketData,
     pub chunk_type: String,
     pub timestamp: f64,
     pub duration: f64,
     pub sequence_number: u64,
+    /// Set on a piece of a frame that did not fit in one message.
+    #[serde(default)]
+    pub fragment: Option<Fragment>,
 }
 
 impl VideoPacket {
@@ -20,56 +58,69 @@ impl VideoPacket {
         sequence_number: u64,
     ) -> Self {
         let duration = chunk.duration().expect("no duration video chunk");
-        let mut buffer: [u8; 1000000] = [0; 1000000];
-        let byte_length = chunk.byte_length() as usize;
-        chunk.copy_to_with_u8_array(&mut buffer);
-        let data = buffer[0..byte_length].to_vec();
         let chunk_type = EncodedVideoChunkTypeWrapper(chunk.type_()).to_string();
         let timestamp = chunk.timestamp();
+        Self::from_parts(chunk.byte_length() as usize, chunk_type, timestamp, duration, sequence_number, |buffer| {
+            chunk.copy_to_with_u8_array(buffer);
+        })
+    }
+
+    /// A packet of `byte_length` bytes written by `copy_to`, right into the
+    /// one allocation a frame gets.
+    pub fn from_parts(
+        byte_length: usize,
+        chunk_type: String,
+        timestamp: f64,
+        duration: f64,
+        sequence_number: u64,
+        copy_to: impl FnOnce(&mut [u8]),
+    ) -> Self {
+        let mut data: Arc<[u8]> = (0..byte_length).map(|_| 0u8).collect();
+        if let Some(buffer) = Arc::get_mut(&mut data) {
+            copy_to(buffer);
+        }
 
         Self {
-            data,
+            data: PacketData::Shared(data, 0..byte_length),
             chunk_type,
             timestamp,
             duration,
             sequence_number,
+            fragment: None,
         }
     }
 
-    pub fn get_encoded_video_chunk(packet: Arc<VideoPacket>) -> EncodedVideoChunk {
-        let video_data = VideoPacket::get_video_data(packet);
-        VideoPacket::get_encoded_video_chunk_from_data(Arc::new(video_data))
-    }
-
-    pub fn get_encoded_video_chunk_from_data(video_data: Arc<VideoPacket>) -> EncodedVideoChunk {
-        let data = Uint8Array::from(video_data.data.as_ref());
-        let chunk_type = EncodedVideoChunkTypeWrapper::from(video_data.chunk_type.as_str()).0;
-        let mut encoded_chunk_init = EncodedVideoChunkInit::new(&data, video_data.timestamp, chunk_type);
-        encoded_chunk_init.duration(video_data.duration);
-        let encoded_video_chunk = EncodedVideoChunk::new(
-            &encoded_chunk_init
-        ).unwrap();
-        encoded_video_chunk
+    /// Pieces of at most `max_len` bytes each, the packet itself if it fits.
+    /// The pieces share the data of the packet.
+    pub fn into_fragments(self, max_len: usize) -> Vec<VideoPacket> {
+        if self.data.len() <= max_len {
+            return vec![self];
+        }
+        let (data, start) = match self.data {
+            PacketData::Shared(ref data, ref range) => (data.clone(), range.start),
+            PacketData::Owned(ref data) => (Arc::from(data.as_slice()), 0),
+        };
+        fragment::split(self.data.len(), max_len)
+            .map(|(fragment, range)| VideoPacket {
+                data: PacketData::Shared(data.clone(), start + range.start..start + range.end),
+                chunk_type: self.chunk_type.clone(),
+                timestamp: self.timestamp,
+                duration: self.duration,
+                sequence_number: self.sequence_number,
+                fragment: Some(fragment),
+            })
+            .collect()
     }
 
-    pub fn get_video_data(packet: Arc<VideoPacket>) -> VideoPacket{
+    /// The chunk for the decoder, the data is copied once, into it.
+    pub fn get_encoded_video_chunk(packet: &VideoPacket) -> EncodedVideoChunk {
         let chunk_type = EncodedVideoChunkTypeWrapper::from(packet.chunk_type.as_str()).0;
-        let video_data = Uint8Array::new_with_length(packet.data.len().try_into().unwrap());
-        video_data.copy_from(&packet.data);
-        let video_chunk = EncodedVideoChunkInit::new(&video_data, packet.timestamp, chunk_type);
-        let chunk = EncodedVideoChunk::new(&video_chunk).unwrap();
-        
-        let mut video_vector = vec![0u8; chunk.byte_length() as usize];
-        let video_message = video_vector.as_mut();
-        chunk.copy_to_with_u8_array(video_message);
-        let data = VideoPacket {
-            data: video_message.to_vec(),
-            chunk_type: packet.chunk_type.clone(),
-            timestamp: packet.timestamp,
-            duration: packet.duration,
-            sequence_number: packet.sequence_number
-        };
-        data
+        // SAFETY: nothing allocates in the wasm memory, and so moves it, until
+        // `EncodedVideoChunk::new` has copied the data out of the view.
+        let data = unsafe { Uint8Array::view(&packet.data) };
+        let mut encoded_chunk_init = EncodedVideoChunkInit::new(&data, packet.timestamp, chunk_type);
+        encoded_chunk_init.duration(packet.duration);
+        EncodedVideoChunk::new(&encoded_chunk_init).unwrap()
     }
 }
 
@@ -84,10 +135,8 @@ pub struct AudioPacket {
 impl AudioPacket {
     pub fn new(chunk: web_sys::EncodedAudioChunk) -> Self {
         let duration = chunk.duration().unwrap();
-        let mut buffer: [u8; 100000] = [0; 100000];
-        let byte_length = chunk.byte_length() as usize;
-        chunk.copy_to_with_u8_array(&mut buffer);
-        let data = buffer[0..byte_length as usize].to_vec();
+        let mut data = vec![0u8; chunk.byte_length() as usize];
+        chunk.copy_to_with_u8_array(&mut data);
         let chunk_type = EncodedAudioChunkTypeWrapper(chunk.type_()).to_string();
         let timestamp = chunk.timestamp();
 
@@ -95,21 +144,18 @@ impl AudioPacket {
             data,
             chunk_type,
             timestamp,
-            duration 
+            duration
         }
     }
 
-    pub fn get_encoded_audio_chunk(packet: AudioPacket) -> EncodedAudioChunk {
-        let chunk_type = EncodedAudioChunkTypeWrapper::from(packet.chunk_type).0;
-        let audio_data = &packet.data;
-        let audio_data_js: js_sys::Uint8Array =
-            js_sys::Uint8Array::new_with_length(audio_data.len() as u32);
-        audio_data_js.copy_from(audio_data.as_slice());
-        let chunk_type = EncodedAudioChunkTypeWrapper(chunk_type);
-        let mut audio_chunk_init =
-            EncodedAudioChunkInit::new(&audio_data_js.into(), packet.timestamp, chunk_type.0);
+    /// The chunk for the decoder, the data is copied once, into it.
+    pub fn get_encoded_audio_chunk(packet: &AudioPacket) -> EncodedAudioChunk {
+        let chunk_type = EncodedAudioChunkTypeWrapper::from(packet.chunk_type.as_str()).0;
+        // SAFETY: nothing allocates in the wasm memory, and so moves it, until
+        // `EncodedAudioChunk::new` has copied the data out of the view.
+        let data = unsafe { Uint8Array::view(&packet.data) };
+        let mut audio_chunk_init = EncodedAudioChunkInit::new(&data, packet.timestamp, chunk_type);
         audio_chunk_init.duration(packet.duration);
-        let encoded_audio_chunk = EncodedAudioChunk::new(&audio_chunk_init).unwrap();
-        encoded_audio_chunk
+        EncodedAudioChunk::new(&audio_chunk_init).unwrap()
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+/// Monaco range, lines and columns start from 1.
+#[derive(PartialEq, Eq, Clone, Serialize, Deserialize, Debug)]
+pub struct SelectionRange {
+    pub start_line: u32,
+    pub start_column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+}
+
+impl SelectionRange {
+    pub fn caret(line: u32, column: u32) -> Self {
+        Self {
+            start_line: line,
+            start_column: column,
+            end_line: line,
+            end_column: column,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start_line == self.end_line && self.start_column == self.end_column
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Serialize, Deserialize, Debug)]
+pub struct EditorCursor {
+    pub line: u32,
+    pub column: u32,
+    pub selections: Vec<SelectionRange>,
+}
+
+impl EditorCursor {
+    /// Lines covered by the first selection, or the caret line.
+    /// A selection that ends at the start of a line doesn't include that line.
+    pub fn selected_lines(&self) -> (u32, u32) {
+        match self.selections.first() {
+            Some(selection) if selection.end_line > selection.start_line && selection.end_column == 1 => {
+                (selection.start_line, selection.end_line - 1)
+            }
+            Some(selection) => (selection.start_line, selection.end_line),
+            None => (self.line, self.line),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Serialize, Deserialize, Debug)]
+pub struct RemoteCursor {
+    pub name: String,
+    pub file_id: String,
+    pub cursor: EditorCursor,
+}
+
+impl RemoteCursor {
+    pub fn new(name: &str, file_id: String, cursor: EditorCursor) -> Self {
+        Self {
+            name: name.to_owned(),
+            file_id,
+            cursor,
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+
+use super::{text_operation::TextOperation, workspace::{Workspace, WorkspaceAction}};
+
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub enum ReplayChange {
+    Edit {
+        file_id: String,
+        operation: TextOperation,
+    },
+    Workspace {
+        action: WorkspaceAction,
+    },
+}
+
+/// `at` is in milliseconds since the start of the recording.
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct ReplayEvent {
+    pub at: f64,
+    pub change: ReplayChange,
+}
+
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct Bookmark {
+    pub at: f64,
+    pub title: String,
+}
+
+/// Every change the host made to the workspace, starting from `initial`.
+/// `started_at` is in milliseconds since the epoch.
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct LessonRecording {
+    pub started_at: f64,
+    pub initial: Workspace,
+    pub events: Vec<ReplayEvent>,
+    pub bookmarks: Vec<Bookmark>,
+}
+
+impl LessonRecording {
+    pub fn new(initial: Workspace, started_at: f64) -> Self {
+        Self {
+            started_at,
+            initial,
+            events: Vec::new(),
+            bookmarks: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, change: ReplayChange, now: f64) {
+        let at = (now - self.started_at).max(self.duration());
+        self.events.push(ReplayEvent { at, change });
+    }
+
+    pub fn add_bookmark(&mut self, title: String, now: f64) -> Bookmark {
+        let bookmark = Bookmark {
+            at: (now - self.started_at).max(0.0),
+            title,
+        };
+        self.insert_bookmark(bookmark.clone());
+        bookmark
+    }
+
+    pub fn insert_bookmark(&mut self, bookmark: Bookmark) {
+        let index = self.bookmarks.partition_point(|current| current.at <= bookmark.at);
+        self.bookmarks.insert(index, bookmark);
+    }
+
+    pub fn duration(&self) -> f64 {
+        let last_event = self.events.last().map_or(0.0, |event| event.at);
+        let last_bookmark = self.bookmarks.last().map_or(0.0, |bookmark| bookmark.at);
+        last_event.max(last_bookmark)
+    }
+}
+
+/// Workspace of a recording at some moment, moving forward applies only the new events.
+#[derive(PartialEq, Clone, Debug)]
+pub struct ReplayState {
+    started_at: f64,
+    applied: usize,
+    pub workspace: Workspace,
+}
+
+impl ReplayState {
+    pub fn new(recording: &LessonRecording) -> Self {
+        Self {
+            started_at: recording.started_at,
+            applied: 0,
+            workspace: recording.initial.clone(),
+        }
+    }
+
+    fn apply(&mut self, change: &ReplayChange) -> crate::Result<()> {
+        match change {
+            ReplayChange::Edit { file_id, operation } => {
+                let content = self.workspace
+                    .content_mut(file_id)
+                    .ok_or_else(|| crate::Error::InvalidWorkspace(format!("file with id '{}' not found", file_id)))?;
+                *content = operation.apply(content)?;
+                Ok(())
+            }
+            ReplayChange::Workspace { action } => self.workspace.apply(action),
+        }
+    }
+
+    pub fn seek(&mut self, recording: &LessonRecording, at: f64) {
+        let is_behind = self.applied > 0
+            && !recording.events.get(self.applied - 1).is_some_and(|event| event.at <= at);
+        if self.started_at != recording.started_at || self.applied > recording.events.len() || is_behind {
+            *self = Self::new(recording);
+        }
+        while let Some(event) = recording.events.get(self.applied).filter(|event| event.at <= at) {
+            // a broken event only loses its own change, the rest of the recording still plays
+            if let Err(err) = self.apply(&event.change) {
+                log::error!("cannot replay event: {}", err);
+            }
+            self.applied += 1;
+        }
+    }
+}
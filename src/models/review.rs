@@ -0,0 +1,242 @@
+use serde::{Deserialize, Serialize};
+
+use super::{text_operation::{OpComponent, TextOperation}, workspace::{DocumentKey, WorkspaceFile}};
+
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Debug)]
+pub enum ReviewAuthor {
+    Host,
+    Student,
+}
+
+impl ReviewAuthor {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReviewAuthor::Host => "Учитель",
+            ReviewAuthor::Student => "Ученик",
+        }
+    }
+}
+
+/// `created_at` is in milliseconds since the epoch.
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct ReviewMessage {
+    pub author: ReviewAuthor,
+    pub text: String,
+    pub created_at: f64,
+}
+
+impl ReviewMessage {
+    pub fn new(author: ReviewAuthor, text: String) -> Self {
+        Self {
+            author,
+            text,
+            created_at: js_sys::Date::now(),
+        }
+    }
+}
+
+/// Host comment on lines of a student's file followed by the replies.
+/// Lines start from 1, like in monaco.
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct ReviewThread {
+    pub id: String,
+    pub file_id: String,
+    pub path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub messages: Vec<ReviewMessage>,
+}
+
+impl ReviewThread {
+    pub fn new(file: &WorkspaceFile, start_line: u32, end_line: u32, text: String) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            file_id: file.id.clone(),
+            path: file.path.clone(),
+            start_line: start_line.min(end_line),
+            end_line: start_line.max(end_line),
+            messages: vec![ReviewMessage::new(ReviewAuthor::Host, text)],
+        }
+    }
+
+    /// Moves the lines along with an edit of the file, `chars` is the content
+    /// the edit was applied to. Lines added right above the thread push it
+    /// down, lines added right below it are left out.
+    fn transform(&mut self, chars: &[char], operation: &TextOperation) {
+        let start = transform_line(chars, operation, line_start(chars, self.start_line), true);
+        let end = transform_line(chars, operation, line_end(chars, self.end_line), false);
+        self.start_line = start;
+        self.end_line = end.max(start);
+    }
+
+    pub fn lines_label(&self) -> String {
+        if self.start_line == self.end_line {
+            format!("строка {}", self.start_line)
+        } else {
+            format!("строки {}–{}", self.start_line, self.end_line)
+        }
+    }
+}
+
+/// Review threads of one student, in the order they were started.
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug, Default)]
+pub struct Review {
+    pub threads: Vec<ReviewThread>,
+}
+
+impl Review {
+    pub fn add(&mut self, thread: ReviewThread) {
+        match self.threads.iter_mut().find(|current| current.id == thread.id) {
+            Some(current) => *current = thread,
+            None => self.threads.push(thread),
+        }
+    }
+
+    pub fn reply(&mut self, thread_id: &str, message: ReviewMessage) -> crate::Result<()> {
+        self.threads
+            .iter_mut()
+            .find(|thread| thread.id == thread_id)
+            .map(|thread| thread.messages.push(message))
+            .ok_or_else(|| crate::Error::UnknownReviewThread(thread_id.to_owned()))
+    }
+
+    /// Keeps the threads on their lines while the file is edited, `text` is
+    /// the content the edit was applied to.
+    pub fn transform(&mut self, document: &DocumentKey, text: &str, operation: &TextOperation) {
+        let DocumentKey::File(file_id) = document else {
+            return;
+        };
+        let mut threads = self.threads.iter_mut().filter(|thread| &thread.file_id == file_id).peekable();
+        if threads.peek().is_none() {
+            return;
+        }
+        let chars: Vec<char> = text.chars().collect();
+        threads.for_each(|thread| thread.transform(&chars, operation));
+    }
+
+    pub fn for_file<'a>(&'a self, file_id: &'a str) -> impl Iterator<Item = &'a ReviewThread> {
+        self.threads.iter().filter(move |thread| thread.file_id == file_id)
+    }
+}
+
+fn newlines(chars: &[char]) -> impl Iterator<Item = usize> + '_ {
+    chars.iter().enumerate().filter(|(_, char)| **char == '\n').map(|(index, _)| index)
+}
+
+fn line_start(chars: &[char], line: u32) -> usize {
+    match (line as usize).checked_sub(2) {
+        Some(previous) => newlines(chars).nth(previous).map_or(chars.len(), |index| index + 1),
+        None => 0,
+    }
+}
+
+fn line_end(chars: &[char], line: u32) -> usize {
+    newlines(chars).nth((line as usize).saturating_sub(1)).unwrap_or(chars.len())
+}
+
+// Line of `position` after the edit, a deleted position goes to where the
+// deletion was. Text inserted right at it ends up before it if `after_insert`.
+fn transform_line(chars: &[char], operation: &TextOperation, position: usize, after_insert: bool) -> u32 {
+    let mut index = 0;
+    let mut line = 1;
+    for op in operation.ops() {
+        match op {
+            OpComponent::Retain(count) => {
+                let end = (index + count).min(chars.len());
+                if position < end {
+                    return line + newlines(&chars[index..position]).count() as u32;
+                }
+                line += newlines(&chars[index..end]).count() as u32;
+                index = end;
+            }
+            OpComponent::Insert(inserted) => {
+                if index == position && !after_insert {
+                    return line;
+                }
+                line += inserted.matches('\n').count() as u32;
+            }
+            OpComponent::Delete(count) => {
+                if position < index + count {
+                    return line;
+                }
+                index += count;
+            }
+        }
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Review, ReviewThread};
+    use crate::models::{text_operation::TextOperation, workspace::DocumentKey};
+
+    const TEXT: &str = "fn main() {\n    let a = 1;\n    let b = 2;\n}\n";
+
+    // a thread on `let a` and `let b`, after the file changes to `new`
+    fn lines_after(new: &str) -> (u32, u32) {
+        let mut review = Review::default();
+        review.add(ReviewThread {
+            id: "thread".to_owned(),
+            file_id: "file".to_owned(),
+            path: "main.rs".to_owned(),
+            start_line: 2,
+            end_line: 3,
+            messages: Vec::new(),
+        });
+        let operation = TextOperation::diff(TEXT, new);
+        review.transform(&DocumentKey::File("file".to_owned()), TEXT, &operation);
+        let thread = &review.threads[0];
+        (thread.start_line, thread.end_line)
+    }
+
+    #[test]
+    fn edit_on_the_lines_keeps_them() {
+        assert_eq!(lines_after("fn main() {\n    let a = 10;\n    let b = 2;\n}\n"), (2, 3));
+        assert_eq!(lines_after("fn main() {\n    let a = 1;\n    let b = 2; // two\n}\n"), (2, 3));
+    }
+
+    #[test]
+    fn lines_added_above_push_it_down() {
+        assert_eq!(lines_after("// entry\nfn main() {\n    let a = 1;\n    let b = 2;\n}\n"), (3, 4));
+        assert_eq!(lines_after("fn main() {\n\n    let a = 1;\n    let b = 2;\n}\n"), (3, 4));
+    }
+
+    #[test]
+    fn lines_removed_above_pull_it_up() {
+        assert_eq!(lines_after("    let a = 1;\n    let b = 2;\n}\n"), (1, 2));
+    }
+
+    #[test]
+    fn lines_added_inside_stretch_it() {
+        assert_eq!(lines_after("fn main() {\n    let a = 1;\n    let c = 3;\n    let b = 2;\n}\n"), (2, 4));
+    }
+
+    #[test]
+    fn lines_added_below_are_left_out() {
+        assert_eq!(lines_after("fn main() {\n    let a = 1;\n    let b = 2;\n    let c = 3;\n}\n"), (2, 3));
+    }
+
+    #[test]
+    fn removed_lines_shrink_it() {
+        assert_eq!(lines_after("fn main() {\n    let b = 2;\n}\n"), (2, 2));
+        assert_eq!(lines_after("fn main() {\n}\n"), (2, 2));
+    }
+
+    #[test]
+    fn other_documents_leave_it_alone() {
+        let mut review = Review::default();
+        review.add(ReviewThread {
+            id: "thread".to_owned(),
+            file_id: "file".to_owned(),
+            path: "main.rs".to_owned(),
+            start_line: 2,
+            end_line: 3,
+            messages: Vec::new(),
+        });
+        let operation = TextOperation::diff("a", "\n\na");
+        review.transform(&DocumentKey::File("other".to_owned()), "a", &operation);
+        review.transform(&DocumentKey::TextArea, "a", &operation);
+        assert_eq!((review.threads[0].start_line, review.threads[0].end_line), (2, 3));
+    }
+}
@@ -0,0 +1,426 @@
+use std::collections::VecDeque;
+
+use crate::constants::SYNC_HISTORY_MAX_LEN;
+
+use super::text_operation::TextOperation;
+
+/// History of a buffer on the side that owns it (the host).
+/// Operations from a client are transformed against everything the client
+/// has not seen yet before they are applied. Only those are kept, at most
+/// `SYNC_HISTORY_MAX_LEN`; an operation against a revision no longer kept
+/// is rejected and the client has to start over.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct SyncServer {
+    history: VecDeque<TextOperation>,
+    /// Revision of the first operation kept.
+    base: u64,
+}
+
+impl SyncServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.base + self.history.len() as u64
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+        self.base = 0;
+    }
+
+    pub fn push(&mut self, operation: TextOperation) {
+        self.history.push_back(operation);
+        if self.history.len() > SYNC_HISTORY_MAX_LEN {
+            self.history.pop_front();
+            self.base += 1;
+        }
+    }
+
+    /// Drops the operations the client has seen, it sends `revision` only after it did.
+    pub fn acknowledge(&mut self, revision: u64) {
+        let seen = revision.saturating_sub(self.base).min(self.history.len() as u64);
+        self.history.drain(..seen as usize);
+        self.base += seen;
+    }
+
+    pub fn transform(&self, revision: u64, operation: TextOperation) -> crate::Result<TextOperation> {
+        if revision < self.base {
+            return Err(crate::Error::InvalidOperation(format!("revision {} is no longer kept", revision)));
+        }
+        let start = usize::try_from(revision - self.base)
+            .ok()
+            .filter(|&start| start <= self.history.len())
+            .ok_or_else(|| crate::Error::InvalidOperation(format!("unknown revision {}", revision)))?;
+        self.history
+            .range(start..)
+            .try_fold(operation, |operation, other| {
+                TextOperation::transform(&operation, other).map(|(operation, _)| operation)
+            })
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+enum SyncState {
+    Synchronized,
+    AwaitingConfirm(TextOperation),
+    AwaitingWithBuffer(TextOperation, TextOperation),
+}
+
+/// Buffer state on the side that edits a document owned by someone else.
+/// At most one operation is in flight; edits made meanwhile are buffered.
+#[derive(PartialEq, Clone, Debug)]
+pub struct SyncClient {
+    revision: u64,
+    state: SyncState,
+}
+
+impl Default for SyncClient {
+    fn default() -> Self {
+        Self {
+            revision: 0,
+            state: SyncState::Synchronized,
+        }
+    }
+}
+
+impl SyncClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reset(&mut self) {
+        self.revision = 0;
+        self.state = SyncState::Synchronized;
+    }
+
+    /// Registers a local edit, returning the revision and operation to send if nothing is in flight.
+    pub fn apply_local(&mut self, operation: TextOperation) -> crate::Result<Option<(u64, TextOperation)>> {
+        let (state, to_send) = match &self.state {
+            SyncState::Synchronized => {
+                (SyncState::AwaitingConfirm(operation.clone()), Some((self.revision, operation)))
+            }
+            SyncState::AwaitingConfirm(outstanding) => {
+                (SyncState::AwaitingWithBuffer(outstanding.clone(), operation), None)
+            }
+            SyncState::AwaitingWithBuffer(outstanding, buffer) => {
+                (SyncState::AwaitingWithBuffer(outstanding.clone(), buffer.compose(&operation)?), None)
+            }
+        };
+        self.state = state;
+        Ok(to_send)
+    }
+
+    /// Registers an operation from the owner, returning it transformed so it can be applied locally.
+    pub fn apply_server(&mut self, operation: TextOperation) -> crate::Result<TextOperation> {
+        let (state, operation) = match &self.state {
+            SyncState::Synchronized => (SyncState::Synchronized, operation),
+            SyncState::AwaitingConfirm(outstanding) => {
+                let (outstanding, operation) = TextOperation::transform(outstanding, &operation)?;
+                (SyncState::AwaitingConfirm(outstanding), operation)
+            }
+            SyncState::AwaitingWithBuffer(outstanding, buffer) => {
+                let (outstanding, operation) = TextOperation::transform(outstanding, &operation)?;
+                let (buffer, operation) = TextOperation::transform(buffer, &operation)?;
+                (SyncState::AwaitingWithBuffer(outstanding, buffer), operation)
+            }
+        };
+        self.revision += 1;
+        self.state = state;
+        Ok(operation)
+    }
+
+    /// Confirms the in-flight operation, returning the buffered one that should be sent next.
+    pub fn server_ack(&mut self) -> Option<(u64, TextOperation)> {
+        self.revision += 1;
+        match std::mem::replace(&mut self.state, SyncState::Synchronized) {
+            SyncState::Synchronized => {
+                log::error!("received ack without pending operation");
+                None
+            }
+            SyncState::AwaitingConfirm(_) => None,
+            SyncState::AwaitingWithBuffer(_, buffer) => {
+                self.state = SyncState::AwaitingConfirm(buffer.clone());
+                Some((self.revision, buffer))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::{SyncClient, SyncServer};
+    use crate::{constants::SYNC_HISTORY_MAX_LEN, models::text_operation::TextOperation};
+
+    fn operation(old: &str, new: &str) -> TextOperation {
+        TextOperation::diff(old, new)
+    }
+
+    enum Reply {
+        Ack,
+        Operation(TextOperation),
+    }
+
+    struct Client {
+        sync: SyncClient,
+        text: String,
+        inbox: VecDeque<Reply>,
+    }
+
+    /// The owner of a document and the clients editing it, messages wait in
+    /// the inboxes until they are delivered one by one.
+    struct Lesson {
+        sync: SyncServer,
+        text: String,
+        inbox: VecDeque<(usize, u64, TextOperation)>,
+        clients: Vec<Client>,
+    }
+
+    impl Lesson {
+        fn new(text: &str, clients: usize) -> Self {
+            let client = || Client { sync: SyncClient::new(), text: text.to_owned(), inbox: VecDeque::new() };
+            Self {
+                sync: SyncServer::new(),
+                text: text.to_owned(),
+                inbox: VecDeque::new(),
+                clients: (0..clients).map(|_| client()).collect(),
+            }
+        }
+
+        fn host_edit(&mut self, new: &str) {
+            let operation = operation(&self.text, new);
+            self.text = new.to_owned();
+            self.sync.push(operation.clone());
+            for client in &mut self.clients {
+                client.inbox.push_back(Reply::Operation(operation.clone()));
+            }
+        }
+
+        fn client_edit(&mut self, index: usize, new: &str) {
+            let client = &mut self.clients[index];
+            let operation = operation(&client.text, new);
+            client.text = new.to_owned();
+            if let Some((revision, operation)) = client.sync.apply_local(operation).unwrap() {
+                self.inbox.push_back((index, revision, operation));
+            }
+        }
+
+        // With one client the history it has seen can go, like on the host.
+        fn deliver_to_host(&mut self) -> bool {
+            let Some((index, revision, operation)) = self.inbox.pop_front() else {
+                return false;
+            };
+            if self.clients.len() == 1 {
+                self.sync.acknowledge(revision);
+            }
+            let operation = self.sync.transform(revision, operation).unwrap();
+            self.text = operation.apply(&self.text).unwrap();
+            self.sync.push(operation.clone());
+            for (other, client) in self.clients.iter_mut().enumerate() {
+                let reply = if other == index { Reply::Ack } else { Reply::Operation(operation.clone()) };
+                client.inbox.push_back(reply);
+            }
+            true
+        }
+
+        fn deliver_to_client(&mut self, index: usize) -> bool {
+            let client = &mut self.clients[index];
+            match client.inbox.pop_front() {
+                Some(Reply::Ack) => {
+                    if let Some((revision, operation)) = client.sync.server_ack() {
+                        self.inbox.push_back((index, revision, operation));
+                    }
+                    true
+                }
+                Some(Reply::Operation(operation)) => {
+                    let operation = client.sync.apply_server(operation).unwrap();
+                    client.text = operation.apply(&client.text).unwrap();
+                    true
+                }
+                None => false,
+            }
+        }
+
+        fn deliver_all(&mut self) {
+            while self.deliver_to_host() || (0..self.clients.len()).any(|index| self.deliver_to_client(index)) {}
+        }
+
+        fn assert_converged(&self) {
+            for client in &self.clients {
+                assert_eq!(client.text, self.text);
+            }
+        }
+    }
+
+    #[test]
+    fn client_sends_one_operation_at_a_time() {
+        let mut sync = SyncClient::new();
+        let sent = sync.apply_local(operation("", "a")).unwrap();
+        assert_eq!(sent, Some((0, operation("", "a"))));
+        assert_eq!(sync.apply_local(operation("a", "ab")).unwrap(), None);
+        assert_eq!(sync.apply_local(operation("ab", "abc")).unwrap(), None);
+
+        // the edits made meanwhile go out together, against the confirmed revision
+        assert_eq!(sync.server_ack(), Some((1, operation("a", "abc"))));
+        assert_eq!(sync.server_ack(), None);
+        assert_eq!(sync.apply_local(operation("abc", "abcd")).unwrap(), Some((2, operation("abc", "abcd"))));
+    }
+
+    #[test]
+    fn ack_without_pending_operation_is_ignored() {
+        let mut sync = SyncClient::new();
+        assert_eq!(sync.server_ack(), None);
+        assert_eq!(sync.apply_local(operation("", "a")).unwrap(), Some((1, operation("", "a"))));
+    }
+
+    #[test]
+    fn remote_operation_is_transformed_over_pending_ones() {
+        let mut sync = SyncClient::new();
+        sync.apply_local(operation("ac", "abc")).unwrap();
+        sync.apply_local(operation("abc", "abcd")).unwrap();
+        let remote = sync.apply_server(operation("ac", "xac")).unwrap();
+        assert_eq!(remote.apply("abcd").unwrap(), "xabcd");
+
+        // the buffered edit now comes after the remote one
+        assert_eq!(sync.server_ack(), Some((2, operation("xabc", "xabcd"))));
+    }
+
+    #[test]
+    fn host_and_student_edits_converge() {
+        let mut lesson = Lesson::new("fn main() {}", 1);
+        lesson.client_edit(0, "fn main() { a(); }");
+        lesson.host_edit("pub fn main() {}");
+        lesson.client_edit(0, "fn main() { a(); b(); }");
+        lesson.deliver_to_host();
+        assert_eq!(lesson.text, "pub fn main() { a(); }");
+        lesson.host_edit("pub fn main() { a(); }\n");
+        lesson.deliver_to_client(0);
+        assert_eq!(lesson.clients[0].text, "pub fn main() { a(); b(); }");
+        lesson.client_edit(0, "// entry\npub fn main() { a(); b(); }");
+        lesson.deliver_all();
+        lesson.assert_converged();
+        assert_eq!(lesson.text, "// entry\npub fn main() { a(); b(); }\n");
+    }
+
+    #[test]
+    fn two_clients_converge() {
+        let mut lesson = Lesson::new("abc", 2);
+        lesson.client_edit(0, "xabc");
+        lesson.client_edit(1, "abcy");
+        lesson.client_edit(0, "xab");
+        lesson.deliver_to_host();
+        lesson.client_edit(1, "bcy");
+        lesson.deliver_to_client(1);
+        lesson.deliver_all();
+        lesson.assert_converged();
+        assert_eq!(lesson.text, "xby");
+    }
+
+    #[test]
+    fn random_interleaved_edits_converge() {
+        // xorshift, the same run every time
+        let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move |bound: usize| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % bound as u64) as usize
+        };
+        for clients in 1..=2 {
+            let mut lesson = Lesson::new("hello", clients);
+            for _ in 0..500 {
+                let editor = next(clients + 1);
+                let text = match lesson.clients.get(editor) {
+                    Some(client) => client.text.clone(),
+                    None => lesson.text.clone(),
+                };
+                let mut chars: Vec<char> = text.chars().collect();
+                let at = next(chars.len() + 1);
+                if next(3) == 0 && at < chars.len() {
+                    chars.remove(at);
+                } else {
+                    chars.insert(at, ['a', 'б', '\n'][next(3)]);
+                }
+                let new: String = chars.into_iter().collect();
+                if editor < clients {
+                    lesson.client_edit(editor, &new);
+                } else {
+                    lesson.host_edit(&new);
+                }
+                match next(clients + 1) {
+                    0 => {
+                        lesson.deliver_to_host();
+                    }
+                    index => {
+                        lesson.deliver_to_client(index - 1);
+                    }
+                }
+            }
+            lesson.deliver_all();
+            lesson.assert_converged();
+        }
+    }
+
+    #[test]
+    fn acknowledge_drops_what_the_client_has_seen() {
+        let mut sync = SyncServer::new();
+        sync.push(operation("", "a"));
+        sync.push(operation("a", "ab"));
+        sync.push(operation("ab", "abc"));
+        sync.acknowledge(2);
+        assert_eq!(sync.revision(), 3);
+        assert!(sync.transform(1, operation("a", "xa")).is_err());
+        let transformed = sync.transform(2, operation("ab", "xab")).unwrap();
+        assert_eq!(transformed.apply("abc").unwrap(), "xabc");
+
+        // an old or repeated acknowledgement changes nothing
+        sync.acknowledge(1);
+        sync.acknowledge(5);
+        assert_eq!(sync.revision(), 3);
+        assert!(sync.transform(3, operation("abc", "abcd")).is_ok());
+        assert!(sync.transform(4, operation("abc", "abcd")).is_err());
+    }
+
+    #[test]
+    fn history_is_capped() {
+        let mut sync = SyncServer::new();
+        let mut text = String::new();
+        for _ in 0..=SYNC_HISTORY_MAX_LEN {
+            let new = format!("{}a", text);
+            sync.push(operation(&text, &new));
+            text = new;
+        }
+        assert_eq!(sync.revision(), SYNC_HISTORY_MAX_LEN as u64 + 1);
+        assert!(sync.transform(0, operation("", "x")).is_err());
+        let transformed = sync.transform(1, operation("a", "xa")).unwrap();
+        assert_eq!(transformed.apply(&text).unwrap(), format!("x{}", text));
+    }
+
+    #[test]
+    fn sync_starts_over_after_a_resync() {
+        let mut lesson = Lesson::new("abc", 1);
+        for _ in 0..=SYNC_HISTORY_MAX_LEN {
+            let new = format!("{}a", lesson.text);
+            lesson.host_edit(&new);
+        }
+        // the student is too far behind, its edit can't be placed
+        lesson.client_edit(0, "xabc");
+        let (_, revision, operation) = lesson.inbox.pop_front().unwrap();
+        assert!(lesson.sync.transform(revision, operation).is_err());
+
+        // both sides start over from the host's content
+        lesson.sync.clear();
+        let client = &mut lesson.clients[0];
+        client.sync.reset();
+        client.inbox.clear();
+        client.text = lesson.text.clone();
+        let new = format!("x{}", lesson.text);
+        lesson.client_edit(0, &new);
+        lesson.deliver_all();
+        lesson.assert_converged();
+        assert!(lesson.text.starts_with("xabc"));
+    }
+}
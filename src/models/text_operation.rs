@@ -0,0 +1,451 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(PartialEq, Eq, Clone, Serialize, Deserialize, Debug)]
+pub enum OpComponent {
+    Retain(usize),
+    Insert(String),
+    Delete(usize),
+}
+
+/// A change to a text buffer, expressed as a sequence of retain/insert/delete
+/// components that walk the whole base document. Lengths are counted in chars.
+#[derive(PartialEq, Eq, Clone, Serialize, Deserialize, Debug, Default)]
+pub struct TextOperation {
+    ops: Vec<OpComponent>,
+    base_len: usize,
+    target_len: usize,
+}
+
+impl TextOperation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ops(&self) -> &[OpComponent] {
+        &self.ops
+    }
+
+    pub fn base_len(&self) -> usize {
+        self.base_len
+    }
+
+    pub fn target_len(&self) -> usize {
+        self.target_len
+    }
+
+    pub fn retain(&mut self, count: usize) -> &mut Self {
+        if count == 0 {
+            return self;
+        }
+        self.base_len += count;
+        self.target_len += count;
+        match self.ops.last_mut() {
+            Some(OpComponent::Retain(last)) => *last += count,
+            _ => self.ops.push(OpComponent::Retain(count)),
+        }
+        self
+    }
+
+    pub fn insert(&mut self, text: &str) -> &mut Self {
+        if text.is_empty() {
+            return self;
+        }
+        self.target_len += text.chars().count();
+        let ops_len = self.ops.len();
+        match self.ops.last_mut() {
+            Some(OpComponent::Insert(last)) => last.push_str(text),
+            // Keep inserts in front of deletes, so equal operations always look the same.
+            Some(OpComponent::Delete(_)) => {
+                let before_delete = ops_len.checked_sub(2).and_then(|index| self.ops.get_mut(index));
+                match before_delete {
+                    Some(OpComponent::Insert(prev)) => prev.push_str(text),
+                    _ => self.ops.insert(ops_len - 1, OpComponent::Insert(text.to_owned())),
+                }
+            }
+            _ => self.ops.push(OpComponent::Insert(text.to_owned())),
+        }
+        self
+    }
+
+    pub fn delete(&mut self, count: usize) -> &mut Self {
+        if count == 0 {
+            return self;
+        }
+        self.base_len += count;
+        match self.ops.last_mut() {
+            Some(OpComponent::Delete(last)) => *last += count,
+            _ => self.ops.push(OpComponent::Delete(count)),
+        }
+        self
+    }
+
+    pub fn is_noop(&self) -> bool {
+        self.ops.iter().all(|op| matches!(op, OpComponent::Retain(_)))
+    }
+
+    /// Builds the operation turning `old` into `new` by trimming their common
+    /// prefix and suffix, which matches what a single keystroke or paste produces.
+    pub fn diff(old: &str, new: &str) -> Self {
+        let old_chars: Vec<char> = old.chars().collect();
+        let new_chars: Vec<char> = new.chars().collect();
+        let prefix = old_chars
+            .iter()
+            .zip(new_chars.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let suffix = old_chars
+            .iter()
+            .skip(prefix)
+            .rev()
+            .zip(new_chars.iter().skip(prefix).rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let inserted: String = new_chars
+            .iter()
+            .skip(prefix)
+            .take(new_chars.len() - prefix - suffix)
+            .collect();
+
+        let mut operation = Self::new();
+        operation
+            .retain(prefix)
+            .delete(old_chars.len() - prefix - suffix)
+            .insert(&inserted)
+            .retain(suffix);
+        operation
+    }
+
+    pub fn apply(&self, text: &str) -> crate::Result<String> {
+        let chars: Vec<char> = text.chars().collect();
+        if chars.len() != self.base_len {
+            return Err(crate::Error::InvalidOperation(format!(
+                "base length {} does not match text length {}",
+                self.base_len,
+                chars.len()
+            )));
+        }
+        let mut result = String::with_capacity(text.len());
+        let mut index = 0;
+        for op in &self.ops {
+            match op {
+                OpComponent::Retain(count) => {
+                    let retained = chars
+                        .get(index..index + count)
+                        .ok_or_else(|| crate::Error::InvalidOperation("retain past end of text".to_owned()))?;
+                    result.extend(retained);
+                    index += count;
+                }
+                OpComponent::Insert(inserted) => result.push_str(inserted),
+                OpComponent::Delete(count) => index += count,
+            }
+        }
+        Ok(result)
+    }
+
+    /// Builds the operation that reverts `self`, `text` is the document `self` was applied to.
+    pub fn invert(&self, text: &str) -> crate::Result<Self> {
+        let chars: Vec<char> = text.chars().collect();
+        if chars.len() != self.base_len {
+            return Err(crate::Error::InvalidOperation(format!(
+                "base length {} does not match text length {}",
+                self.base_len,
+                chars.len()
+            )));
+        }
+        let mut inverse = Self::new();
+        let mut index = 0;
+        for op in &self.ops {
+            match op {
+                OpComponent::Retain(count) => {
+                    inverse.retain(*count);
+                    index += count;
+                }
+                OpComponent::Insert(inserted) => {
+                    inverse.delete(inserted.chars().count());
+                }
+                OpComponent::Delete(count) => {
+                    let deleted: String = chars[index..index + count].iter().collect();
+                    inverse.insert(&deleted);
+                    index += count;
+                }
+            }
+        }
+        Ok(inverse)
+    }
+
+    /// Merges `self` followed by `other` into a single operation.
+    pub fn compose(&self, other: &Self) -> crate::Result<Self> {
+        if self.target_len != other.base_len {
+            return Err(crate::Error::InvalidOperation(
+                "compose: target length of the first operation must match base length of the second".to_owned(),
+            ));
+        }
+        let mut result = Self::new();
+        let mut ops1 = self.ops.iter().cloned();
+        let mut ops2 = other.ops.iter().cloned();
+        let mut op1 = ops1.next();
+        let mut op2 = ops2.next();
+        loop {
+            match (op1.take(), op2.take()) {
+                (None, None) => break,
+                (Some(OpComponent::Delete(count)), second) => {
+                    result.delete(count);
+                    op1 = ops1.next();
+                    op2 = second;
+                }
+                (first, Some(OpComponent::Insert(text))) => {
+                    result.insert(&text);
+                    op1 = first;
+                    op2 = ops2.next();
+                }
+                (None, _) | (_, None) => {
+                    return Err(crate::Error::InvalidOperation("compose: operations have different lengths".to_owned()));
+                }
+                (Some(OpComponent::Retain(n1)), Some(OpComponent::Retain(n2))) => {
+                    let min = n1.min(n2);
+                    result.retain(min);
+                    op1 = advance(n1, min, OpComponent::Retain, &mut ops1);
+                    op2 = advance(n2, min, OpComponent::Retain, &mut ops2);
+                }
+                (Some(OpComponent::Insert(text)), Some(OpComponent::Delete(n2))) => {
+                    let len = text.chars().count();
+                    let min = len.min(n2);
+                    op1 = advance_insert(&text, min, &mut ops1);
+                    op2 = advance(n2, min, OpComponent::Delete, &mut ops2);
+                }
+                (Some(OpComponent::Insert(text)), Some(OpComponent::Retain(n2))) => {
+                    let len = text.chars().count();
+                    let min = len.min(n2);
+                    let kept: String = text.chars().take(min).collect();
+                    result.insert(&kept);
+                    op1 = advance_insert(&text, min, &mut ops1);
+                    op2 = advance(n2, min, OpComponent::Retain, &mut ops2);
+                }
+                (Some(OpComponent::Retain(n1)), Some(OpComponent::Delete(n2))) => {
+                    let min = n1.min(n2);
+                    result.delete(min);
+                    op1 = advance(n1, min, OpComponent::Retain, &mut ops1);
+                    op2 = advance(n2, min, OpComponent::Delete, &mut ops2);
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Transforms two concurrent operations `a` and `b` made on the same document
+    /// into `(a', b')` so that `b` then `a'` equals `a` then `b'`.
+    /// Inserts of `a` win ties at the same position.
+    pub fn transform(a: &Self, b: &Self) -> crate::Result<(Self, Self)> {
+        if a.base_len != b.base_len {
+            return Err(crate::Error::InvalidOperation("transform: both operations must have the same base length".to_owned()));
+        }
+        let mut a_prime = Self::new();
+        let mut b_prime = Self::new();
+        let mut ops1 = a.ops.iter().cloned();
+        let mut ops2 = b.ops.iter().cloned();
+        let mut op1 = ops1.next();
+        let mut op2 = ops2.next();
+        loop {
+            match (op1.take(), op2.take()) {
+                (None, None) => break,
+                (Some(OpComponent::Insert(text)), second) => {
+                    a_prime.insert(&text);
+                    b_prime.retain(text.chars().count());
+                    op1 = ops1.next();
+                    op2 = second;
+                }
+                (first, Some(OpComponent::Insert(text))) => {
+                    a_prime.retain(text.chars().count());
+                    b_prime.insert(&text);
+                    op1 = first;
+                    op2 = ops2.next();
+                }
+                (None, _) | (_, None) => {
+                    return Err(crate::Error::InvalidOperation("transform: operations have different lengths".to_owned()));
+                }
+                (Some(OpComponent::Retain(n1)), Some(OpComponent::Retain(n2))) => {
+                    let min = n1.min(n2);
+                    a_prime.retain(min);
+                    b_prime.retain(min);
+                    op1 = advance(n1, min, OpComponent::Retain, &mut ops1);
+                    op2 = advance(n2, min, OpComponent::Retain, &mut ops2);
+                }
+                (Some(OpComponent::Delete(n1)), Some(OpComponent::Delete(n2))) => {
+                    let min = n1.min(n2);
+                    op1 = advance(n1, min, OpComponent::Delete, &mut ops1);
+                    op2 = advance(n2, min, OpComponent::Delete, &mut ops2);
+                }
+                (Some(OpComponent::Delete(n1)), Some(OpComponent::Retain(n2))) => {
+                    let min = n1.min(n2);
+                    a_prime.delete(min);
+                    op1 = advance(n1, min, OpComponent::Delete, &mut ops1);
+                    op2 = advance(n2, min, OpComponent::Retain, &mut ops2);
+                }
+                (Some(OpComponent::Retain(n1)), Some(OpComponent::Delete(n2))) => {
+                    let min = n1.min(n2);
+                    b_prime.delete(min);
+                    op1 = advance(n1, min, OpComponent::Retain, &mut ops1);
+                    op2 = advance(n2, min, OpComponent::Delete, &mut ops2);
+                }
+            }
+        }
+        Ok((a_prime, b_prime))
+    }
+}
+
+fn advance(
+    count: usize,
+    consumed: usize,
+    make: fn(usize) -> OpComponent,
+    rest: &mut impl Iterator<Item = OpComponent>,
+) -> Option<OpComponent> {
+    if count > consumed {
+        Some(make(count - consumed))
+    } else {
+        rest.next()
+    }
+}
+
+fn advance_insert(
+    text: &str,
+    consumed: usize,
+    rest: &mut impl Iterator<Item = OpComponent>,
+) -> Option<OpComponent> {
+    if text.chars().count() > consumed {
+        Some(OpComponent::Insert(text.chars().skip(consumed).collect()))
+    } else {
+        rest.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OpComponent, TextOperation};
+
+    fn operation(old: &str, new: &str) -> TextOperation {
+        TextOperation::diff(old, new)
+    }
+
+    #[test]
+    fn apply_builds_the_target() {
+        let mut op = TextOperation::new();
+        op.retain(2).delete(1).insert("XY").retain(2);
+        assert_eq!(op.base_len(), 5);
+        assert_eq!(op.target_len(), 6);
+        assert_eq!(op.apply("abcde").unwrap(), "abXYde");
+    }
+
+    #[test]
+    fn apply_rejects_a_wrong_base() {
+        assert!(operation("abc", "abXc").apply("ab").is_err());
+    }
+
+    #[test]
+    fn invert_undoes() {
+        let op = operation("hello world", "hello, rust");
+        let inverse = op.invert("hello world").unwrap();
+        assert_eq!(inverse.apply("hello, rust").unwrap(), "hello world");
+    }
+
+    #[test]
+    fn compose_equals_applying_both() {
+        let text = "fn main() {}";
+        let first = operation(text, "fn main() { run(); }");
+        let second = operation("fn main() { run(); }", "pub fn main() { run(); }");
+        let composed = first.compose(&second).unwrap();
+        assert_eq!(composed.apply(text).unwrap(), "pub fn main() { run(); }");
+    }
+
+    #[test]
+    fn compose_rejects_mismatched_lengths() {
+        assert!(operation("ab", "abc").compose(&operation("ab", "a")).is_err());
+    }
+
+    #[test]
+    fn transform_converges() {
+        let text = "let x = 1;";
+        let cases = [
+            ("let x = 10;", "let y = 1;"),
+            ("let x = 1; // one", "// start\nlet x = 1;"),
+            ("let = 1;", "let x = 2;"),
+            ("", "let x = 1;\nlet y = 2;"),
+            ("let x = 1;", "let x = 1;"),
+        ];
+        for (left, right) in cases {
+            let a = operation(text, left);
+            let b = operation(text, right);
+            let (a_prime, b_prime) = TextOperation::transform(&a, &b).unwrap();
+            let via_a = b_prime.apply(&a.apply(text).unwrap()).unwrap();
+            let via_b = a_prime.apply(&b.apply(text).unwrap()).unwrap();
+            assert_eq!(via_a, via_b, "{:?} / {:?}", left, right);
+        }
+    }
+
+    #[test]
+    fn transform_keeps_both_inserts_at_one_place() {
+        let a = operation("ab", "aXb");
+        let b = operation("ab", "aYb");
+        let (a_prime, b_prime) = TextOperation::transform(&a, &b).unwrap();
+        let via_a = b_prime.apply("aXb").unwrap();
+        assert_eq!(via_a, a_prime.apply("aYb").unwrap());
+        assert!(via_a == "aXYb" || via_a == "aYXb");
+    }
+
+    #[test]
+    fn diff_trims_prefix_and_suffix() {
+        let op = operation("hello world", "hello brave world");
+        assert_eq!(op.ops(), [
+            OpComponent::Retain(6),
+            OpComponent::Insert("brave ".to_owned()),
+            OpComponent::Retain(5),
+        ]);
+    }
+
+    #[test]
+    fn diff_replaces_the_middle() {
+        let op = operation("abcdef", "abXYef");
+        assert_eq!(op.ops(), [
+            OpComponent::Retain(2),
+            OpComponent::Insert("XY".to_owned()),
+            OpComponent::Delete(2),
+            OpComponent::Retain(2),
+        ]);
+    }
+
+    #[test]
+    fn diff_of_equal_texts_is_noop() {
+        assert!(operation("same", "same").is_noop());
+        assert!(operation("", "").is_noop());
+    }
+
+    #[test]
+    fn diff_counts_chars_not_bytes() {
+        let op = operation("привет мир", "привет, мир");
+        assert_eq!(op.base_len(), 10);
+        assert_eq!(op.ops(), [
+            OpComponent::Retain(6),
+            OpComponent::Insert(",".to_owned()),
+            OpComponent::Retain(4),
+        ]);
+        assert_eq!(op.apply("привет мир").unwrap(), "привет, мир");
+    }
+
+    #[test]
+    fn diff_does_not_split_a_multibyte_char() {
+        // "é" and "ê" share their first byte
+        let op = operation("café", "cafê");
+        assert_eq!(op.ops(), [
+            OpComponent::Retain(3),
+            OpComponent::Insert("ê".to_owned()),
+            OpComponent::Delete(1),
+        ]);
+        assert_eq!(op.apply("café").unwrap(), "cafê");
+    }
+
+    #[test]
+    fn diff_of_a_repeated_char() {
+        let op = operation("aaa", "aaaa");
+        assert_eq!(op.apply("aaa").unwrap(), "aaaa");
+        assert_eq!(op.target_len() - op.base_len(), 1);
+    }
+}
@@ -1,11 +1,12 @@
-use std::{collections::BTreeMap, sync::Arc};
 use web_sys::{ VideoDecoder, VideoDecoderConfig, CodecState, HtmlVideoElement};
+use crate::constants::{VIDEO_FRAGMENT_SIZE, VIDEO_FRAGMENT_TIMEOUT_MS, VIDEO_JITTER_BUFFER_SIZE, VIDEO_JITTER_WAIT_MS, VIDEO_MAX_FRAME_BYTES};
 use crate::utils::{device::{ create_video_decoder_video, VideoElementKind, create_video_decoder_video_screen}, dom::remove_element};
-use super::packet::VideoPacket;
+use super::{bitrate::{ReceiverReport, ReceiverStats, VideoStream}, fragment::Reassembler, jitter_buffer::{Arrival, JitterBuffer, Release}, keyframe::KeyframeRequester, packet::{PacketData, VideoPacket}};
 
 #[derive(Clone, PartialEq)]
 pub struct Video {
-    pub cache: BTreeMap<u64, Arc<VideoPacket>>,
+    pub cache: JitterBuffer<VideoPacket>,
+    pub fragments: Reassembler,
     pub video_decoder: VideoDecoder,
     pub video_config: VideoDecoderConfig,
     pub on_video: bool,
@@ -17,6 +18,8 @@ pub struct Video {
     pub require_key: bool,
     pub video_element: HtmlVideoElement,
     pub is_screen: bool,
+    pub stats: ReceiverStats,
+    pub keyframes: KeyframeRequester,
 }
 
 impl Video {
@@ -29,7 +32,8 @@ impl Video {
         is_screen: bool,
     ) -> Self {
         Self {
-            cache: BTreeMap::new(),
+            cache: JitterBuffer::new(VIDEO_JITTER_BUFFER_SIZE, VIDEO_JITTER_WAIT_MS),
+            fragments: Reassembler::new(VIDEO_FRAGMENT_SIZE, VIDEO_MAX_FRAME_BYTES, VIDEO_FRAGMENT_TIMEOUT_MS),
             video_decoder,
             video_config,
             on_video: true,
@@ -41,72 +45,94 @@ impl Video {
             require_key: false,
             video_element,
             is_screen,
+            stats: ReceiverStats::new(if is_screen { VideoStream::Screen } else { VideoStream::Camera }),
+            keyframes: KeyframeRequester::default(),
         }
     }
 
-    pub fn decode_break(&mut self, packet: Arc<VideoPacket>) -> Result<(), anyhow::Error> {
-        let new_sequence_number = packet.sequence_number;
-        if packet.chunk_type == "key" {
-            self.require_key = false;
-            self.decode_packet(packet);
-            self.sequence = Some(new_sequence_number);
-        } else if let Some(sequence) =self.sequence {
-            if self.require_key {
-                return Ok(());
-            }
-            let is_next_frame = new_sequence_number == sequence + 1;
-            if is_next_frame {
-                self.decode_packet(packet);
-                self.sequence = Some(new_sequence_number);
-            }
+    /// What arrived since the last report, for the sender to adapt its
+    /// quality to. `None` until the report interval is over.
+    pub fn take_report(&mut self) -> Option<ReceiverReport> {
+        self.stats.set_decode_queue(self.video_decoder.decode_queue_size());
+        let mut report = self.stats.report(js_sys::Date::now())?;
+        let jitter = self.cache.take_stats();
+        report.reordered = jitter.reordered;
+        report.late = jitter.late;
+        report.incomplete = self.fragments.take_dropped();
+        Some(report)
+    }
+
+    /// The stream to ask the sender a key frame of, while the decoder cannot
+    /// go on without one.
+    pub fn take_key_request(&mut self) -> Option<VideoStream> {
+        let is_waiting = self.require_key || self.sequence.is_none();
+        if self.keyframes.should_request(is_waiting, js_sys::Date::now()) {
+            Some(self.stream())
+        } else {
+            None
         }
-        Ok(())
     }
 
-    pub fn decode_packet(&mut self, packet: Arc<VideoPacket>) {
-        let encoded_video_chunk = VideoPacket::get_encoded_video_chunk(packet);
-        match self.video_decoder.state() {
-            CodecState::Unconfigured => {
-                log::info!("video decoder unconfigured");
-            },
-            CodecState::Configured => {
-                let _ = self.video_decoder.decode(&encoded_video_chunk);
-            },
-            CodecState::Closed => {
-                log::error!("video decoder closed");
-                self.require_key = true;
-                
-                if self.is_screen {
-                    self.video_decoder = create_video_decoder_video_screen(self.render_id.clone(), self.element_kind.clone())
-                    .video_decoder;
-                } else {
-                    remove_element(self.render_id.clone());
-                    self.video_decoder = create_video_decoder_video(self.render_id.clone(), self.element_kind.clone())
-                    .video_decoder;
-                }
-                
-            },
-            _ => {},
+    fn stream(&self) -> VideoStream {
+        if self.is_screen {
+            VideoStream::Screen
+        } else {
+            VideoStream::Camera
         }
     }
 
-    pub fn decode_break_data(&mut self, packet: Arc<VideoPacket>) -> Result<(), anyhow::Error> {
-        let new_sequence_number = packet.sequence_number;
-        if packet.chunk_type == "key" {
-            self.require_key = false;
-            self.decode_packet_data(packet);
-            self.sequence = Some(new_sequence_number);
-        } else if let Some(sequence) = self.sequence {
-            if !self.require_key && new_sequence_number == sequence + 1 {
-                self.decode_packet_data(packet);
-                self.sequence = Some(new_sequence_number);
+    /// Puts the packet into the jitter buffer and decodes whatever comes out
+    /// of it in order.
+    pub fn decode_break(&mut self, packet: VideoPacket) -> Result<(), anyhow::Error> {
+        let now = js_sys::Date::now();
+        let Some(packet) = self.reassemble(packet, now) else {
+            return Ok(());
+        };
+        self.stats.on_packet(packet.data.len(), packet.timestamp, now);
+        let is_key = packet.chunk_type == "key";
+        match self.cache.push(packet.sequence_number, is_key, packet, now) {
+            Arrival::Held => {},
+            // a key frame given up on is what the decoder waits for, it is asked for again
+            Arrival::Late => self.require_key |= is_key,
+            Arrival::Restart => self.stats.on_restart(),
+        }
+        while let Some(release) = self.cache.pop(now) {
+            match release {
+                Release::Frame(packet) => {
+                    let new_sequence_number = packet.sequence_number;
+                    if packet.chunk_type == "key" {
+                        self.require_key = false;
+                    } else if self.require_key || self.sequence.is_none() {
+                        continue;
+                    }
+                    self.decode_packet(&packet);
+                    self.sequence = Some(new_sequence_number);
+                },
+                Release::Lost(frames) => {
+                    // the next ones cannot be decoded without a key frame
+                    self.stats.on_lost(frames);
+                    self.require_key = true;
+                },
             }
         }
         Ok(())
     }
 
-    pub fn decode_packet_data(&mut self, packet: Arc<VideoPacket>) {
-        let encoded_video_chunk = VideoPacket::get_encoded_video_chunk_from_data(packet);
+    /// The whole frame, once all its fragments are here.
+    fn reassemble(&mut self, packet: VideoPacket, now: f64) -> Option<VideoPacket> {
+        let Some(fragment) = packet.fragment else {
+            return Some(packet);
+        };
+        let data = self.fragments.push(packet.sequence_number, fragment, &packet.data, now)?;
+        Some(VideoPacket {
+            data: PacketData::Owned(data),
+            fragment: None,
+            ..packet
+        })
+    }
+
+    pub fn decode_packet(&mut self, packet: &VideoPacket) {
+        let encoded_video_chunk = VideoPacket::get_encoded_video_chunk(packet);
         match self.video_decoder.state() {
             CodecState::Unconfigured => {
                 log::info!("video decoder unconfigured");
@@ -131,5 +157,4 @@ impl Video {
             _ => {},
         }
     }
-
 }
\ No newline at end of file
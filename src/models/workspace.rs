@@ -0,0 +1,224 @@
+use serde::{Deserialize, Serialize};
+
+use crate::constants::DEFAULT_FILE_PATH;
+
+use super::commons::AreaKind;
+
+/// Identifies a synced buffer: a workspace file by its id, or the plain text area.
+#[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize, Debug)]
+pub enum DocumentKey {
+    File(String),
+    TextArea,
+}
+
+impl DocumentKey {
+    pub fn area_kind(&self) -> AreaKind {
+        match self {
+            DocumentKey::File(_) => AreaKind::Editor,
+            DocumentKey::TextArea => AreaKind::TextArea,
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct WorkspaceFile {
+    pub id: String,
+    pub path: String,
+    pub content: String,
+}
+
+impl WorkspaceFile {
+    pub fn new(path: String, content: String) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            path,
+            content,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+
+    pub fn depth(&self) -> usize {
+        self.path.matches('/').count()
+    }
+}
+
+/// Changes to the file list. Files are addressed by id so that renames
+/// don't break edits that are still in flight.
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub enum WorkspaceAction {
+    Create {
+        id: String,
+        path: String,
+    },
+    Rename {
+        id: String,
+        path: String,
+    },
+    Delete {
+        id: String,
+    },
+    Open {
+        id: String,
+    },
+    Close {
+        id: String,
+    },
+}
+
+impl WorkspaceAction {
+    pub fn create(path: String) -> Self {
+        Self::Create {
+            id: uuid::Uuid::new_v4().to_string(),
+            path,
+        }
+    }
+}
+
+/// Files of a lesson, the open tabs and the file shown in the editor.
+/// A workspace always has at least one file and one open tab.
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct Workspace {
+    pub files: Vec<WorkspaceFile>,
+    pub tabs: Vec<String>,
+    pub active: String,
+}
+
+impl Default for Workspace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Workspace {
+    pub fn new() -> Self {
+        Self::from_file(WorkspaceFile::new(DEFAULT_FILE_PATH.to_owned(), String::default()))
+    }
+
+    pub fn from_file(file: WorkspaceFile) -> Self {
+        Self {
+            tabs: vec![file.id.clone()],
+            active: file.id.clone(),
+            files: vec![file],
+        }
+    }
+
+    pub fn file(&self, id: &str) -> Option<&WorkspaceFile> {
+        self.files.iter().find(|file| file.id == id)
+    }
+
+    pub fn file_by_path(&self, path: &str) -> Option<&WorkspaceFile> {
+        self.files.iter().find(|file| file.path == path)
+    }
+
+    pub fn content_mut(&mut self, id: &str) -> Option<&mut String> {
+        self.files
+            .iter_mut()
+            .find(|file| file.id == id)
+            .map(|file| &mut file.content)
+    }
+
+    pub fn active_file(&self) -> Option<&WorkspaceFile> {
+        self.file(&self.active)
+    }
+
+    pub fn active_key(&self) -> DocumentKey {
+        DocumentKey::File(self.active.clone())
+    }
+
+    pub fn active_content(&self) -> &str {
+        self.active_file().map_or("", |file| file.content.as_str())
+    }
+
+    pub fn set_active_content(&mut self, content: String) {
+        let active = self.active.clone();
+        if let Some(current) = self.content_mut(&active) {
+            *current = content;
+        }
+    }
+
+    /// Files ordered by path, so that a folder is followed by its content.
+    pub fn sorted_files(&self) -> Vec<&WorkspaceFile> {
+        let mut files: Vec<&WorkspaceFile> = self.files.iter().collect();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        files
+    }
+
+    fn check_path(&self, id: &str, path: &str) -> crate::Result<()> {
+        if path.is_empty() || path.starts_with('/') || path.ends_with('/') || path.contains("//") {
+            return Err(crate::Error::InvalidWorkspace(format!("invalid file path '{}'", path)));
+        }
+        if self.files.iter().any(|file| file.path == path && file.id != id) {
+            return Err(crate::Error::InvalidWorkspace(format!("file '{}' already exists", path)));
+        }
+        Ok(())
+    }
+
+    fn check_file(&self, id: &str) -> crate::Result<()> {
+        self.file(id)
+            .map(|_| ())
+            .ok_or_else(|| crate::Error::InvalidWorkspace(format!("file with id '{}' not found", id)))
+    }
+
+    fn open(&mut self, id: &str) {
+        if !self.tabs.iter().any(|tab| tab == id) {
+            self.tabs.push(id.to_owned());
+        }
+        self.active = id.to_owned();
+    }
+
+    fn close(&mut self, id: &str) {
+        self.tabs.retain(|tab| tab != id);
+        if self.tabs.is_empty() {
+            if let Some(file) = self.files.first() {
+                self.tabs.push(file.id.clone());
+            }
+        }
+        if self.active == id || !self.tabs.contains(&self.active) {
+            self.active = self.tabs.last().cloned().unwrap_or_default();
+        }
+    }
+
+    pub fn apply(&mut self, action: &WorkspaceAction) -> crate::Result<()> {
+        match action {
+            WorkspaceAction::Create { id, path } => {
+                if self.file(id).is_some() {
+                    return Err(crate::Error::InvalidWorkspace(format!("file with id '{}' already exists", id)));
+                }
+                self.check_path(id, path)?;
+                self.files.push(WorkspaceFile {
+                    id: id.clone(),
+                    path: path.clone(),
+                    content: String::default(),
+                });
+                self.open(id);
+            }
+            WorkspaceAction::Rename { id, path } => {
+                self.check_file(id)?;
+                self.check_path(id, path)?;
+                if let Some(file) = self.files.iter_mut().find(|file| &file.id == id) {
+                    file.path = path.clone();
+                }
+            }
+            WorkspaceAction::Delete { id } => {
+                self.check_file(id)?;
+                if self.files.len() == 1 {
+                    return Err(crate::Error::InvalidWorkspace("cannot delete the last file".to_owned()));
+                }
+                self.files.retain(|file| &file.id != id);
+                self.close(id);
+            }
+            WorkspaceAction::Open { id } => {
+                self.check_file(id)?;
+                self.open(id);
+            }
+            WorkspaceAction::Close { id } => {
+                self.check_file(id)?;
+                self.close(id);
+            }
+        }
+        Ok(())
+    }
+}
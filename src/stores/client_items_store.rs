@@ -1,24 +1,28 @@
-use std::{collections::HashMap, rc::Rc};
+use std::{collections::{HashMap, HashSet}, rc::Rc};
 
 use wasm_bindgen::JsCast;
 use wasm_peers::UserId;
 use web_sys::{HtmlElement, MouseEvent};
 use yewdux::{store::{Store, Reducer}, prelude::Dispatch};
 
-use crate::{models::{client::ClientItem, commons::{AreaKind, InitUser}}, utils::dom::{remove_element, create_video_id}};
+use crate::{models::{assignment::{Assignment, StudentSubmissions, Submission, SubmissionStatus}, client::ClientItem, commons::{AreaKind, InitUser}, review::{ReviewAuthor, ReviewMessage, ReviewThread}, text_operation::TextOperation, workspace::{DocumentKey, Workspace, WorkspaceAction}}, utils::{dom::{download_file, remove_element, create_video_id}, inputs::Message}};
 
-use super::client_props_store::{ClientPropsStore, HostClientMsg};
+use super::{client_props_store::{ClientPropsStore, HostClientMsg}, host_store::{self, HostStore}};
 
 
 #[derive(Clone, PartialEq, Store)]
 pub struct ClientItemsStore {
     players: HashMap<UserId, ClientItem>,
+    assignment: Option<Assignment>,
+    submissions: HashMap<UserId, Vec<Submission>>,
 }
 
 impl Default for ClientItemsStore {
     fn default() -> Self {
         Self { 
             players: HashMap::new(),
+            assignment: None,
+            submissions: HashMap::new(),
         }
     }
 }
@@ -28,16 +32,60 @@ impl ClientItemsStore {
         self.players.clone()
     }
 
+    pub fn player_ids(&self) -> Vec<UserId> {
+        self.players.keys().copied().collect()
+    }
+
     pub fn get_mut_players(&mut self) -> &mut HashMap<UserId, ClientItem> {
         &mut self.players
     }
+
+    pub fn get_assignment(&self) -> Option<&Assignment> {
+        self.assignment.as_ref()
+    }
+
+    /// `None` while no assignment has been handed out.
+    pub fn get_status(&self, user_id: &UserId) -> Option<SubmissionStatus> {
+        let submissions = self.submissions
+            .get(user_id)
+            .map_or(&[][..], Vec::as_slice);
+        self.assignment
+            .as_ref()
+            .map(|assignment| SubmissionStatus::of(assignment, submissions))
+    }
+
+    fn export_submissions(&self) -> crate::Result<()> {
+        let user_ids: HashSet<&UserId> = self.submissions
+            .keys()
+            .chain(self.players.keys())
+            .collect();
+        let mut students: Vec<StudentSubmissions> = user_ids
+            .into_iter()
+            .map(|user_id| StudentSubmissions {
+                user_id: user_id.to_string(),
+                submissions: self.submissions.get(user_id).map_or(&[][..], Vec::as_slice),
+                review: self.players.get(user_id).map_or(&[][..], |client_item| client_item.review.threads.as_slice()),
+            })
+            .collect();
+        students.sort_by(|a, b| a.user_id.cmp(&b.user_id));
+        let json = serde_json::to_string_pretty(&students)
+            .map_err(|err| crate::Error::FailedToExport(err.to_string()))?;
+        download_file("submissions.json", &json, "application/json")
+    }
 }
 
 pub enum ClientItemMsg {
     AddClient(UserId),
     InitClient(UserId, InitUser),
-    SetTextAreaContent(UserId, String),
-    SetEditorContent(UserId, String),
+    ClientEdit(UserId, u64, TextOperation, DocumentKey),
+    HostEdit(UserId, DocumentKey, String),
+    ClientWorkspace(UserId, WorkspaceAction),
+    Distribute(Assignment),
+    Submit(UserId, String, Workspace),
+    ExportSubmissions,
+    HostReviewThread(UserId, ReviewThread),
+    HostReviewReply(UserId, String, ReviewMessage),
+    ClientReviewReply(UserId, String, ReviewMessage),
     ClientSwitchArea(UserId, AreaKind),
     ChooseItem(MouseEvent),
     SwitchSpeakers(String),
@@ -49,6 +97,7 @@ impl Reducer<ClientItemsStore> for ClientItemMsg {
     fn apply(self, mut store: Rc<ClientItemsStore>) -> Rc<ClientItemsStore> {
         let state = Rc::make_mut(&mut store);
         let client_area_dispatch = Dispatch::<ClientPropsStore>::new();
+        let global_dispatch = Dispatch::<HostStore>::new();
         match self {
             ClientItemMsg::AddClient(user_id) => {
                 state.players
@@ -57,25 +106,163 @@ impl Reducer<ClientItemsStore> for ClientItemMsg {
             ClientItemMsg::InitClient(user_id, init_user) => {
                 let client_item = state.players.get_mut(&user_id).unwrap();
                 client_item.set_area_kind(init_user.area_kind);
-                client_item.set_editor_content(init_user.editor_content);
+                client_item.set_workspace(init_user.workspace);
                 client_item.set_text_area_content(init_user.text_area_content);
+                client_item.reset_sync();
+                // a late student gets the current assignment, a reconnecting one keeps their work
+                let has_assignment = |assignment: &Assignment| {
+                    assignment.workspace.files
+                        .iter()
+                        .any(|file| client_item.workspace.file(&file.id).is_some())
+                };
+                if let Some(assignment) = state.assignment.as_ref().filter(|assignment| !has_assignment(assignment)) {
+                    client_item.replace_workspace(assignment.workspace.clone());
+                    let message = Message::HostAssignment { assignment: assignment.clone() };
+                    global_dispatch.apply(host_store::Msg::SendMessageToUser(user_id, message));
+                }
             }
-            ClientItemMsg::SetTextAreaContent(user_id, content) => {
+            ClientItemMsg::ClientEdit(user_id, revision, operation, document) => {
+                match state.players.get_mut(&user_id) {
+                    Some(client_item) if client_item.is_resyncing => {
+                        log::info!("edit from a resyncing client ignored, id: {}", user_id.to_string());
+                    },
+                    Some(client_item) => {
+                        client_item.set_area_kind(document.area_kind());
+                        match client_item.apply_client_operation(&document, revision, operation) {
+                            Ok(content) => {
+                                let message = Message::HostAckClient { document: document.clone() };
+                                global_dispatch.apply(host_store::Msg::SendMessageToUser(user_id, message));
+                                client_area_dispatch.apply(HostClientMsg::SetContent(user_id, document, content));
+                            },
+                            Err(err) => {
+                                log::error!("cannot apply client operation, id: {}, err: {}", user_id.to_string(), err);
+                                let message = Message::HostResync { message: client_item.start_resync() };
+                                global_dispatch.apply(host_store::Msg::SendMessageToUser(user_id, message));
+                            },
+                        }
+                    },
+                    None => {
+                        log::error!("cannot find client item, id: {}", user_id.to_string());
+                    },
+                }
+            },
+            ClientItemMsg::HostEdit(user_id, document, content) => {
+                match state.players.get_mut(&user_id) {
+                    Some(client_item) => {
+                        if let Some(operation) = client_item.apply_host_content(&document, content) {
+                            let message = Message::HostToClient {
+                                operation,
+                                document,
+                            };
+                            global_dispatch.apply(host_store::Msg::SendMessageToUser(user_id, message));
+                        }
+                    },
+                    None => {
+                        log::error!("cannot find client item, id: {}", user_id.to_string());
+                    },
+                }
+            },
+            ClientItemMsg::ClientWorkspace(user_id, action) => {
+                match state.players.get_mut(&user_id) {
+                    Some(client_item) => {
+                        match client_item.apply_workspace_action(&action) {
+                            Ok(()) => {
+                                client_area_dispatch.apply(HostClientMsg::ClientWorkspace(user_id, action));
+                            },
+                            Err(err) => {
+                                log::error!("cannot apply client workspace action, id: {}, err: {}", user_id.to_string(), err);
+                            },
+                        }
+                    },
+                    None => {
+                        log::error!("cannot find client item, id: {}", user_id.to_string());
+                    },
+                }
+            },
+            ClientItemMsg::Distribute(assignment) => {
+                state.players
+                    .values_mut()
+                    .for_each(|client_item| client_item.replace_workspace(assignment.workspace.clone()));
+                client_area_dispatch.apply(HostClientMsg::ReplaceWorkspace(assignment.workspace.clone()));
+                let message = Message::HostAssignment { assignment: assignment.clone() };
+                global_dispatch.apply(host_store::Msg::SendMessage(message));
+                state.assignment = Some(assignment);
+            },
+            ClientItemMsg::Submit(user_id, assignment_id, workspace) => {
+                match &state.assignment {
+                    Some(assignment) if assignment.id == assignment_id => {
+                        let submission = Submission {
+                            assignment_id: assignment_id.clone(),
+                            title: assignment.title.clone(),
+                            workspace,
+                            submitted_at: js_sys::Date::now(),
+                        };
+                        let message = Message::HostSubmissionAccepted {
+                            assignment_id,
+                            submitted_at: submission.submitted_at,
+                        };
+                        state.submissions
+                            .entry(user_id)
+                            .or_default()
+                            .push(submission);
+                        global_dispatch.apply(host_store::Msg::SendMessageToUser(user_id, message));
+                    },
+                    _ => {
+                        log::error!("submission for unknown assignment, id: {}", user_id.to_string());
+                    },
+                }
+            },
+            ClientItemMsg::ExportSubmissions => {
+                if let Err(err) = state.export_submissions() {
+                    log::error!("cannot export submissions: {}", err);
+                }
+            },
+            ClientItemMsg::HostReviewThread(user_id, thread) => {
+                match state.players.get_mut(&user_id) {
+                    Some(client_item) => {
+                        client_item.add_review_thread(thread.clone());
+                        let message = Message::HostReviewThread { thread };
+                        global_dispatch.apply(host_store::Msg::SendMessageToUser(user_id, message));
+                    },
+                    None => {
+                        log::error!("cannot find client item, id: {}", user_id.to_string());
+                    },
+                }
+            },
+            ClientItemMsg::HostReviewReply(user_id, thread_id, message) => {
                 match state.players.get_mut(&user_id) {
                     Some(client_item) => {
-                        client_item.set_area_kind(AreaKind::TextArea);
-                        client_item.set_text_area_content(content);                      
+                        match client_item.review_reply(&thread_id, message.clone()) {
+                            Ok(()) => {
+                                let message = Message::HostReviewReply { thread_id, message };
+                                global_dispatch.apply(host_store::Msg::SendMessageToUser(user_id, message));
+                            },
+                            Err(err) => {
+                                log::error!("cannot reply to review, id: {}, err: {}", user_id.to_string(), err);
+                            },
+                        }
                     },
                     None => {
                         log::error!("cannot find client item, id: {}", user_id.to_string());
                     },
                 }
             },
-            ClientItemMsg::SetEditorContent(user_id, content) => {
+            ClientItemMsg::ClientReviewReply(user_id, thread_id, message) => {
+                // the author comes from the connection, not from what the student sent
+                let message = ReviewMessage {
+                    author: ReviewAuthor::Student,
+                    ..message
+                };
                 match state.players.get_mut(&user_id) {
                     Some(client_item) => {
-                        client_item.set_area_kind(AreaKind::Editor);
-                        client_item.set_editor_content(content);                      
+                        match client_item.review_reply(&thread_id, message.clone()) {
+                            Ok(()) => {
+                                client_area_dispatch.apply(HostClientMsg::ClientReviewReply(user_id, thread_id, message));
+                            },
+                            Err(err) => {
+                                log::error!("cannot add client reply, id: {}, err: {}", user_id.to_string(), err);
+                            },
+                        }
                     },
                     None => {
                         log::error!("cannot find client item, id: {}", user_id.to_string());
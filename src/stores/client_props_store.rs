@@ -5,7 +5,7 @@ use wasm_peers::UserId;
 use web_sys::{InputEvent, HtmlTextAreaElement};
 use yewdux::{store::{Store, Reducer}, prelude::Dispatch};
 
-use crate::{models::{client::{ClientProps, ClientItem}, commons::{AreaKind, InitUser}}, stores::{client_store::{ClientStore, ClientMsg}, host_store::{HostStore, self}, client_items_store::{ClientItemsStore, ClientItemMsg}}, utils::inputs::{ClientMessage, Message}};
+use crate::{constants::{CLIENT_CURSOR_NAME, HOST_CURSOR_NAME}, models::{assignment::Assignment, client::{ClientProps, ClientItem}, commons::{AreaKind, InitUser}, history::HistoryAction, presence::{EditorCursor, RemoteCursor}, review::{ReviewAuthor, ReviewMessage, ReviewThread}, text_operation::TextOperation, workspace::{DocumentKey, Workspace, WorkspaceAction}}, stores::{client_store::{ClientStore, ClientMsg}, host_store::{self, HostStore}, client_items_store::{ClientItemsStore, ClientItemMsg}}, utils::inputs::{ClientMessage, Message}};
 
 
 #[derive(Clone, PartialEq, Store)]
@@ -35,10 +35,58 @@ pub enum ClientPropsMsg {
     SwitchArea(AreaKind),
     UpdateClientValue(String),
     UpdateClientTextArea(InputEvent),
+    History(DocumentKey, HistoryAction),
     HostToClient {
-        message: String,
-        area_kind: AreaKind,
+        operation: TextOperation,
+        document: DocumentKey,
     },
+    HostAck(DocumentKey),
+    Resync(InitUser),
+    Workspace(WorkspaceAction),
+    SetAssignment(Assignment),
+    SetLocked(bool),
+    Submit,
+    SubmissionAccepted(String, f64),
+    UpdateCursor(EditorCursor),
+    SetRemoteCursor(Option<RemoteCursor>),
+    AddReviewThread(ReviewThread),
+    HostReviewReply(String, ReviewMessage),
+    ReviewReply(String, String),
+}
+
+// The host starts over from what it gets here, so the sync starts over too.
+fn send_state_to_host(props: &mut ClientProps) {
+    let init_user = InitUser {
+        workspace: props.workspace.clone(),
+        text_area_content: props.client_text_area.content.clone(),
+        area_kind: props.client_area_kind,
+        is_communication: false
+    };
+    props.reset_sync();
+    let message = ClientMessage::InitClient { message: init_user };
+    Dispatch::<ClientStore>::new().apply(ClientMsg::SendMessage(message));
+}
+
+fn send_local_content(props: &mut ClientProps, document: DocumentKey, content: String) {
+    let result = props.apply_local_content(&document, content);
+    send_local_result(document, result);
+}
+
+fn send_local_result(document: DocumentKey, result: crate::Result<Option<(u64, TextOperation)>>) {
+    match result {
+        Ok(Some((revision, operation))) => {
+            let message = ClientMessage::ClientToClient {
+                revision,
+                operation,
+                document,
+            };
+            Dispatch::<ClientStore>::new().apply(ClientMsg::SendMessage(message));
+        }
+        Ok(None) => {}
+        Err(err) => {
+            log::error!("cannot apply local edit: {}", err);
+        }
+    }
 }
 
 
@@ -48,17 +96,7 @@ impl Reducer<ClientPropsStore> for ClientPropsMsg {
         let global_dispatch = Dispatch::<ClientStore>::new();
         match self {
             ClientPropsMsg::SendStateToHost => {
-                let editor_content = state.get_client_props().client_editor_content.clone();
-                let text_area_content = state.get_client_props().client_text_area.content.clone();
-                let area_kind = state.get_client_props().client_area_kind;
-                let init_user = InitUser {
-                    editor_content,
-                    text_area_content,
-                    area_kind: area_kind.clone(),
-                    is_communication: false
-                };
-                let message = ClientMessage::InitClient { message: init_user };
-                global_dispatch.apply(ClientMsg::SendMessage(message));
+                send_state_to_host(state.get_mut_client_props());
             }
             ClientPropsMsg::SwitchArea(area_kind) => {
                 state.get_mut_client_props().set_area_kind(area_kind);
@@ -66,14 +104,9 @@ impl Reducer<ClientPropsStore> for ClientPropsMsg {
                 global_dispatch.apply(ClientMsg::SendMessage(message));
             },
             ClientPropsMsg::UpdateClientValue(content) => {
-                state.get_mut_client_props().set_editor_content(content.clone());
                 state.get_mut_client_props().set_is_write(false);
-
-                let message = ClientMessage::ClientToClient {
-                             message: content,
-                             area_kind: state.get_client_props().client_area_kind
-                };
-                global_dispatch.apply(ClientMsg::SendMessage(message)); 
+                let document = state.get_client_props().workspace.active_key();
+                send_local_content(state.get_mut_client_props(), document, content);
             },
             ClientPropsMsg::UpdateClientTextArea(event) => {
                  let content = event
@@ -81,24 +114,111 @@ impl Reducer<ClientPropsStore> for ClientPropsMsg {
                     .unwrap()
                     .unchecked_into::<HtmlTextAreaElement>()
                     .value();
-                state.get_mut_client_props().set_text_area_content(content.clone());
-                let message = ClientMessage::ClientToClient {
-                             message: content,
-                             area_kind: state.get_client_props().client_area_kind
-                };
-                global_dispatch.apply(ClientMsg::SendMessage(message));
+                send_local_content(state.get_mut_client_props(), DocumentKey::TextArea, content);
+            },
+            ClientPropsMsg::History(document, action) => {
+                // the host's lock covers undo as well
+                if state.get_client_props().client_text_area.is_disabled {
+                    return store;
+                }
+                let result = state.get_mut_client_props().apply_local_history(&document, action);
+                send_local_result(document, result);
+                state.get_mut_client_props().set_is_write(true);
             },
             ClientPropsMsg::HostToClient {
-                message,
-                area_kind
+                operation,
+                document
             } => {
-                match area_kind {
-                    AreaKind::Editor => {
-                        state.get_mut_client_props().set_editor_content(message);
-                        state.get_mut_client_props().set_is_write(true);
+                match state.get_mut_client_props().apply_host_operation(&document, operation) {
+                    Ok(()) => {
+                        if document == state.get_client_props().workspace.active_key() {
+                            state.get_mut_client_props().set_is_write(true);
+                        }
+                    },
+                    Err(err) => {
+                        log::error!("cannot apply host operation: {}", err);
+                    },
+                }
+            },
+            ClientPropsMsg::HostAck(document) => {
+                if let Some((revision, operation)) = state.get_mut_client_props().host_ack(&document) {
+                    let message = ClientMessage::ClientToClient {
+                        revision,
+                        operation,
+                        document,
+                    };
+                    global_dispatch.apply(ClientMsg::SendMessage(message));
+                }
+            },
+            ClientPropsMsg::Resync(init_user) => {
+                let props = state.get_mut_client_props();
+                props.set_workspace(init_user.workspace);
+                props.set_text_area_content(init_user.text_area_content);
+                props.set_is_write(true);
+                send_state_to_host(props);
+            },
+            ClientPropsMsg::SetLocked(is_locked) => {
+                state.get_mut_client_props().client_text_area.set_disabled(is_locked);
+            },
+            ClientPropsMsg::Workspace(action) => {
+                match state.get_mut_client_props().apply_workspace_action(&action) {
+                    Ok(()) => {
+                        let message = ClientMessage::ClientWorkspace { action };
+                        global_dispatch.apply(ClientMsg::SendMessage(message));
+                    },
+                    Err(err) => {
+                        log::error!("cannot apply workspace action: {}", err);
+                    },
+                }
+            },
+            ClientPropsMsg::SetAssignment(assignment) => {
+                state.get_mut_client_props().set_assignment(assignment);
+                state.get_mut_client_props().set_is_write(true);
+            },
+            ClientPropsMsg::Submit => {
+                if let Some(assignment) = &state.get_client_props().assignment {
+                    let message = ClientMessage::ClientSubmit {
+                        assignment_id: assignment.id.clone(),
+                        workspace: state.get_client_props().workspace.clone(),
+                    };
+                    global_dispatch.apply(ClientMsg::SendMessage(message));
+                }
+            },
+            ClientPropsMsg::SubmissionAccepted(assignment_id, submitted_at) => {
+                let is_current = state.get_client_props().assignment
+                    .as_ref()
+                    .is_some_and(|assignment| assignment.id == assignment_id);
+                if is_current {
+                    state.get_mut_client_props().set_submitted_at(submitted_at);
+                }
+            },
+            ClientPropsMsg::UpdateCursor(cursor) => {
+                let file_id = state.get_client_props().workspace.active.clone();
+                let message = ClientMessage::ClientCursor {
+                    message: RemoteCursor::new(CLIENT_CURSOR_NAME, file_id, cursor),
+                };
+                global_dispatch.apply(ClientMsg::SendMessage(message));
+            },
+            ClientPropsMsg::SetRemoteCursor(remote_cursor) => {
+                state.get_mut_client_props().set_remote_cursor(remote_cursor);
+            },
+            ClientPropsMsg::AddReviewThread(thread) => {
+                state.get_mut_client_props().review.add(thread);
+            },
+            ClientPropsMsg::HostReviewReply(thread_id, message) => {
+                if let Err(err) = state.get_mut_client_props().review.reply(&thread_id, message) {
+                    log::error!("cannot add host reply: {}", err);
+                }
+            },
+            ClientPropsMsg::ReviewReply(thread_id, text) => {
+                let message = ReviewMessage::new(ReviewAuthor::Student, text);
+                match state.get_mut_client_props().review.reply(&thread_id, message.clone()) {
+                    Ok(()) => {
+                        let message = ClientMessage::ClientReviewReply { thread_id, message };
+                        global_dispatch.apply(ClientMsg::SendMessage(message));
                     },
-                    AreaKind::TextArea => {
-                        state.get_mut_client_props().set_text_area_content(message);
+                    Err(err) => {
+                        log::error!("cannot reply to review: {}", err);
                     },
                 }
             },
@@ -109,11 +229,19 @@ impl Reducer<ClientPropsStore> for ClientPropsMsg {
 }
 
 pub enum HostClientMsg {
-    SetTextAreaContent(UserId, String),
-    SetEditorContent(UserId, String),
+    SetContent(UserId, DocumentKey, String),
     ClientSwitchArea(UserId, AreaKind),
+    ClientWorkspace(UserId, WorkspaceAction),
     HostClientToClient(String),
+    History(DocumentKey, HistoryAction),
+    OpenFile(String),
+    ReplaceWorkspace(Workspace),
     SetFromChoosedItem(String, ClientItem),
+    SetRemoteCursor(UserId, RemoteCursor),
+    SendCursor(EditorCursor),
+    AddReviewThread(ReviewThread),
+    ReviewReply(String, String),
+    ClientReviewReply(UserId, String, ReviewMessage),
 }
 
 impl Reducer<ClientPropsStore> for HostClientMsg {
@@ -122,17 +250,10 @@ impl Reducer<ClientPropsStore> for HostClientMsg {
         let global_dispatch = Dispatch::<HostStore>::new();
         let client_item_dispatch = Dispatch::<ClientItemsStore>::new();
         match self {
-            HostClientMsg::SetTextAreaContent(
-                user_id,
-                message,
-            ) => {
-                if state.get_client_props().client_id == user_id.to_string() {
-                    state.get_mut_client_props().set_text_area_content(message);
-                }
-            }
-            HostClientMsg::SetEditorContent(user_id, content) => {
-                if state.get_client_props().client_id == user_id.to_string() {
-                    state.get_mut_client_props().set_editor_content(content);
+            HostClientMsg::SetContent(user_id, document, content) => {
+                if state.get_client_props().client_id == user_id.to_string()
+                    && state.get_mut_client_props().set_document_content(&document, content)
+                    && document == state.get_client_props().workspace.active_key() {
                     state.get_mut_client_props().is_write = true;
                 }
             },
@@ -141,37 +262,120 @@ impl Reducer<ClientPropsStore> for HostClientMsg {
                     state.get_mut_client_props().set_area_kind(area_kind);
                 }
             }
+            HostClientMsg::ClientWorkspace(user_id, action) => {
+                if state.get_client_props().client_id == user_id.to_string() {
+                    if let Err(err) = state.get_mut_client_props().apply_workspace_action(&action) {
+                        log::error!("cannot apply client workspace action, id: {}, err: {}", user_id.to_string(), err);
+                    }
+                }
+            }
             HostClientMsg::HostClientToClient(content) => {
                 let client_id = state.get_client_props().client_id.clone();
                 if !client_id.is_empty() {
                     let user_id: UserId = UserId::new(client_id.parse::<u64>().unwrap());
-                    let area_kind = state.get_client_props().client_area_kind;
-                    match area_kind {
-                        AreaKind::Editor => {
-                            client_item_dispatch.apply(ClientItemMsg::SetEditorContent(user_id, content.clone()));
-                            state.get_mut_client_props().set_editor_content(content.clone());
+                    let document = match state.get_client_props().client_area_kind {
+                        AreaKind::Editor => state.get_client_props().workspace.active_key(),
+                        AreaKind::TextArea => DocumentKey::TextArea,
+                    };
+                    if let Err(err) = state.get_mut_client_props().set_own_document_content(&document, content.clone()) {
+                        log::error!("cannot apply host edit: {}", err);
+                    }
+                    client_item_dispatch.apply(ClientItemMsg::HostEdit(user_id, document, content));
+                    state.get_mut_client_props().set_is_write(false);
+                }
+            }
+            HostClientMsg::History(document, action) => {
+                let client_id = state.get_client_props().client_id.clone();
+                if !client_id.is_empty() {
+                    let user_id: UserId = UserId::new(client_id.parse::<u64>().unwrap());
+                    match state.get_mut_client_props().apply_own_history(&document, action) {
+                        Ok(Some(content)) => {
+                            client_item_dispatch.apply(ClientItemMsg::HostEdit(user_id, document, content));
+                            state.get_mut_client_props().set_is_write(true);
                         },
-                        AreaKind::TextArea => {
-                            client_item_dispatch.apply(ClientItemMsg::SetTextAreaContent(user_id, content.clone()));
-                            state.get_mut_client_props().set_text_area_content(content.clone());
+                        Ok(None) => {},
+                        Err(err) => {
+                            log::error!("cannot apply host history: {}", err);
                         },
                     }
-                                                            
-                    let message = Message::HostToClient {
-                        message: content,
-                        area_kind,
-                    };
-                    global_dispatch.apply(host_store::Msg::SendMessageToUser(user_id, message));
-                    state.get_mut_client_props().set_is_write(false);
+                }
+            }
+            HostClientMsg::OpenFile(file_id) => {
+                // only the host's view changes, the student keeps their own tab
+                let action = WorkspaceAction::Open { id: file_id };
+                if let Err(err) = state.get_mut_client_props().workspace.apply(&action) {
+                    log::error!("cannot open client file: {}", err);
+                }
+            }
+            HostClientMsg::ReplaceWorkspace(workspace) => {
+                if !state.get_client_props().client_id.is_empty() {
+                    state.get_mut_client_props().replace_workspace(workspace);
+                    state.get_mut_client_props().set_is_write(true);
                 }
             }
             HostClientMsg::SetFromChoosedItem(client_id, client_item) => {
+                let previous_id = state.get_client_props().client_id.clone();
+                if !previous_id.is_empty() && previous_id != client_id {
+                    let user_id: UserId = UserId::new(previous_id.parse::<u64>().unwrap());
+                    let message = Message::HostCursor { message: None };
+                    global_dispatch.apply(host_store::Msg::SendMessageToUser(user_id, message));
+                }
+                state.get_mut_client_props().set_remote_cursor(None);
                 state.get_mut_client_props().set_client_id(client_id.clone());
                 state.get_mut_client_props().set_area_kind(client_item.area_kind);
-                state.get_mut_client_props().set_editor_content(client_item.editor_content);
+                state.get_mut_client_props().set_workspace(client_item.workspace);
                 state.get_mut_client_props().set_text_area_content(client_item.text_area_content);
+                state.get_mut_client_props().set_review(client_item.review);
                 state.get_mut_client_props().is_write = true;
             }
+            HostClientMsg::SetRemoteCursor(user_id, mut remote_cursor) => {
+                if state.get_client_props().client_id == user_id.to_string() {
+                    // named here, the host tells students apart by their id
+                    remote_cursor.name = format!("{} {}", CLIENT_CURSOR_NAME, user_id);
+                    state.get_mut_client_props().set_remote_cursor(Some(remote_cursor));
+                }
+            }
+            HostClientMsg::SendCursor(cursor) => {
+                let client_id = state.get_client_props().client_id.clone();
+                if !client_id.is_empty() {
+                    let user_id: UserId = UserId::new(client_id.parse::<u64>().unwrap());
+                    let file_id = state.get_client_props().workspace.active.clone();
+                    let message = Message::HostCursor {
+                        message: Some(RemoteCursor::new(HOST_CURSOR_NAME, file_id, cursor)),
+                    };
+                    global_dispatch.apply(host_store::Msg::SendMessageToUser(user_id, message));
+                }
+            }
+            HostClientMsg::AddReviewThread(thread) => {
+                let client_id = state.get_client_props().client_id.clone();
+                if !client_id.is_empty() {
+                    let user_id: UserId = UserId::new(client_id.parse::<u64>().unwrap());
+                    state.get_mut_client_props().review.add(thread.clone());
+                    client_item_dispatch.apply(ClientItemMsg::HostReviewThread(user_id, thread));
+                }
+            }
+            HostClientMsg::ReviewReply(thread_id, text) => {
+                let client_id = state.get_client_props().client_id.clone();
+                if !client_id.is_empty() {
+                    let user_id: UserId = UserId::new(client_id.parse::<u64>().unwrap());
+                    let message = ReviewMessage::new(ReviewAuthor::Host, text);
+                    match state.get_mut_client_props().review.reply(&thread_id, message.clone()) {
+                        Ok(()) => {
+                            client_item_dispatch.apply(ClientItemMsg::HostReviewReply(user_id, thread_id, message));
+                        },
+                        Err(err) => {
+                            log::error!("cannot reply to review: {}", err);
+                        },
+                    }
+                }
+            }
+            HostClientMsg::ClientReviewReply(user_id, thread_id, message) => {
+                if state.get_client_props().client_id == user_id.to_string() {
+                    if let Err(err) = state.get_mut_client_props().review.reply(&thread_id, message) {
+                        log::error!("cannot add client reply, id: {}, err: {}", user_id.to_string(), err);
+                    }
+                }
+            }
         }
         store
     }
@@ -1,10 +1,10 @@
 use std::{rc::Rc, cell::RefCell};
-use wasm_peers::{SessionId, one_to_many::MiniClient, many_to_many::NetworkManager};
+use wasm_peers::{SessionId, UserId, one_to_many::MiniClient, many_to_many::NetworkManager};
 use yewdux::{store::{Store, Reducer}, prelude::Dispatch};
 
-use crate::{components::multi::client::client_manager::ClientManager, models::{audio::Audio, commons::{AreaKind, InitUser}}, utils::{inputs::{ClientMessage, ManyMassage, PaintAction}, dom::{on_visible_el, switch_visible_el}}};
+use crate::{components::multi::client::client_manager::ClientManager, models::{assignment::Assignment, audio::Audio, bitrate::{ReceiverReport, VideoStream}, board::{Board, BoardOperation, PageAction, Whiteboard}, laser::LaserUpdate, commons::{AreaKind, InitUser, LockMode}, editor_settings::EditorSettings, presence::RemoteCursor, review::{ReviewMessage, ReviewThread}, text_operation::TextOperation, workspace::{DocumentKey, WorkspaceAction}}, utils::{inputs::{ClientMessage, ManyMassage}, dom::{on_visible_el, switch_visible_el}}};
 
-use super::{client_props_store::{ClientPropsStore, ClientPropsMsg}, host_props_store::{HostPropsStore, ClientHostPropsMsg}, media_store::{MediaStore, ClientMediaMsg}};
+use super::{client_props_store::{ClientPropsStore, ClientPropsMsg}, host_props_store::{HostPropsStore, ClientHostPropsMsg}, laser_store::{LaserMsg, LaserStore}, media_store::{MediaStore, ClientMediaMsg}};
 
 #[derive(Clone, PartialEq, Store)]
 pub struct ClientStore {
@@ -59,24 +59,51 @@ pub enum ClientMsg {
     SendStateToHost,
     SendMessage(ClientMessage),
     SendManyMessage(ManyMassage),
+    SendManyMessageToUser(UserId, ManyMassage),
     // Client manager action
     HostToHost {
-        message: String,
-        area_kind: AreaKind,
+        operation: TextOperation,
+        document: DocumentKey,
     },
     HostToClient {
-        message: String,
-        area_kind: AreaKind,
+        operation: TextOperation,
+        document: DocumentKey,
     },
-    InitHostAra(InitUser),
+    HostAckClient {
+        document: DocumentKey,
+    },
+    HostResync(InitUser),
+    HostWorkspace {
+        action: WorkspaceAction,
+    },
+    HostLock(LockMode),
+    HostScroll(u32),
+    HostAssignment(Assignment),
+    HostSubmissionAccepted {
+        assignment_id: String,
+        submitted_at: f64,
+    },
+    HostCursor {
+        message: Option<RemoteCursor>,
+    },
+    HostBookmark(String),
+    HostReviewThread(ReviewThread),
+    HostReviewReply(String, ReviewMessage),
+    InitHostAra(InitUser, EditorSettings),
+    HostEditorSettings(EditorSettings),
     HostIsScreenShare(bool),
     HostSwitchArea(AreaKind),
-    OpenPaint,
-    HostPaint {
-        offset_x: f64,
-        offset_y: f64,
-        action: PaintAction,
-    },
+    OpenPaint(Whiteboard),
+    HostPaint(String, BoardOperation),
+    HostBoardPage(PageAction),
+    HostBoardAccess(Option<String>),
+    HostAnnotations(Option<Board>),
+    HostAnnotation(BoardOperation),
+    HostLaser(LaserUpdate),
+    HostVideoReport(ReceiverReport),
+    PeerVideoReport(UserId, ReceiverReport),
+    KeyframeRequest(VideoStream),
+    ClosePaint,
     OnCummunication {
         message: bool
     }
@@ -102,8 +129,8 @@ impl Reducer<ClientStore> for ClientMsg {
                 let on_action = move |msg: ClientMsg| {
                     dispatch.apply(msg);
                 };
-                state.get_client_manager().unwrap().borrow_mut().init(on_action);
-                state.get_client_manager().unwrap().borrow_mut().many_init();
+                state.get_client_manager().unwrap().borrow_mut().init(on_action.clone());
+                state.get_client_manager().unwrap().borrow_mut().many_init(on_action);
             }
             ClientMsg::SendStateToHost => {
                 client_props_dispatch.apply(ClientPropsMsg::SendStateToHost);
@@ -114,21 +141,60 @@ impl Reducer<ClientStore> for ClientMsg {
             ClientMsg::SendManyMessage(message) => {
                 let _ = state.get_many_network_manager().send_message_to_all(&message);
             }
+            ClientMsg::SendManyMessageToUser(user_id, message) => {
+                let _ = state.get_many_network_manager().send_message(user_id, &message);
+            }
             // Client manager action
             ClientMsg::HostToHost { 
-                message,
-                area_kind, 
+                operation,
+                document, 
             } => {
-                host_props_dispatch.apply(ClientHostPropsMsg::HostToHost { message, area_kind })
+                host_props_dispatch.apply(ClientHostPropsMsg::HostToHost { operation, document })
             }
             ClientMsg::HostToClient {
-                message,
-                area_kind
+                operation,
+                document
             } => {
-                client_props_dispatch.apply(ClientPropsMsg::HostToClient { message, area_kind })
+                client_props_dispatch.apply(ClientPropsMsg::HostToClient { operation, document })
+            },
+            ClientMsg::HostAckClient { document } => {
+                client_props_dispatch.apply(ClientPropsMsg::HostAck(document))
+            },
+            ClientMsg::HostResync(init_user) => {
+                client_props_dispatch.apply(ClientPropsMsg::Resync(init_user))
+            },
+            ClientMsg::HostWorkspace { action } => {
+                host_props_dispatch.apply(ClientHostPropsMsg::HostWorkspace(action))
+            },
+            ClientMsg::HostLock(mode) => {
+                host_props_dispatch.apply(ClientHostPropsMsg::HostLock(mode))
+            },
+            ClientMsg::HostScroll(line) => {
+                host_props_dispatch.apply(ClientHostPropsMsg::HostScroll(line))
+            },
+            ClientMsg::HostAssignment(assignment) => {
+                client_props_dispatch.apply(ClientPropsMsg::SetAssignment(assignment))
+            },
+            ClientMsg::HostSubmissionAccepted { assignment_id, submitted_at } => {
+                client_props_dispatch.apply(ClientPropsMsg::SubmissionAccepted(assignment_id, submitted_at))
+            },
+            ClientMsg::HostCursor { message } => {
+                client_props_dispatch.apply(ClientPropsMsg::SetRemoteCursor(message))
             },
-            ClientMsg::InitHostAra(user) => {
-                host_props_dispatch.apply(ClientHostPropsMsg::InitHost(user));
+            ClientMsg::HostBookmark(title) => {
+                host_props_dispatch.apply(ClientHostPropsMsg::HostBookmark(title))
+            },
+            ClientMsg::HostReviewThread(thread) => {
+                client_props_dispatch.apply(ClientPropsMsg::AddReviewThread(thread))
+            },
+            ClientMsg::HostReviewReply(thread_id, message) => {
+                client_props_dispatch.apply(ClientPropsMsg::HostReviewReply(thread_id, message))
+            },
+            ClientMsg::InitHostAra(user, settings) => {
+                host_props_dispatch.apply(ClientHostPropsMsg::InitHost(user, settings));
+            }
+            ClientMsg::HostEditorSettings(settings) => {
+                host_props_dispatch.apply(ClientHostPropsMsg::SetEditorSettings(settings));
             }
             ClientMsg::HostIsScreenShare(is_share) => {
                 on_visible_el(is_share, "container", "shcreen_container");
@@ -137,15 +203,38 @@ impl Reducer<ClientStore> for ClientMsg {
             ClientMsg::HostSwitchArea(area_kind) => {
                 host_props_dispatch.apply(ClientHostPropsMsg::HostSwitchArea(area_kind));
             }
-            ClientMsg::OpenPaint => {
-                host_props_dispatch.apply(ClientHostPropsMsg::OpenPaint);
+            ClientMsg::OpenPaint(board) => {
+                host_props_dispatch.apply(ClientHostPropsMsg::OpenPaint(board));
             }
-            ClientMsg::HostPaint { 
-                offset_x,
-                offset_y,
-                action
-            } => {
-                host_props_dispatch.apply(ClientHostPropsMsg::HostPaint { offset_x, offset_y, action })
+            ClientMsg::HostPaint(page_id, operation) => {
+                host_props_dispatch.apply(ClientHostPropsMsg::HostPaint(page_id, operation));
+            }
+            ClientMsg::HostBoardPage(action) => {
+                host_props_dispatch.apply(ClientHostPropsMsg::HostBoardPage(action));
+            }
+            ClientMsg::HostBoardAccess(author) => {
+                host_props_dispatch.apply(ClientHostPropsMsg::HostBoardAccess(author));
+            }
+            ClientMsg::HostAnnotations(board) => {
+                host_props_dispatch.apply(ClientHostPropsMsg::HostAnnotations(board));
+            }
+            ClientMsg::HostAnnotation(operation) => {
+                host_props_dispatch.apply(ClientHostPropsMsg::HostAnnotation(operation));
+            }
+            ClientMsg::HostLaser(update) => {
+                Dispatch::<LaserStore>::new().apply(LaserMsg::Host(update));
+            }
+            ClientMsg::HostVideoReport(report) => {
+                media_dispatch.apply(ClientMediaMsg::VideoReport(None, report));
+            }
+            ClientMsg::PeerVideoReport(user_id, report) => {
+                media_dispatch.apply(ClientMediaMsg::VideoReport(Some(user_id), report));
+            }
+            ClientMsg::KeyframeRequest(stream) => {
+                media_dispatch.apply(ClientMediaMsg::KeyframeRequest(stream));
+            }
+            ClientMsg::ClosePaint => {
+                host_props_dispatch.apply(ClientHostPropsMsg::ClosePaint);
             }
             ClientMsg::OnCummunication { message } => {
                 media_dispatch.apply(ClientMediaMsg::OnCummunication(message));
@@ -1,29 +1,37 @@
-use std::{rc::Rc, collections::HashMap};
+use std::{collections::{HashMap, HashSet}, rc::Rc};
 
 use wasm_bindgen::JsCast;
 use wasm_peers::UserId;
-use web_sys::{HtmlCanvasElement, HtmlTextAreaElement, InputEvent};
-use yew::Callback;
+use web_sys::{HtmlTextAreaElement, InputEvent};
 use yewdux::{store::{Store, Reducer}, prelude::Dispatch};
 
-use crate::{models::{host::HostPorps, commons::{AreaKind, InitUser}}, components::multi::draw::paint, utils::{inputs::{PaintAction, Message}, dom::remove_element}, stores::host_store::{self, HostStore}};
+use crate::{models::{assignment::Assignment, board::{Board, BoardHistory, BoardOperation, BoardPermission, PageAction, Whiteboard}, history::HistoryAction, host::HostPorps, commons::{AreaKind, InitUser, LockMode}, editor_settings::EditorSettings, text_operation::TextOperation, workspace::{DocumentKey, WorkspaceAction}}, utils::{inputs::{ClientMessage, Message}, dom::download_file}, stores::host_store::{self, HostStore}};
 
-use super::media_store::{MediaStore, ClientMediaMsg};
+use super::{client_items_store::{ClientItemsStore, ClientItemMsg}, client_store::{ClientMsg, ClientStore}, client_props_store::{ClientPropsStore, ClientPropsMsg}, media_store::{MediaStore, ClientMediaMsg}};
 
 
 #[derive(Clone, PartialEq, Store)]
 pub struct HostPropsStore {
     host_props: Option<HostPorps>,
-    paints: Option<HashMap<i8, Rc<HtmlCanvasElement>>>,
-    paints_f: Option<HashMap<i8, String>>
+    board: Option<Whiteboard>,
+    board_histories: HashMap<String, BoardHistory>,
+    board_permission: BoardPermission,
+    board_author: Option<String>,
+    annotation: Option<Board>,
+    /// Strokes of students already put back after a rejected operation.
+    resynced_strokes: HashSet<String>,
 }
 
 impl Default for HostPropsStore {
     fn default() -> Self {
         Self { 
             host_props: Some(HostPorps::new()),
-            paints: Some(HashMap::new()),
-            paints_f: Some(HashMap::new()),
+            board: None,
+            board_histories: HashMap::new(),
+            board_permission: BoardPermission::default(),
+            board_author: None,
+            annotation: None,
+            resynced_strokes: HashSet::new(),
         }
     }
 }
@@ -36,16 +44,80 @@ impl HostPropsStore {
         self.host_props.as_mut().unwrap()
     }
 
-    pub fn get_paints(&mut self) -> &mut HashMap<i8, Rc<HtmlCanvasElement>> {
-        self.paints.as_mut().unwrap()
+    /// `None` while the board is closed.
+    pub fn get_board(&self) -> Option<&Whiteboard> {
+        self.board.as_ref()
     }
 
-    pub fn get_mut_paints_f(&mut self) -> &mut HashMap<i8, String> {
-        self.paints_f.as_mut().unwrap()
+    /// Host's own edits of the current page.
+    pub fn get_board_history(&self) -> Option<&BoardHistory> {
+        self.board
+            .as_ref()
+            .and_then(|board| self.board_histories.get(&board.current))
     }
 
-    pub fn get_paints_f(&self) -> & HashMap<i8, String> {
-        self.paints_f.as_ref().unwrap()
+    pub fn get_board_permission(&self) -> &BoardPermission {
+        &self.board_permission
+    }
+
+    /// On the student side, the id to draw under or `None` if drawing is not allowed.
+    pub fn get_board_author(&self) -> Option<&String> {
+        self.board_author.as_ref()
+    }
+
+    /// Strokes over the shared screen, `None` while the screen is not shared.
+    pub fn get_annotation(&self) -> Option<&Board> {
+        self.annotation.as_ref()
+    }
+
+    fn annotation_mut(&mut self) -> crate::Result<&mut Board> {
+        self.annotation
+            .as_mut()
+            .ok_or_else(|| crate::Error::InvalidBoard("screen is not shared".to_owned()))
+    }
+
+    fn board_mut(&mut self) -> crate::Result<&mut Whiteboard> {
+        self.board
+            .as_mut()
+            .ok_or_else(|| crate::Error::InvalidBoard("board is not open".to_owned()))
+    }
+
+    fn current_page_id(&self) -> Option<String> {
+        self.board.as_ref().map(|board| board.current.clone())
+    }
+
+    fn apply_board_operation(&mut self, page_id: &str, operation: &BoardOperation) -> crate::Result<()> {
+        self.board_mut()?.page_mut(page_id)?.apply(operation)
+    }
+
+    fn send_page_action(&mut self, action: PageAction) {
+        match self.board_mut().and_then(|board| board.apply(&action)) {
+            Ok(()) => {
+                if let PageAction::Delete { id } = &action {
+                    self.board_histories.remove(id);
+                }
+                let message = Message::HostBoardPage { action };
+                Dispatch::<HostStore>::new().apply(host_store::Msg::SendMessage(message));
+            },
+            Err(err) => {
+                log::error!("cannot apply page action: {}", err);
+            },
+        }
+    }
+
+    /// What the host is showing, new pages are drawn over it.
+    fn host_content(&self) -> String {
+        match self.get_host_props().host_area_kind {
+            AreaKind::Editor => self.get_host_props().editor_content().to_owned(),
+            AreaKind::TextArea => self.get_host_props().host_area_content.content.clone(),
+        }
+    }
+
+    fn board_access(&self, user_id: UserId) -> Message {
+        let user_id = user_id.to_string();
+        Message::HostBoardAccess {
+            author: Some(user_id).filter(|user_id| self.board_permission.allows(user_id)),
+        }
     }
 }
 
@@ -53,9 +125,30 @@ pub enum HostHostMsg {
     AddClient(UserId),
     HostUpdateValue(String),
     HostTextAreaInput(InputEvent),
+    Workspace(WorkspaceAction),
+    SetEditorSettings(EditorSettings),
+    DistributeAssignment(String),
+    SaveReference,
+    AddBookmark(String),
+    ExportRecording,
+    SetLock(LockMode),
+    Scroll(u32),
     SwitchHostArea(AreaKind),
     OpenPaint,
+    Board(BoardOperation),
+    BoardHistory(HistoryAction),
+    AddBoardPage,
+    BoardPage(PageAction),
+    ClientBoard(UserId, String, BoardOperation),
+    SetBoardAccess(bool),
+    SetStudentBoardAccess(UserId, bool),
+    ClearStudentBoard(UserId),
     ClosePaint,
+    StartAnnotation,
+    Annotation(BoardOperation),
+    ClientAnnotation(UserId, BoardOperation),
+    ClearAnnotation,
+    StopAnnotation,
     OnCummunication,
 }
 
@@ -65,28 +158,51 @@ impl Reducer<HostPropsStore> for HostHostMsg {
         let global_dispatch = Dispatch::<HostStore>::new();
         match self {
             HostHostMsg::AddClient(user_id) => {
-                let editor_content = state.get_host_props().host_editor_content.clone();
+                let workspace = state.get_host_props().workspace.clone();
                 let text_area_content = state.get_host_props().host_area_content.content.clone();
                 let area_kind = state.get_host_props().host_area_kind;
                 let is_communication = state.get_host_props().is_communication;
                 let init_user = InitUser {
-                    editor_content,
+                    workspace,
                     text_area_content,
                     area_kind: area_kind.clone(),
                     is_communication
                 };
                 let message = Message::InitHostArea { 
-                    message: init_user,      
+                    message: init_user,
+                    settings: state.get_host_props().editor_settings.clone(),
                 };
                 global_dispatch.apply(host_store::Msg::SendMessageToUser(user_id, message));
+                let lock_mode = state.get_host_props().lock_mode;
+                if lock_mode.is_locked() {
+                    let message = Message::HostLock { mode: lock_mode };
+                    global_dispatch.apply(host_store::Msg::SendMessageToUser(user_id, message));
+                }
+                if let (LockMode::FollowHost, Some(line)) = (lock_mode, state.get_host_props().scroll_line) {
+                    let message = Message::HostScroll { line };
+                    global_dispatch.apply(host_store::Msg::SendMessageToUser(user_id, message));
+                }
+                if let Some(board) = state.get_board() {
+                    let message = Message::OpenPaint { board: board.clone() };
+                    global_dispatch.apply(host_store::Msg::SendMessageToUser(user_id, message));
+                }
+                if state.board_permission.allows(&user_id.to_string()) {
+                    global_dispatch.apply(host_store::Msg::SendMessageToUser(user_id, state.board_access(user_id)));
+                }
+                if let Some(board) = state.get_annotation() {
+                    let message = Message::HostAnnotations { board: Some(board.clone()) };
+                    global_dispatch.apply(host_store::Msg::SendMessageToUser(user_id, message));
+                }
             }
             HostHostMsg::HostUpdateValue(content) => {
-                state.get_mut_host_props().set_editor_content(content.clone());
-                let message = Message::HostToHost {
-                             message: content,
-                             area_kind: state.get_host_props().host_area_kind
-                };
-                global_dispatch.apply(host_store::Msg::SendMessage(message));
+                let document = state.get_host_props().workspace.active_key();
+                if let Some(operation) = state.get_mut_host_props().update_content(&document, content) {
+                    let message = Message::HostToHost {
+                        operation,
+                        document,
+                    };
+                    global_dispatch.apply(host_store::Msg::SendMessage(message));
+                }
             }
             HostHostMsg::HostTextAreaInput(event) => {
                 let content = event
@@ -94,12 +210,70 @@ impl Reducer<HostPropsStore> for HostHostMsg {
                     .unwrap()
                     .unchecked_into::<HtmlTextAreaElement>()
                     .value();
-                state.get_mut_host_props().set_text_area_content(content.clone());
-                let message = Message::HostToHost {
-                             message: content,
-                             area_kind: state.get_host_props().host_area_kind
-                };
+                if let Some(operation) = state.get_mut_host_props().update_content(&DocumentKey::TextArea, content) {
+                    let message = Message::HostToHost {
+                        operation,
+                        document: DocumentKey::TextArea,
+                    };
+                    global_dispatch.apply(host_store::Msg::SendMessage(message));
+                }
+            }
+            HostHostMsg::Workspace(action) => {
+                match state.get_mut_host_props().apply_workspace_action(&action) {
+                    Ok(()) => {
+                        let message = Message::HostWorkspace { action };
+                        global_dispatch.apply(host_store::Msg::SendMessage(message));
+                    },
+                    Err(err) => {
+                        log::error!("cannot apply workspace action: {}", err);
+                    },
+                }
+            }
+            HostHostMsg::SetEditorSettings(settings) => {
+                state.get_mut_host_props().set_editor_settings(settings.clone());
+                let message = Message::HostEditorSettings { settings };
+                global_dispatch.apply(host_store::Msg::SendMessage(message));
+            }
+            HostHostMsg::DistributeAssignment(title) => {
+                let assignment = Assignment::new(title, &state.get_host_props().workspace);
+                Dispatch::<ClientItemsStore>::new().apply(ClientItemMsg::Distribute(assignment));
+            }
+            HostHostMsg::SetLock(mode) => {
+                state.get_mut_host_props().set_lock_mode(mode);
+                let message = Message::HostLock { mode };
                 global_dispatch.apply(host_store::Msg::SendMessage(message));
+                if mode == LockMode::FollowHost {
+                    // students may have browsed to other files, bring them back to the host's one
+                    let action = WorkspaceAction::Open { id: state.get_host_props().workspace.active.clone() };
+                    global_dispatch.apply(host_store::Msg::SendMessage(Message::HostWorkspace { action }));
+                    if let Some(line) = state.get_host_props().scroll_line {
+                        let message = Message::HostScroll { line };
+                        global_dispatch.apply(host_store::Msg::SendMessage(message));
+                    }
+                }
+            }
+            HostHostMsg::Scroll(line) => {
+                let is_changed = state.get_mut_host_props().update_scroll_line(line);
+                if is_changed && state.get_host_props().lock_mode == LockMode::FollowHost {
+                    let message = Message::HostScroll { line };
+                    global_dispatch.apply(host_store::Msg::SendMessage(message));
+                }
+            }
+            HostHostMsg::SaveReference => {
+                state.get_mut_host_props().save_reference();
+            }
+            HostHostMsg::AddBookmark(title) => {
+                state.get_mut_host_props().add_bookmark(title.clone());
+                let message = Message::HostBookmark { title };
+                global_dispatch.apply(host_store::Msg::SendMessage(message));
+            }
+            HostHostMsg::ExportRecording => {
+                let result = serde_json::to_string(&*state.get_host_props().recording.borrow())
+                    .map_err(|err| crate::Error::FailedToExport(err.to_string()))
+                    .and_then(|json| download_file("lesson-replay.json", &json, "application/json"));
+                if let Err(err) = result {
+                    log::error!("cannot export recording: {}", err);
+                }
             }
             HostHostMsg::SwitchHostArea(area_kind) => {
                 state.get_mut_host_props().set_host_area_kind(area_kind);
@@ -108,26 +282,237 @@ impl Reducer<HostPropsStore> for HostHostMsg {
                 global_dispatch.apply(host_store::Msg::SendMessage(message));
             }
             HostHostMsg::OpenPaint => {
-                if state.get_paints_f().len() == 0 {
-                    let area_kind = state.get_host_props().host_area_kind;
-                    match area_kind {
-                        AreaKind::Editor => {
-                            let content: String = state.get_host_props().host_editor_content.clone();
-                            state.get_mut_paints_f().insert(1, content);
+                if state.board.is_none() {
+                    let board = Whiteboard::new(state.host_content());
+                    let message = Message::OpenPaint { board: board.clone() };
+                    state.board = Some(board);
+                    state.board_histories.clear();
+                    state.resynced_strokes.clear();
+                    global_dispatch.apply(host_store::Msg::SendMessage(message));
+                }
+            }
+            HostHostMsg::Board(operation) => {
+                let Some(page_id) = state.current_page_id() else {
+                    log::error!("cannot apply board operation: board is not open");
+                    return store;
+                };
+                let before = state.get_board()
+                    .and_then(|board| board.page(&page_id))
+                    .and_then(|page| page.stroke(operation.stroke_id()))
+                    .cloned();
+                match state.apply_board_operation(&page_id, &operation) {
+                    Ok(()) => {
+                        if let Some(page) = state.board.as_ref().and_then(|board| board.page(&page_id)) {
+                            state.board_histories
+                                .entry(page_id.clone())
+                                .or_default()
+                                .record(page, &operation, before, js_sys::Date::now());
+                        }
+                        let message = Message::HostPaint { page_id, operation };
+                        global_dispatch.apply(host_store::Msg::SendMessage(message));
+                    },
+                    Err(err) => {
+                        log::error!("cannot apply board operation: {}", err);
+                    },
+                }
+            }
+            HostHostMsg::BoardHistory(action) => {
+                let Some(page_id) = state.current_page_id() else {
+                    return store;
+                };
+                let operations = state.board_histories
+                    .get_mut(&page_id)
+                    .map(|history| history.apply(action))
+                    .unwrap_or_default();
+                for operation in operations {
+                    match state.apply_board_operation(&page_id, &operation) {
+                        Ok(()) => {
+                            let message = Message::HostPaint { page_id: page_id.clone(), operation };
+                            global_dispatch.apply(host_store::Msg::SendMessage(message));
                         },
-                        AreaKind::TextArea => {
-                            let content = state.get_host_props().host_area_content.content.clone();
-                            state.get_mut_paints_f().insert(1, content);
+                        Err(err) => {
+                            log::error!("cannot apply board history: {}", err);
+                        },
+                    }
+                }
+            }
+            HostHostMsg::AddBoardPage => {
+                // the new page keeps the background of the one it follows
+                let background = state
+                    .get_board()
+                    .and_then(Whiteboard::current_page)
+                    .map(|page| page.background)
+                    .unwrap_or_default();
+                let action = PageAction::create(state.host_content(), background);
+                state.send_page_action(action);
+            }
+            HostHostMsg::BoardPage(action) => {
+                state.send_page_action(action);
+            }
+            HostHostMsg::ClientBoard(user_id, page_id, operation) => {
+                let author = user_id.to_string();
+                let operation = operation.with_author(&author);
+                // students only draw on the page the host is showing
+                let result = if !state.board_permission.allows(&author) {
+                    Err(crate::Error::InvalidBoard("drawing is not allowed".to_owned()))
+                } else if state.current_page_id().as_ref() != Some(&page_id) {
+                    Err(crate::Error::InvalidBoard(format!("page '{}' is not shown", page_id)))
+                } else {
+                    state.board_mut()
+                        .and_then(|board| board.page_mut(&page_id))
+                        .and_then(|page| page.apply_as(&operation, &author))
+                };
+                match result {
+                    Ok(()) => {
+                        // the student has already drawn it
+                        let others = Dispatch::<ClientItemsStore>::new().get().player_ids();
+                        others
+                            .into_iter()
+                            .filter(|other| *other != user_id)
+                            .for_each(|other| {
+                                let message = Message::HostPaint { page_id: page_id.clone(), operation: operation.clone() };
+                                global_dispatch.apply(host_store::Msg::SendMessageToUser(other, message));
+                            });
+                    },
+                    Err(err) => {
+                        log::error!("cannot apply client board operation, id: {}, err: {}", author, err);
+                        // undo what the student drew locally, once for the whole stroke
+                        if !state.resynced_strokes.insert(operation.stroke_id().to_owned()) {
+                            return store;
+                        }
+                        let messages = match state.get_board() {
+                            Some(board) => {
+                                let mut messages: Vec<Message> = board.page(&page_id)
+                                    .map(|page| page.resync_stroke(operation.stroke_id()))
+                                    .unwrap_or_default()
+                                    .into_iter()
+                                    .map(|operation| Message::HostPaint { page_id: page_id.clone(), operation })
+                                    .collect();
+                                if board.current != page_id {
+                                    let action = PageAction::Show { id: board.current.clone() };
+                                    messages.push(Message::HostBoardPage { action });
+                                }
+                                messages
+                            },
+                            None => vec![Message::ClosePaint],
+                        };
+                        for message in messages {
+                            global_dispatch.apply(host_store::Msg::SendMessageToUser(user_id, message));
+                        }
+                    },
+                }
+            }
+            HostHostMsg::SetBoardAccess(everyone) => {
+                state.board_permission.set_everyone(everyone);
+                for user_id in Dispatch::<ClientItemsStore>::new().get().player_ids() {
+                    global_dispatch.apply(host_store::Msg::SendMessageToUser(user_id, state.board_access(user_id)));
+                }
+            }
+            HostHostMsg::SetStudentBoardAccess(user_id, allowed) => {
+                state.board_permission.set_student(user_id.to_string(), allowed);
+                global_dispatch.apply(host_store::Msg::SendMessageToUser(user_id, state.board_access(user_id)));
+            }
+            HostHostMsg::ClearStudentBoard(user_id) => {
+                let author = user_id.to_string();
+                let strokes: Vec<(String, String)> = state.get_board()
+                    .map(|board| {
+                        board.pages
+                            .iter()
+                            .flat_map(|page| page.strokes_by(&author).into_iter().map(|id| (page.id.clone(), id)))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                for (page_id, id) in strokes {
+                    let operation = BoardOperation::Delete { id };
+                    match state.apply_board_operation(&page_id, &operation) {
+                        Ok(()) => {
+                            let message = Message::HostPaint { page_id, operation };
+                            global_dispatch.apply(host_store::Msg::SendMessage(message));
+                        },
+                        Err(err) => {
+                            log::error!("cannot clear student strokes: {}", err);
+                        },
+                    }
+                }
+                let annotations = state.get_annotation()
+                    .map(|board| board.strokes_by(&author))
+                    .unwrap_or_default();
+                for id in annotations {
+                    let operation = BoardOperation::Delete { id };
+                    match state.annotation_mut().and_then(|board| board.apply(&operation)) {
+                        Ok(()) => {
+                            let message = Message::HostAnnotation { operation };
+                            global_dispatch.apply(host_store::Msg::SendMessage(message));
+                        },
+                        Err(err) => {
+                            log::error!("cannot clear student annotations: {}", err);
                         },
                     }
-                    let message = Message::OpenPaint;
-                    global_dispatch.apply(host_store::Msg::SendMessage(message));
                 }
             }
             HostHostMsg::ClosePaint => {
-                remove_element("draw-canvas".to_string());
                 let message = Message::ClosePaint;
-                state.get_mut_paints_f().remove(&1);
+                state.board = None;
+                global_dispatch.apply(host_store::Msg::SendMessage(message));
+            }
+            HostHostMsg::StartAnnotation | HostHostMsg::ClearAnnotation => {
+                let board = Board::new(String::default());
+                let message = Message::HostAnnotations { board: Some(board.clone()) };
+                state.annotation = Some(board);
+                global_dispatch.apply(host_store::Msg::SendMessage(message));
+            }
+            HostHostMsg::Annotation(operation) => {
+                match state.annotation_mut().and_then(|board| board.apply(&operation)) {
+                    Ok(()) => {
+                        let message = Message::HostAnnotation { operation };
+                        global_dispatch.apply(host_store::Msg::SendMessage(message));
+                    },
+                    Err(err) => {
+                        log::error!("cannot apply annotation: {}", err);
+                    },
+                }
+            }
+            HostHostMsg::ClientAnnotation(user_id, operation) => {
+                let author = user_id.to_string();
+                let operation = operation.with_author(&author);
+                // the same students draw on the shared screen as on the board
+                let result = if state.board_permission.allows(&author) {
+                    state.annotation_mut().and_then(|board| board.apply_as(&operation, &author))
+                } else {
+                    Err(crate::Error::InvalidBoard("drawing is not allowed".to_owned()))
+                };
+                match result {
+                    Ok(()) => {
+                        let others = Dispatch::<ClientItemsStore>::new().get().player_ids();
+                        others
+                            .into_iter()
+                            .filter(|other| *other != user_id)
+                            .for_each(|other| {
+                                let message = Message::HostAnnotation { operation: operation.clone() };
+                                global_dispatch.apply(host_store::Msg::SendMessageToUser(other, message));
+                            });
+                    },
+                    Err(err) => {
+                        log::error!("cannot apply client annotation, id: {}, err: {}", author, err);
+                        if !state.resynced_strokes.insert(operation.stroke_id().to_owned()) {
+                            return store;
+                        }
+                        let messages = match state.get_annotation() {
+                            Some(board) => board.resync_stroke(operation.stroke_id())
+                                .into_iter()
+                                .map(|operation| Message::HostAnnotation { operation })
+                                .collect(),
+                            None => vec![Message::HostAnnotations { board: None }],
+                        };
+                        for message in messages {
+                            global_dispatch.apply(host_store::Msg::SendMessageToUser(user_id, message));
+                        }
+                    },
+                }
+            }
+            HostHostMsg::StopAnnotation => {
+                state.annotation = None;
+                let message = Message::HostAnnotations { board: None };
                 global_dispatch.apply(host_store::Msg::SendMessage(message));
             }
             HostHostMsg::OnCummunication => {
@@ -143,18 +528,26 @@ impl Reducer<HostPropsStore> for HostHostMsg {
 }
 
 pub enum ClientHostPropsMsg {
-    InitHost(InitUser),
+    InitHost(InitUser, EditorSettings),
+    SetEditorSettings(EditorSettings),
+    HostLock(LockMode),
+    HostScroll(u32),
+    HostBookmark(String),
     HostSwitchArea(AreaKind),
     HostToHost {
-        message: String,
-        area_kind: AreaKind,
-    },
-    OpenPaint,
-    HostPaint {
-        offset_x: f64,
-        offset_y: f64,
-        action: PaintAction,
+        operation: TextOperation,
+        document: DocumentKey,
     },
+    HostWorkspace(WorkspaceAction),
+    OpenPaint(Whiteboard),
+    HostPaint(String, BoardOperation),
+    HostBoardPage(PageAction),
+    HostBoardAccess(Option<String>),
+    Board(BoardOperation),
+    HostAnnotations(Option<Board>),
+    HostAnnotation(BoardOperation),
+    Annotation(BoardOperation),
+    ClosePaint,
 }
 
 
@@ -164,71 +557,93 @@ impl Reducer<HostPropsStore> for ClientHostPropsMsg {
         let media_dispatch = Dispatch::<MediaStore>::new();
         match self {
             ClientHostPropsMsg::HostToHost { 
-                message,
-                area_kind
+                operation,
+                document
             } => {
-                match area_kind {
-                    AreaKind::Editor => {
-                        state.get_mut_host_props().set_editor_content(message);
-                    },
-                    AreaKind::TextArea => {
-                        state.get_mut_host_props().host_area_content.set_content(message)
-                    },
+                if let Err(err) = state.get_mut_host_props().apply_operation(&document, &operation) {
+                    log::error!("cannot apply host operation: {}", err);
                 }
             }
-            ClientHostPropsMsg::InitHost(user) => {
+            ClientHostPropsMsg::HostWorkspace(action) => {
+                if let Err(err) = state.get_mut_host_props().apply_workspace_action(&action) {
+                    log::error!("cannot apply host workspace action: {}", err);
+                }
+            }
+            ClientHostPropsMsg::InitHost(user, settings) => {
+                state.get_mut_host_props().set_editor_settings(settings);
                 state.get_mut_host_props().host_area_content.set_content(user.text_area_content);
-                state.get_mut_host_props().set_editor_content(user.editor_content);
+                state.get_mut_host_props().set_workspace(user.workspace);
                 state.get_mut_host_props().set_host_area_kind(user.area_kind);
                 state.get_mut_host_props().set_communication(user.is_communication);
                 media_dispatch.apply(ClientMediaMsg::SetCommunication(user.is_communication));
             },
+            ClientHostPropsMsg::SetEditorSettings(settings) => {
+                state.get_mut_host_props().set_editor_settings(settings);
+            },
+            ClientHostPropsMsg::HostLock(mode) => {
+                state.get_mut_host_props().set_lock_mode(mode);
+                Dispatch::<ClientPropsStore>::new().apply(ClientPropsMsg::SetLocked(mode.is_locked()));
+            },
+            ClientHostPropsMsg::HostScroll(line) => {
+                state.get_mut_host_props().update_scroll_line(line);
+            },
+            ClientHostPropsMsg::HostBookmark(title) => {
+                state.get_mut_host_props().add_bookmark(title);
+            },
             ClientHostPropsMsg::HostSwitchArea(area_kind) => {
                 state.get_mut_host_props().set_host_area_kind(area_kind);
             },
-            ClientHostPropsMsg::OpenPaint => {
-                match state.get_host_props().host_area_kind {
-                    AreaKind::Editor => {
-                        let canvas = paint::start(&state.get_host_props().host_editor_content, Callback::default(), false)
-                            .expect("cannot get canvas");
-                        state.get_paints().insert(1, canvas);
+            ClientHostPropsMsg::OpenPaint(board) => {
+                state.board = Some(board);
+            }
+            ClientHostPropsMsg::HostPaint(page_id, operation) => {
+                if let Err(err) = state.apply_board_operation(&page_id, &operation) {
+                    log::error!("cannot apply host board operation: {}", err);
+                }
+            }
+            ClientHostPropsMsg::HostBoardPage(action) => {
+                if let Err(err) = state.board_mut().and_then(|board| board.apply(&action)) {
+                    log::error!("cannot apply host page action: {}", err);
+                }
+            }
+            ClientHostPropsMsg::HostBoardAccess(author) => {
+                state.board_author = author;
+            }
+            ClientHostPropsMsg::Board(operation) => {
+                let Some(page_id) = state.current_page_id() else {
+                    return store;
+                };
+                match state.apply_board_operation(&page_id, &operation) {
+                    Ok(()) => {
+                        let message = ClientMessage::ClientBoard { page_id, operation };
+                        Dispatch::<ClientStore>::new().apply(ClientMsg::SendMessage(message));
                     },
-                    AreaKind::TextArea => {
-                        let canvas = paint::start(&state.get_host_props().host_area_content.content, Callback::default(), false)
-                            .expect("cannot get canvas");
-                        state.get_paints().insert(2, canvas);
+                    Err(err) => {
+                        log::error!("cannot apply board operation: {}", err);
                     },
                 }
             }
-            ClientHostPropsMsg::HostPaint { 
-                offset_x,
-                offset_y,
-                action
-            } => {
-                let key = 1;
-                let canvas = state.get_paints().get(&key).expect("cannot get canvas");
-                let context = canvas
-                    .get_context("2d")
-                    .expect("cannot get canvas")
-                    .unwrap()
-                    .dyn_into::<web_sys::CanvasRenderingContext2d>()
-                    .expect("cannot get canvas");
-                match action {
-                    PaintAction::Down => {
-                        context.begin_path();
-                        context.move_to(offset_x, offset_y);
-                    },
-                    PaintAction::Move => {
-                        context.line_to(offset_x, offset_y);
-                        context.stroke();
-                        context.begin_path();
-                        context.move_to(offset_x, offset_y);
+            ClientHostPropsMsg::HostAnnotations(board) => {
+                state.annotation = board;
+            }
+            ClientHostPropsMsg::HostAnnotation(operation) => {
+                if let Err(err) = state.annotation_mut().and_then(|board| board.apply(&operation)) {
+                    log::error!("cannot apply host annotation: {}", err);
+                }
+            }
+            ClientHostPropsMsg::Annotation(operation) => {
+                match state.annotation_mut().and_then(|board| board.apply(&operation)) {
+                    Ok(()) => {
+                        let message = ClientMessage::ClientAnnotation { operation };
+                        Dispatch::<ClientStore>::new().apply(ClientMsg::SendMessage(message));
                     },
-                    PaintAction::Up => {
-                        context.line_to(offset_x, offset_y);
-                        context.stroke();
+                    Err(err) => {
+                        log::error!("cannot apply annotation: {}", err);
                     },
-                };
+                }
+            }
+            ClientHostPropsMsg::ClosePaint => {
+                state.board = None;
             }
         }
         store
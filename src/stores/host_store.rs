@@ -3,7 +3,7 @@ use std::{cell::RefCell, rc::Rc, collections::HashMap};
 use wasm_peers::{SessionId, UserId, one_to_many::MiniServer};
 use yewdux::{store::{Reducer, Store}, prelude::Dispatch};
 
-use crate::{components::multi::host::host_manager::HostManager, models::{client::ClientItem, commons::{AreaKind, InitUser}, video::Video, audio::Audio}, stores::host_store, utils::{inputs::Message, dom::{create_video_id, on_visible_el}}};
+use crate::{components::multi::host::host_manager::HostManager, models::{bitrate::{ReceiverReport, VideoStream}, board::BoardOperation, client::ClientItem, commons::{AreaKind, InitUser}, video::Video, audio::Audio, laser::LaserUpdate, presence::RemoteCursor, review::ReviewMessage, text_operation::TextOperation, workspace::{DocumentKey, Workspace, WorkspaceAction}}, stores::host_store, utils::{inputs::Message, dom::{create_video_id, on_visible_el}}};
 
 use super::{client_items_store::{ClientItemsStore, ClientItemMsg}, client_props_store::{ClientPropsStore, HostClientMsg}, host_props_store::{HostPropsStore, HostHostMsg}, media_store::{MediaStore, HostMediaMsg}};
 
@@ -76,6 +76,23 @@ impl HostStore {
     pub fn send_message_to_all(&self, mesage: Message) {
         let _ = self.get_mini_server().send_message_to_all(&mesage);
     }
+
+    /// Students whose laser channel is not open yet miss the update.
+    pub fn send_laser(&self, update: &LaserUpdate) {
+        let Some(host_manager) = self.host_manager.as_ref() else {
+            return;
+        };
+        let message = match serde_json::to_string(update) {
+            Ok(message) => message,
+            Err(err) => {
+                log::error!("cannot serialize laser update: {}", err);
+                return;
+            }
+        };
+        for laser_channel in host_manager.borrow().laser_channels.borrow().values() {
+            laser_channel.send(&message);
+        }
+    }
 }
 
 pub enum Msg {
@@ -88,8 +105,16 @@ pub enum Msg {
     DisconnectClient(UserId),
     InitClient(UserId, InitUser),
     ClientSwitchVideo(UserId, bool),
-    ClientToClient(UserId, String, AreaKind),
+    ClientToClient(UserId, u64, TextOperation, DocumentKey),
+    ClientWorkspace(UserId, WorkspaceAction),
+    ClientSubmit(UserId, String, Workspace),
     ClientSwitchArea(UserId, AreaKind),
+    ClientCursor(UserId, RemoteCursor),
+    ClientReviewReply(UserId, String, ReviewMessage),
+    ClientBoard(UserId, String, BoardOperation),
+    ClientAnnotation(UserId, BoardOperation),
+    ClientVideoReport(UserId, ReceiverReport),
+    ClientKeyframeRequest(VideoStream),
     // Host manager actions
 }
 
@@ -137,24 +162,40 @@ impl Reducer<HostStore> for Msg {
             }
             Msg::ClientToClient(
                 user_id,
-                message,
-                area_kind
+                revision,
+                operation,
+                document
             ) => {
-                match area_kind {
-                    AreaKind::Editor => {
-                        client_items_dispatch.apply(ClientItemMsg::SetEditorContent(user_id, message.clone()));
-                        client_area_dispatch.apply(HostClientMsg::SetEditorContent(user_id, message));
-                    },
-                    AreaKind::TextArea => {
-                        client_items_dispatch.apply(ClientItemMsg::SetTextAreaContent(user_id, message.clone()));
-                        client_area_dispatch.apply(HostClientMsg::SetTextAreaContent(user_id, message));
-                    },
-                }
+                client_items_dispatch.apply(ClientItemMsg::ClientEdit(user_id, revision, operation, document));
+            }
+            Msg::ClientWorkspace(user_id, action) => {
+                client_items_dispatch.apply(ClientItemMsg::ClientWorkspace(user_id, action));
+            }
+            Msg::ClientSubmit(user_id, assignment_id, workspace) => {
+                client_items_dispatch.apply(ClientItemMsg::Submit(user_id, assignment_id, workspace));
             }
             Msg::ClientSwitchArea(user_id, area_kind) => {
                 client_area_dispatch.apply(HostClientMsg::ClientSwitchArea(user_id, area_kind));
                 client_items_dispatch.apply(ClientItemMsg::ClientSwitchArea(user_id, area_kind));                
             }
+            Msg::ClientCursor(user_id, remote_cursor) => {
+                client_area_dispatch.apply(HostClientMsg::SetRemoteCursor(user_id, remote_cursor));
+            }
+            Msg::ClientReviewReply(user_id, thread_id, message) => {
+                client_items_dispatch.apply(ClientItemMsg::ClientReviewReply(user_id, thread_id, message));
+            }
+            Msg::ClientBoard(user_id, page_id, operation) => {
+                host_area_dispatch.apply(HostHostMsg::ClientBoard(user_id, page_id, operation));
+            }
+            Msg::ClientAnnotation(user_id, operation) => {
+                host_area_dispatch.apply(HostHostMsg::ClientAnnotation(user_id, operation));
+            }
+            Msg::ClientVideoReport(user_id, report) => {
+                media_dispatch.apply(HostMediaMsg::VideoReport(user_id, report));
+            }
+            Msg::ClientKeyframeRequest(stream) => {
+                media_dispatch.apply(HostMediaMsg::KeyframeRequest(stream));
+            }
         };
 
         store
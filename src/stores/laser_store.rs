@@ -0,0 +1,82 @@
+use std::{cell::RefCell, rc::Rc};
+
+use gloo_timers::callback::Timeout;
+use yewdux::{store::{Store, Reducer}, prelude::Dispatch};
+
+use crate::{constants::LASER_INTERVAL_MS, models::laser::{LaserPoint, LaserThrottle, LaserUpdate}};
+
+use super::host_store::HostStore;
+
+/// The host's laser pointer. It is kept apart from the other stores so that
+/// the pointer moving around does not re-render the editor or the board.
+#[derive(Clone, PartialEq, Store, Default)]
+pub struct LaserStore {
+    enabled: bool,
+    // shared, so that a held back position does not notify anybody
+    throttle: Rc<RefCell<LaserThrottle>>,
+    latest: Option<LaserUpdate>,
+}
+
+impl LaserStore {
+    /// On the host side, whether the pointer is shown to the students instead of drawing.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// On the student side, the last update from the host.
+    pub fn get_latest(&self) -> Option<&LaserUpdate> {
+        self.latest.as_ref()
+    }
+
+    // Updates go over the unordered laser channels, not with the other
+    // messages. One may be lost or overtaken, a receiver drops updates older
+    // than the one it shows and the dot fades out by itself.
+    fn send(&self, update: Option<LaserUpdate>) {
+        if let Some(update) = update {
+            Dispatch::<HostStore>::new().get().send_laser(&update);
+        }
+        if self.throttle.borrow_mut().schedule() {
+            Timeout::new(LASER_INTERVAL_MS as u32, move || {
+                Dispatch::<LaserStore>::new().apply(LaserMsg::Flush);
+            }).forget();
+        }
+    }
+}
+
+pub enum LaserMsg {
+    SetEnabled(bool),
+    Move(Option<LaserPoint>),
+    Flush,
+    Host(LaserUpdate),
+}
+
+impl Reducer<LaserStore> for LaserMsg {
+    fn apply(self, mut store: Rc<LaserStore>) -> Rc<LaserStore> {
+        let state = Rc::make_mut(&mut store);
+        match self {
+            LaserMsg::SetEnabled(enabled) => {
+                state.enabled = enabled;
+                if !enabled {
+                    let update = state.throttle.borrow_mut().push(None, js_sys::Date::now());
+                    state.send(update);
+                }
+            }
+            LaserMsg::Move(point) => {
+                if state.enabled {
+                    let update = state.throttle.borrow_mut().push(point, js_sys::Date::now());
+                    state.send(update);
+                }
+            }
+            LaserMsg::Flush => {
+                let update = state.throttle.borrow_mut().flush(js_sys::Date::now());
+                state.send(update);
+            }
+            LaserMsg::Host(update) => {
+                if state.latest.as_ref().map_or(true, |latest| update.is_newer_than(latest)) {
+                    state.latest = Some(update);
+                }
+            }
+        }
+        store
+    }
+}
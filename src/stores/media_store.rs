@@ -4,9 +4,9 @@ use gloo_timers::callback::Timeout;
 use wasm_peers::{UserId, one_to_many::MiniServer};
 use yewdux::{store::{Store, Reducer}, prelude::Dispatch};
 
-use crate::{encoders::{camera_encoder::CameraEncoder, microphone_encoder::MicrophoneEncoder, screen_encoder::ScreenEncoder}, stores::client_store::{ClientStore, ClientMsg}, utils::{inputs::{ManyMassage, ClientMessage, Message}, dom::{on_visible_el, switch_visible_el}}, models::packet::{AudioPacket, VideoPacket}, constants::VIDEO_ELEMENT_ID, components::multi::{host::host_manager::HostManager, client::client_manager::ClientManager}};
+use crate::{encoders::{camera_encoder::CameraEncoder, microphone_encoder::MicrophoneEncoder, screen_encoder::ScreenEncoder}, stores::client_store::{ClientStore, ClientMsg}, utils::{inputs::{ManyMassage, ClientMessage, Message}, dom::{on_visible_el, switch_visible_el}}, models::{bitrate::{ReceiverReport, VideoStream}, packet::{AudioPacket, VideoPacket}}, constants::{SCREEN_SHARE_ELEMENT_ID, VIDEO_ELEMENT_ID}, components::multi::{host::host_manager::HostManager, client::client_manager::ClientManager}};
 
-use super::host_store::{HostStore, self};
+use super::{host_props_store::{HostHostMsg, HostPropsStore}, host_store::{HostStore, self}};
 
 
 
@@ -83,6 +83,22 @@ impl MediaStore {
     pub fn is_communication(&self) -> bool {
         *self.is_communication.borrow()
     }
+
+    /// Hands a receiver's report to the encoder it is about. The encoders keep
+    /// the quality to themselves, so this does not re-render anything.
+    fn on_video_report(&self, receiver: &str, report: ReceiverReport) {
+        match report.stream {
+            VideoStream::Camera => self.get_camera().on_report(receiver, report),
+            VideoStream::Screen => self.get_screen().on_report(receiver, report),
+        }
+    }
+
+    fn on_keyframe_request(&self, stream: VideoStream) {
+        match stream {
+            VideoStream::Camera => self.get_camera().request_keyframe(),
+            VideoStream::Screen => self.get_screen().request_keyframe(),
+        }
+    }
 }
 
 pub enum HostMediaMsg {
@@ -99,6 +115,8 @@ pub enum HostMediaMsg {
     EnableScreenShare(bool),
     SendIsScreenState(UserId),
     ResumeVideo,
+    VideoReport(UserId, ReceiverReport),
+    KeyframeRequest(VideoStream),
 }
 
 impl Reducer<MediaStore> for HostMediaMsg {
@@ -207,6 +225,8 @@ impl Reducer<MediaStore> for HostMediaMsg {
                     log::error!("is screen do");
                     let message = Message::HostIsScreenShare { message: *state.is_screen.borrow() };
                     global_dispatch_move.apply(host_store::Msg::SendMessage(message));
+                    let host_props_dispatch = Dispatch::<HostPropsStore>::new();
+                    host_props_dispatch.apply(HostHostMsg::StartAnnotation);
                     let is_screen = state.is_screen.clone();
                     let on_frame = move |packet: VideoPacket| {
                         
@@ -222,11 +242,13 @@ impl Reducer<MediaStore> for HostMediaMsg {
                         is_screen.replace(false);
                         let message = Message::HostIsScreenShare { message: *is_screen.borrow() };
                         global_dispatch.apply(host_store::Msg::SendMessage(message));
+                        host_props_dispatch.apply(HostHostMsg::StopAnnotation);
                         dispatch.apply(HostMediaMsg::EnableScreenShare(false));
                     };
                     state.get_mut_screen().start(
                         on_frame,
                         on_stop_share,
+                        SCREEN_SHARE_ELEMENT_ID,
                     );
                 }
             }
@@ -237,6 +259,12 @@ impl Reducer<MediaStore> for HostMediaMsg {
             HostMediaMsg::ResumeVideo => {
                 dispatch.apply(HostMediaMsg::EnableVideo(true));
             }
+            HostMediaMsg::VideoReport(user_id, report) => {
+                state.on_video_report(&user_id.to_string(), report);
+            }
+            HostMediaMsg::KeyframeRequest(stream) => {
+                state.on_keyframe_request(stream);
+            }
             HostMediaMsg::OnCummunication(message) => {
                 switch_visible_el(message, "video-box");
                 state.set_communication(message);
@@ -261,6 +289,10 @@ pub enum ClientMediaMsg {
     SwitchVedeo(bool),
     OnCummunication (bool),
     SetCommunication(bool),
+    /// A report about the camera, from a peer or from the host when `None`.
+    VideoReport(Option<UserId>, ReceiverReport),
+    /// From the host or a peer, the camera is all a student sends.
+    KeyframeRequest(VideoStream),
 }
 
 impl Reducer<MediaStore> for ClientMediaMsg {
@@ -380,6 +412,13 @@ impl Reducer<MediaStore> for ClientMediaMsg {
             ClientMediaMsg::SetCommunication(message) => {
                 state.set_communication(message);
             }
+            ClientMediaMsg::VideoReport(user_id, report) => {
+                let receiver = user_id.map_or_else(|| "host".to_owned(), |user_id| user_id.to_string());
+                state.on_video_report(&receiver, report);
+            }
+            ClientMediaMsg::KeyframeRequest(stream) => {
+                state.on_keyframe_request(stream);
+            }
         }
         store
     }
@@ -3,4 +3,5 @@ pub mod client_store;
 pub mod media_store;
 pub mod client_items_store;
 pub mod client_props_store;
-pub mod host_props_store;
\ No newline at end of file
+pub mod host_props_store;
+pub mod laser_store;
\ No newline at end of file
@@ -1,7 +1,9 @@
-use wasm_bindgen::JsCast;
-use web_sys::{Element, HtmlInputElement, HtmlTextAreaElement, UrlSearchParams, Window, HtmlElement, HtmlVideoElement};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Blob, BlobPropertyBag, Element, HtmlAnchorElement, HtmlInputElement, HtmlTextAreaElement, KeyboardEvent, MouseEvent, Url, UrlSearchParams, Window, HtmlElement, HtmlVideoElement};
 use yew::NodeRef;
 
+use crate::models::history::HistoryAction;
+
 pub fn global_window() -> Window {
     web_sys::window().expect("there was no window global object!")
 }
@@ -125,3 +127,73 @@ pub fn create_video_element() -> HtmlVideoElement {
         .expect("cannot cast video element");
     video_element
 }
+
+/// Lets the browser save `content` as a file.
+pub fn download_file(file_name: &str, content: &str, mime_type: &str) -> crate::Result<()> {
+    let parts = js_sys::Array::of1(&JsValue::from_str(content));
+    let mut options = BlobPropertyBag::new();
+    options.type_(mime_type);
+    let blob = Blob::new_with_str_sequence_and_options(&parts, &options)
+        .map_err(|err| crate::Error::FailedToExport(format!("{:?}", err)))?;
+    let url = Url::create_object_url_with_blob(&blob)
+        .map_err(|err| crate::Error::FailedToExport(format!("{:?}", err)))?;
+    let result = download_url(file_name, &url);
+    let _ = Url::revoke_object_url(&url);
+    result
+}
+
+/// Saves whatever `url` points to, a blob or a data url, under `file_name`.
+pub fn download_url(file_name: &str, url: &str) -> crate::Result<()> {
+    let link = get_document()
+        .create_element("a")
+        .map_err(|err| crate::Error::FailedToExport(format!("{:?}", err)))?
+        .dyn_into::<HtmlAnchorElement>()
+        .map_err(|err| {
+            crate::Error::UnexpectedElement(format!("element is not an anchor: {:?}", err))
+        })?;
+    link.set_href(url);
+    link.set_download(file_name);
+    link.click();
+    Ok(())
+}
+
+/// Pointer position as a fraction of the size of the element the handler is on.
+pub fn pointer_fraction(event: &MouseEvent) -> Option<(f64, f64)> {
+    let rect = event
+        .current_target()?
+        .dyn_into::<Element>()
+        .ok()?
+        .get_bounding_client_rect();
+    if rect.width() <= 0.0 || rect.height() <= 0.0 {
+        return None;
+    }
+    Some((
+        (event.client_x() as f64 - rect.left()) / rect.width(),
+        (event.client_y() as f64 - rect.top()) / rect.height(),
+    ))
+}
+
+pub fn format_time(timestamp: f64) -> String {
+    js_sys::Date::new(&JsValue::from_f64(timestamp))
+        .to_locale_time_string("ru-RU")
+        .into()
+}
+
+/// Undo or redo asked for with Ctrl+Z, Ctrl+Shift+Z or Ctrl+Y.
+pub fn history_shortcut(event: &KeyboardEvent) -> Option<HistoryAction> {
+    if !(event.ctrl_key() || event.meta_key()) {
+        return None;
+    }
+    match event.key().to_lowercase().as_str() {
+        "z" if event.shift_key() => Some(HistoryAction::Redo),
+        "z" => Some(HistoryAction::Undo),
+        "y" => Some(HistoryAction::Redo),
+        _ => None,
+    }
+}
+
+/// `mm:ss` of an offset in milliseconds.
+pub fn format_duration(millis: f64) -> String {
+    let seconds = (millis / 1000.0).max(0.0) as u64;
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
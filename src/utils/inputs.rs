@@ -1,26 +1,59 @@
 use serde::{Serialize, Deserialize};
 
-use crate::models::{packet::{VideoPacket, AudioPacket}, commons::{AreaKind, InitUser}};
-
-#[derive(PartialEq, Serialize, Deserialize)]
-pub enum PaintAction {
-    Down,
-    Move,
-    Up
-}
+use crate::models::{assignment::Assignment, bitrate::{ReceiverReport, VideoStream}, board::{Board, BoardOperation, PageAction, Whiteboard}, laser::LaserSignal, packet::{VideoPacket, AudioPacket}, commons::{AreaKind, InitUser, LockMode}, editor_settings::EditorSettings, presence::RemoteCursor, review::{ReviewMessage, ReviewThread}, text_operation::TextOperation, workspace::{DocumentKey, Workspace, WorkspaceAction}};
 
 #[derive(Serialize, Deserialize)]
 pub enum Message {
     InitHostArea {
-        message: InitUser
+        message: InitUser,
+        settings: EditorSettings,
+    },
+    HostEditorSettings {
+        settings: EditorSettings,
     },
     HostToHost {
-        message: String,
-        area_kind: AreaKind,
+        operation: TextOperation,
+        document: DocumentKey,
     },
     HostToClient {
-        message: String,
-        area_kind: AreaKind,
+        operation: TextOperation,
+        document: DocumentKey,
+    },
+    HostAckClient {
+        document: DocumentKey,
+    },
+    /// The host could not apply an edit of the student, who starts over from this.
+    HostResync {
+        message: InitUser,
+    },
+    HostWorkspace {
+        action: WorkspaceAction,
+    },
+    HostLock {
+        mode: LockMode,
+    },
+    HostScroll {
+        line: u32,
+    },
+    HostBookmark {
+        title: String,
+    },
+    HostAssignment {
+        assignment: Assignment,
+    },
+    HostSubmissionAccepted {
+        assignment_id: String,
+        submitted_at: f64,
+    },
+    HostCursor {
+        message: Option<RemoteCursor>,
+    },
+    HostReviewThread {
+        thread: ReviewThread,
+    },
+    HostReviewReply {
+        thread_id: String,
+        message: ReviewMessage,
     },
     HostVideo {
         message: VideoPacket
@@ -42,12 +75,39 @@ pub enum Message {
     HostSwitchArea {
         message: AreaKind
     },
-    OpenPaint,
+    OpenPaint {
+        board: Whiteboard,
+    },
     ClosePaint,
     HostPaint {
-        offset_x: f64,
-        offset_y: f64,
-        action: PaintAction,
+        page_id: String,
+        operation: BoardOperation,
+    },
+    HostBoardPage {
+        action: PageAction,
+    },
+    /// Id to draw under, `None` when the student may not draw.
+    HostBoardAccess {
+        author: Option<String>,
+    },
+    /// Annotations over the shared screen, `None` once sharing stops.
+    HostAnnotations {
+        board: Option<Board>,
+    },
+    HostAnnotation {
+        operation: BoardOperation,
+    },
+    /// Sets up the laser channel, the updates themselves don't come this way.
+    HostLaserSignal {
+        signal: LaserSignal,
+    },
+    /// How the student's camera arrives at the host.
+    HostVideoReport {
+        report: ReceiverReport,
+    },
+    /// The host cannot decode the student's camera until a key frame.
+    HostKeyframeRequest {
+        stream: VideoStream,
     },
     OnCummunication {
         message: bool
@@ -74,11 +134,44 @@ pub enum ClientMessage {
         message: bool
     },
     ClientToClient {
-        message: String,
-        area_kind: AreaKind,
+        revision: u64,
+        operation: TextOperation,
+        document: DocumentKey,
+    },
+    ClientWorkspace {
+        action: WorkspaceAction,
+    },
+    ClientSubmit {
+        assignment_id: String,
+        workspace: Workspace,
     },
     ClientSwitchArea {
         message: AreaKind,
+    },
+    ClientCursor {
+        message: RemoteCursor,
+    },
+    ClientReviewReply {
+        thread_id: String,
+        message: ReviewMessage,
+    },
+    ClientBoard {
+        page_id: String,
+        operation: BoardOperation,
+    },
+    ClientAnnotation {
+        operation: BoardOperation,
+    },
+    /// How the host's camera or screen arrives at the student.
+    ClientVideoReport {
+        report: ReceiverReport,
+    },
+    /// The student cannot decode the host's camera or screen until a key frame.
+    ClientKeyframeRequest {
+        stream: VideoStream,
+    },
+    ClientLaserSignal {
+        signal: LaserSignal,
     }
 }
 
@@ -89,5 +182,13 @@ pub enum ManyMassage {
     },
     Video {
         packet: VideoPacket
+    },
+    /// How the sender's camera arrives at this peer.
+    VideoReport {
+        report: ReceiverReport,
+    },
+    /// This peer cannot decode the sender's camera until a key frame.
+    KeyframeRequest {
+        stream: VideoStream,
     }
 }
\ No newline at end of file
@@ -0,0 +1,171 @@
+use std::{cell::RefCell, rc::Rc};
+
+use js_sys::{Array, Reflect};
+use wasm_bindgen::{prelude::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{MessageEvent, RtcConfiguration, RtcDataChannel, RtcDataChannelEvent, RtcDataChannelInit, RtcDataChannelState, RtcIceCandidateInit, RtcIceServer, RtcPeerConnection, RtcPeerConnectionIceEvent, RtcSdpType, RtcSessionDescriptionInit};
+
+use crate::models::laser::{LaserSignal, LaserUpdate};
+
+/// Data channel for the laser pointer that is neither ordered nor
+/// retransmitted, a lost update is replaced by the next one anyway.
+/// wasm-peers only opens reliable channels, so this one gets a peer
+/// connection of its own, set up with `LaserSignal`s sent over the lesson.
+#[derive(Clone, PartialEq)]
+pub struct LaserChannel {
+    connection: RtcPeerConnection,
+    channel: Rc<RefCell<Option<RtcDataChannel>>>,
+}
+
+impl LaserChannel {
+    /// On the host side, opens the channel to a student and sends the offer.
+    pub fn offer(on_signal: impl Fn(LaserSignal) + 'static) -> Result<Self, JsValue> {
+        let on_signal: Rc<dyn Fn(LaserSignal)> = Rc::new(on_signal);
+        let connection = create_connection(on_signal.clone())?;
+        let mut init = RtcDataChannelInit::new();
+        init.ordered(false).max_retransmits(0);
+        let channel = connection.create_data_channel_with_data_channel_dict("laser", &init);
+        let laser = Self {
+            connection: connection.clone(),
+            channel: Rc::new(RefCell::new(Some(channel))),
+        };
+        spawn_local(async move {
+            if let Err(err) = send_offer(&connection, on_signal.as_ref()).await {
+                log::error!("cannot offer the laser channel: {:?}", err);
+            }
+        });
+        Ok(laser)
+    }
+
+    /// On the student side, answers the host's offer. Updates arriving on
+    /// the channel go to `on_update`.
+    pub fn answer(
+        sdp: String,
+        on_signal: impl Fn(LaserSignal) + 'static,
+        on_update: impl Fn(LaserUpdate) + 'static,
+    ) -> Result<Self, JsValue> {
+        let on_signal: Rc<dyn Fn(LaserSignal)> = Rc::new(on_signal);
+        let connection = create_connection(on_signal.clone())?;
+        let channel = Rc::new(RefCell::new(None));
+        let on_message = Closure::<dyn Fn(MessageEvent)>::new(move |event: MessageEvent| {
+            match event.data().as_string().and_then(|data| serde_json::from_str(&data).ok()) {
+                Some(update) => on_update(update),
+                None => log::error!("cannot read laser update"),
+            }
+        });
+        let on_channel = {
+            let channel = channel.clone();
+            Closure::<dyn Fn(RtcDataChannelEvent)>::new(move |event: RtcDataChannelEvent| {
+                let data_channel = event.channel();
+                data_channel.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+                *channel.borrow_mut() = Some(data_channel);
+            })
+        };
+        connection.set_ondatachannel(Some(on_channel.as_ref().unchecked_ref()));
+        on_channel.forget();
+        let laser = Self {
+            connection: connection.clone(),
+            channel,
+        };
+        spawn_local(async move {
+            if let Err(err) = send_answer(&connection, &sdp, on_signal.as_ref()).await {
+                log::error!("cannot answer the laser channel: {:?}", err);
+            }
+        });
+        Ok(laser)
+    }
+
+    /// Takes the answer or an ICE candidate of the other side.
+    pub fn signal(&self, signal: LaserSignal) {
+        let connection = self.connection.clone();
+        spawn_local(async move {
+            let result = match signal {
+                LaserSignal::Offer { .. } => Err(JsValue::from_str("the channel is already offered")),
+                LaserSignal::Answer { sdp } => {
+                    let mut description = RtcSessionDescriptionInit::new(RtcSdpType::Answer);
+                    description.sdp(&sdp);
+                    JsFuture::from(connection.set_remote_description(&description)).await
+                }
+                LaserSignal::Candidate { candidate, sdp_mid, sdp_m_line_index } => {
+                    let mut init = RtcIceCandidateInit::new(&candidate);
+                    init.sdp_mid(sdp_mid.as_deref()).sdp_m_line_index(sdp_m_line_index);
+                    JsFuture::from(connection.add_ice_candidate_with_opt_rtc_ice_candidate_init(Some(&init))).await
+                }
+            };
+            if let Err(err) = result {
+                log::error!("cannot apply laser signal: {:?}", err);
+            }
+        });
+    }
+
+    /// Sends if the channel is open, an update sent before is simply lost.
+    pub fn send(&self, message: &str) {
+        if let Some(channel) = self.channel.borrow().as_ref() {
+            if channel.ready_state() == RtcDataChannelState::Open {
+                let _ = channel.send_with_str(message);
+            }
+        }
+    }
+
+    pub fn close(&self) {
+        self.connection.close();
+    }
+}
+
+// The same servers as the lesson connection.
+fn create_connection(on_signal: Rc<dyn Fn(LaserSignal)>) -> Result<RtcPeerConnection, JsValue> {
+    let mut stun = RtcIceServer::new();
+    stun.urls(&urls(env!("STUN_SERVER_URLS")));
+    let mut turn = RtcIceServer::new();
+    turn.urls(&urls(env!("TURN_SERVER_URLS")))
+        .username(env!("TURN_SERVER_USERNAME"))
+        .credential(env!("TURN_SERVER_CREDENTIAL"));
+    let mut configuration = RtcConfiguration::new();
+    configuration.ice_servers(&Array::of2(&stun, &turn));
+    let connection = RtcPeerConnection::new_with_configuration(&configuration)?;
+    let on_candidate = Closure::<dyn Fn(RtcPeerConnectionIceEvent)>::new(move |event: RtcPeerConnectionIceEvent| {
+        if let Some(candidate) = event.candidate() {
+            on_signal(LaserSignal::Candidate {
+                candidate: candidate.candidate(),
+                sdp_mid: candidate.sdp_mid(),
+                sdp_m_line_index: candidate.sdp_m_line_index(),
+            });
+        }
+    });
+    connection.set_onicecandidate(Some(on_candidate.as_ref().unchecked_ref()));
+    on_candidate.forget();
+    Ok(connection)
+}
+
+fn urls(urls: &str) -> Array {
+    urls.split(',').map(|url| JsValue::from_str(url.trim())).collect()
+}
+
+async fn send_offer(connection: &RtcPeerConnection, on_signal: &dyn Fn(LaserSignal)) -> Result<(), JsValue> {
+    let offer = JsFuture::from(connection.create_offer()).await?;
+    let sdp = read_sdp(&offer)?;
+    let mut description = RtcSessionDescriptionInit::new(RtcSdpType::Offer);
+    description.sdp(&sdp);
+    JsFuture::from(connection.set_local_description(&description)).await?;
+    on_signal(LaserSignal::Offer { sdp });
+    Ok(())
+}
+
+async fn send_answer(connection: &RtcPeerConnection, offer: &str, on_signal: &dyn Fn(LaserSignal)) -> Result<(), JsValue> {
+    let mut description = RtcSessionDescriptionInit::new(RtcSdpType::Offer);
+    description.sdp(offer);
+    JsFuture::from(connection.set_remote_description(&description)).await?;
+    let answer = JsFuture::from(connection.create_answer()).await?;
+    let sdp = read_sdp(&answer)?;
+    let mut description = RtcSessionDescriptionInit::new(RtcSdpType::Answer);
+    description.sdp(&sdp);
+    JsFuture::from(connection.set_local_description(&description)).await?;
+    on_signal(LaserSignal::Answer { sdp });
+    Ok(())
+}
+
+fn read_sdp(description: &JsValue) -> Result<String, JsValue> {
+    Reflect::get(description, &JsValue::from_str("sdp"))?
+        .as_string()
+        .ok_or_else(|| JsValue::from_str("description without sdp"))
+}
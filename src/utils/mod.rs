@@ -1,4 +1,5 @@
 pub mod config;
 pub mod dom;
 pub mod inputs;
-pub mod device;
\ No newline at end of file
+pub mod device;
+pub mod laser_channel;
\ No newline at end of file